@@ -0,0 +1,25 @@
+// Embed the git commit the binary was built from, for `interminai version`.
+// Builds outside a git checkout (e.g. from a release tarball) leave it unset.
+
+use std::path::Path;
+use std::process::Command;
+
+fn main() {
+    for path in [".git/HEAD", ".git/refs", ".git/packed-refs"] {
+        if Path::new(path).exists() {
+            println!("cargo:rerun-if-changed={}", path);
+        }
+    }
+
+    let commit = Command::new("git")
+        .args(["rev-parse", "--short=12", "HEAD"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|commit| commit.trim().to_string())
+        .filter(|commit| !commit.is_empty());
+    if let Some(commit) = commit {
+        println!("cargo:rustc-env=INTERMINAI_GIT_COMMIT={}", commit);
+    }
+}
@@ -2,15 +2,19 @@
 //
 // This module provides a terminal emulator implementation using alacritty_terminal.
 
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::{Arc, Mutex};
 use alacritty_terminal::event::{Event, EventListener};
-use alacritty_terminal::term::{Config, Term};
+use alacritty_terminal::term::{Config, Term, TermMode};
 use alacritty_terminal::term::cell::Flags;
 use alacritty_terminal::grid::Dimensions;
-use alacritty_terminal::vte::ansi::{self, Color, NamedColor};
+use alacritty_terminal::vte::ansi::{self, Color, Handler, NamedColor};
 use alacritty_terminal::index::{Column, Line};
 
-use crate::terminal::{TerminalEmulator, UnhandledSequence};
+use crate::terminal::{decrqss_response, window_size_report, xtversion_response, CursorShape, CursorStyle, DebugBuffer, EscapeTracker, Hyperlink, MouseTracking, TerminalEmulator, UnhandledSequence};
+
+/// Max bytes leading up to a parser panic kept in the debug buffer
+const PANIC_CONTEXT_LIMIT: usize = 64;
 
 /// Display-related flags that affect ANSI output (excludes internal flags like WRAPLINE)
 fn display_flags(flags: Flags) -> Flags {
@@ -38,23 +42,120 @@ impl Dimensions for TermDimensions {
     }
 }
 
-/// Event listener that captures PtyWrite events for responses
+/// Replies queued for the PTY, shared between the listener and the terminal
+type PendingResponses = Arc<Mutex<Vec<Vec<u8>>>>;
+
+/// Event listener that captures PtyWrite events for responses and counts bells
 pub struct ResponseCapturingListener {
-    responses: Arc<Mutex<Vec<Vec<u8>>>>,
+    responses: PendingResponses,
+    bells: Arc<AtomicUsize>,
 }
 
 impl ResponseCapturingListener {
-    fn new() -> (Self, Arc<Mutex<Vec<Vec<u8>>>>) {
+    fn new() -> (Self, PendingResponses, Arc<AtomicUsize>) {
         let responses = Arc::new(Mutex::new(Vec::new()));
-        (Self { responses: responses.clone() }, responses)
+        let bells = Arc::new(AtomicUsize::new(0));
+        (Self { responses: responses.clone(), bells: bells.clone() }, responses, bells)
     }
 }
 
 impl EventListener for ResponseCapturingListener {
     fn send_event(&self, event: Event) {
-        if let Event::PtyWrite(data) = event {
+        match event {
+            Event::PtyWrite(data) => {
+                if let Ok(mut responses) = self.responses.lock() {
+                    responses.push(data.into_bytes());
+                }
+            }
+            Event::Bell => {
+                self.bells.fetch_add(1, Ordering::Relaxed);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Watches the byte stream for queries alacritty_terminal doesn't answer
+/// and queues replies alongside the ones captured from PtyWrite events
+struct QuerySniffer {
+    responses: PendingResponses,
+    /// DECRQSS payload being collected
+    decrqss: Option<Vec<u8>>,
+    /// Finished DECRQSS query; answered from terminal state by the caller
+    decrqss_query: Option<Vec<u8>>,
+    /// DECDSR (`CSI ? Ps n`) report requested; answered by the caller
+    dec_dsr_query: Option<u16>,
+    /// XTWINOPS size report (`CSI Ps t`) requested; answered by the caller
+    window_query: Option<u16>,
+    /// A character was printed; lets the caller spot wraps at the right margin
+    printed: bool,
+    /// G1 is invoked (SO) rather than G0 (SI)
+    shifted_out: bool,
+    /// SS2 / SS3 seen: alacritty_terminal ignores them, so the caller
+    /// invokes G2 / G3 for the next character
+    single_shift: Option<ansi::CharsetIndex>,
+}
+
+impl vte::Perform for QuerySniffer {
+    fn print(&mut self, _c: char) {
+        self.printed = true;
+    }
+
+    fn execute(&mut self, byte: u8) {
+        match byte {
+            0x0e => self.shifted_out = true,
+            0x0f => self.shifted_out = false,
+            _ => {}
+        }
+    }
+
+    fn esc_dispatch(&mut self, intermediates: &[u8], _ignore: bool, byte: u8) {
+        match (byte, intermediates) {
+            (b'N', []) => self.single_shift = Some(ansi::CharsetIndex::G2),
+            (b'O', []) => self.single_shift = Some(ansi::CharsetIndex::G3),
+            // RIS: alacritty_terminal goes back to G0
+            (b'c', []) => {
+                self.shifted_out = false;
+                self.single_shift = None;
+            }
+            _ => {}
+        }
+    }
+
+    fn hook(&mut self, _params: &vte::Params, intermediates: &[u8], _ignore: bool, action: char) {
+        if action == 'q' && intermediates == [b'$'] {
+            self.decrqss = Some(Vec::new());
+        }
+    }
+
+    fn put(&mut self, byte: u8) {
+        if let Some(payload) = self.decrqss.as_mut() {
+            // Settings are a few bytes long; anything bigger isn't a valid query
+            if payload.len() < 16 {
+                payload.push(byte);
+            }
+        }
+    }
+
+    fn unhook(&mut self) {
+        self.decrqss_query = self.decrqss.take();
+    }
+
+    fn csi_dispatch(&mut self, params: &vte::Params, intermediates: &[u8], _ignore: bool, action: char) {
+        if action == 'q' && intermediates == [b'>'] {
+            // XTVERSION
             if let Ok(mut responses) = self.responses.lock() {
-                responses.push(data.into_bytes());
+                responses.push(xtversion_response());
+            }
+        }
+        if action == 'n' && intermediates == [b'?'] {
+            self.dec_dsr_query = params.iter().next().and_then(|p| p.first()).copied();
+        }
+        if action == 't' && intermediates.is_empty() {
+            match params.iter().next().and_then(|p| p.first()).copied() {
+                // alacritty_terminal answers 18 itself and asks the window for 14
+                Some(18) => {}
+                op => self.window_query = op,
             }
         }
     }
@@ -64,33 +165,98 @@ impl EventListener for ResponseCapturingListener {
 pub struct AlacrittyTerminal {
     term: Term<ResponseCapturingListener>,
     parser: ansi::Processor,
-    responses: Arc<Mutex<Vec<Vec<u8>>>>,
+    responses: PendingResponses,
+    bells: Arc<AtomicUsize>,
+    sniffer: QuerySniffer,
+    sniffer_parser: vte::Parser,
+    escape: EscapeTracker,
     rows: usize,
     cols: usize,
     scrollback_cap: usize,
+    /// Bumped by process_bytes, resize and clear
+    generation: u64,
+    /// Input that made the parser panic
+    debug_buffer: DebugBuffer,
+    /// Wraps at the right margin since last read
+    wrapped_lines: usize,
+    /// Characters overwritten at the right margin with autowrap off, since last read
+    margin_truncated: bool,
+    /// G2 or G3 is invoked for one character by SS2 / SS3
+    single_shifted: bool,
 }
 
 impl AlacrittyTerminal {
     pub fn new(rows: usize, cols: usize, scrollback: usize) -> Self {
-        let mut config = Config::default();
-        config.scrolling_history = scrollback;
+        let config = Config {
+            scrolling_history: scrollback,
+            ..Config::default()
+        };
         let scrollback_cap = scrollback;
         let dimensions = TermDimensions {
             columns: cols,
             screen_lines: rows,
         };
 
-        let (listener, responses) = ResponseCapturingListener::new();
+        let (listener, responses, bells) = ResponseCapturingListener::new();
         let term = Term::new(config, &dimensions, listener);
         let parser = ansi::Processor::new();
 
+        let sniffer = QuerySniffer {
+            responses: responses.clone(),
+            decrqss: None,
+            decrqss_query: None,
+            dec_dsr_query: None,
+            window_query: None,
+            printed: false,
+            shifted_out: false,
+            single_shift: None,
+        };
+
         AlacrittyTerminal {
             term,
             parser,
             responses,
+            bells,
+            sniffer,
+            sniffer_parser: vte::Parser::new(),
+            escape: EscapeTracker::default(),
             rows,
             cols,
             scrollback_cap,
+            generation: 0,
+            debug_buffer: DebugBuffer::new(10),
+            wrapped_lines: 0,
+            margin_truncated: false,
+            single_shifted: false,
+        }
+    }
+
+    /// Answer a DECRQSS query from the current terminal state
+    fn decrqss_reply(&self, query: &[u8]) -> Vec<u8> {
+        match query {
+            b"m" => {
+                // Current SGR attributes, as used for newly printed text
+                let template = &self.term.grid().cursor.template;
+                let sgr = build_sgr_sequence(&template.fg, &template.bg, template.flags);
+                // Drop the leading CSI: the reply carries just "Ps;...m"
+                decrqss_response(Some(&sgr[2..]))
+            }
+            b" q" => decrqss_response(Some(&format!("{} q", self.cursor_style().decscusr()))),
+            _ => decrqss_response(None),
+        }
+    }
+
+    /// Reply to DECDSR `CSI ? mode n`, for the reports we know
+    fn dec_dsr_reply(&self, mode: u16) -> Option<Vec<u8>> {
+        match mode {
+            6 => {
+                // DECXCPR: the DEC form of the cursor position report
+                let cursor = self.term.grid().cursor.point;
+                Some(format!("\x1b[?{};{}R", cursor.line.0 + 1, cursor.column.0 + 1).into_bytes())
+            }
+            // Printer status: no printer
+            15 => Some(b"\x1b[?13n".to_vec()),
+            _ => None,
         }
     }
 }
@@ -233,26 +399,70 @@ fn trim_end_preserve_ansi(s: &str) -> &str {
 
 impl TerminalEmulator for AlacrittyTerminal {
     fn process_bytes(&mut self, bytes: &[u8]) {
-        self.parser.advance(&mut self.term, bytes);
+        self.generation += 1;
+        // Feed both parsers byte by byte so replies stay in query order
+        for (i, byte) in bytes.iter().enumerate() {
+            // A character printed while the cursor waits at the right margin
+            // either wraps or, with autowrap off, replaces the last column
+            let needs_wrap = self.term.grid().cursor.input_needs_wrap;
+            // A parser bug must not take the whole daemon down with it
+            let (parser, term) = (&mut self.parser, &mut self.term);
+            let parsed = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+                parser.advance(term, std::slice::from_ref(byte));
+            }));
+            if let Err(panic) = parsed {
+                // Drop the half-parsed sequence and carry on with the next byte
+                self.parser = ansi::Processor::new();
+                self.escape = EscapeTracker::default();
+                let message = panic.downcast_ref::<&str>().copied()
+                    .or_else(|| panic.downcast_ref::<String>().map(String::as_str))
+                    .unwrap_or("unknown panic");
+                let context = &bytes[(i + 1).saturating_sub(PANIC_CONTEXT_LIMIT)..=i];
+                self.debug_buffer.push(format!("parser panic: {}", message), context, None);
+            } else {
+                self.escape.advance(*byte);
+            }
+            self.sniffer_parser.advance(&mut self.sniffer, *byte);
+            let printed = std::mem::take(&mut self.sniffer.printed);
+            if printed && std::mem::take(&mut self.single_shifted) {
+                // Back to the set SI / SO invoked
+                let locking = if self.sniffer.shifted_out { ansi::CharsetIndex::G1 } else { ansi::CharsetIndex::G0 };
+                self.term.set_active_charset(locking);
+            }
+            if let Some(index) = self.sniffer.single_shift.take() {
+                self.term.set_active_charset(index);
+                self.single_shifted = true;
+            }
+            if printed && needs_wrap {
+                if self.term.mode().contains(TermMode::LINE_WRAP) {
+                    self.wrapped_lines += 1;
+                } else {
+                    self.margin_truncated = true;
+                }
+            }
+            if let Some(query) = self.sniffer.decrqss_query.take() {
+                let reply = self.decrqss_reply(&query);
+                if let Ok(mut responses) = self.responses.lock() {
+                    responses.push(reply);
+                }
+            }
+            if let Some(reply) = self.sniffer.dec_dsr_query.take().and_then(|mode| self.dec_dsr_reply(mode)) {
+                if let Ok(mut responses) = self.responses.lock() {
+                    responses.push(reply);
+                }
+            }
+            if let Some(reply) = self.sniffer.window_query.take().and_then(|op| window_size_report(op, self.rows, self.cols)) {
+                if let Ok(mut responses) = self.responses.lock() {
+                    responses.push(reply);
+                }
+            }
+        }
     }
 
     fn get_screen_content(&self) -> String {
-        let grid = self.term.grid();
         let mut result = String::new();
 
-        for line_idx in 0..grid.screen_lines() {
-            let line = &grid[Line(line_idx as i32)];
-            let line_str: String = (0..grid.columns())
-                .filter_map(|col| {
-                    let cell = &line[Column(col)];
-                    // Skip wide char spacer cells (placeholder after wide char)
-                    if cell.flags.contains(Flags::WIDE_CHAR_SPACER) {
-                        None
-                    } else {
-                        Some(cell.c)
-                    }
-                })
-                .collect();
+        for line_str in self.get_screen_rows() {
             result.push_str(line_str.trim_end());
             result.push('\n');
         }
@@ -260,6 +470,27 @@ impl TerminalEmulator for AlacrittyTerminal {
         result
     }
 
+    fn get_screen_rows(&self) -> Vec<String> {
+        let grid = self.term.grid();
+
+        (0..grid.screen_lines())
+            .map(|line_idx| {
+                let line = &grid[Line(line_idx as i32)];
+                (0..grid.columns())
+                    .filter_map(|col| {
+                        let cell = &line[Column(col)];
+                        // Skip wide char spacer cells (placeholder after wide char)
+                        if cell.flags.contains(Flags::WIDE_CHAR_SPACER) {
+                            None
+                        } else {
+                            Some(cell.c)
+                        }
+                    })
+                    .collect()
+            })
+            .collect()
+    }
+
     fn get_screen_content_ansi(&self) -> String {
         let grid = self.term.grid();
         let mut result = String::new();
@@ -326,7 +557,12 @@ impl TerminalEmulator for AlacrittyTerminal {
         (self.rows, self.cols)
     }
 
+    fn generation(&self) -> u64 {
+        self.generation
+    }
+
     fn resize(&mut self, rows: usize, cols: usize) {
+        self.generation += 1;
         let dimensions = TermDimensions {
             columns: cols,
             screen_lines: rows,
@@ -336,6 +572,12 @@ impl TerminalEmulator for AlacrittyTerminal {
         self.cols = cols;
     }
 
+    fn clear(&mut self) {
+        self.generation += 1;
+        // Home, erase display, then erase scrollback (ED 2 pushes the screen into history)
+        self.parser.advance(&mut self.term, b"\x1b[H\x1b[2J\x1b[3J");
+    }
+
     fn take_pending_responses(&mut self) -> Vec<Vec<u8>> {
         if let Ok(mut responses) = self.responses.lock() {
             std::mem::take(&mut *responses)
@@ -353,6 +595,17 @@ impl TerminalEmulator for AlacrittyTerminal {
         grid.total_lines().saturating_sub(grid.screen_lines())
     }
 
+    fn truncate_scrollback(&mut self, max_lines: usize) -> usize {
+        let excess = self.scrollback_lines().saturating_sub(max_lines);
+        if excess > 0 {
+            // Shrinking the history limit drops the oldest lines; then restore it
+            let grid = self.term.grid_mut();
+            grid.update_history(max_lines);
+            grid.update_history(self.scrollback_cap);
+        }
+        excess
+    }
+
     fn get_scrollback_content(&self, lines: usize) -> String {
         let grid = self.term.grid();
         let history = grid.total_lines().saturating_sub(grid.screen_lines());
@@ -437,16 +690,124 @@ impl TerminalEmulator for AlacrittyTerminal {
         result
     }
 
+    fn bracketed_paste(&self) -> bool {
+        self.term.mode().contains(TermMode::BRACKETED_PASTE)
+    }
+
+    fn focus_reporting(&self) -> bool {
+        self.term.mode().contains(TermMode::FOCUS_IN_OUT)
+    }
+
+    fn application_cursor_keys(&self) -> bool {
+        self.term.mode().contains(TermMode::APP_CURSOR)
+    }
+
+    fn application_keypad(&self) -> bool {
+        self.term.mode().contains(TermMode::APP_KEYPAD)
+    }
+
+    fn alt_screen(&self) -> bool {
+        self.term.mode().contains(TermMode::ALT_SCREEN)
+    }
+
+    fn autowrap(&self) -> bool {
+        self.term.mode().contains(TermMode::LINE_WRAP)
+    }
+
+    fn origin_mode(&self) -> bool {
+        self.term.mode().contains(TermMode::ORIGIN)
+    }
+
+    fn parser_in_escape(&self) -> bool {
+        self.escape.in_escape()
+    }
+
+    fn cursor_style(&self) -> CursorStyle {
+        let style = self.term.cursor_style();
+        let shape = match style.shape {
+            ansi::CursorShape::Underline => CursorShape::Underline,
+            ansi::CursorShape::Beam => CursorShape::Bar,
+            _ => CursorShape::Block,
+        };
+        CursorStyle { shape, blinking: style.blinking }
+    }
+
+    fn mouse_tracking(&self) -> MouseTracking {
+        // Setting one of these clears the others
+        let mode = self.term.mode();
+        if mode.contains(TermMode::MOUSE_MOTION) {
+            MouseTracking::Motion
+        } else if mode.contains(TermMode::MOUSE_DRAG) {
+            MouseTracking::Drag
+        } else if mode.contains(TermMode::MOUSE_REPORT_CLICK) {
+            MouseTracking::Click
+        } else {
+            MouseTracking::Off
+        }
+    }
+
+    fn sgr_mouse(&self) -> bool {
+        self.term.mode().contains(TermMode::SGR_MOUSE)
+    }
+
+    fn reflows_on_resize(&self) -> bool {
+        // Term::resize rewraps the primary screen (not the alternate one)
+        true
+    }
+
+    fn hyperlinks(&self) -> Vec<Hyperlink> {
+        let grid = self.term.grid();
+        let mut links = Vec::new();
+
+        for row in 0..grid.screen_lines() {
+            let line = &grid[Line(row as i32)];
+            // Adjacent cells with the same link form one run
+            let mut run: Option<(alacritty_terminal::term::cell::Hyperlink, Hyperlink)> = None;
+            for col in 0..grid.columns() {
+                let cell = &line[Column(col)];
+                if cell.flags.contains(Flags::WIDE_CHAR_SPACER) {
+                    continue;
+                }
+                let link = cell.hyperlink();
+                match (&mut run, link) {
+                    (Some((current, found)), Some(link)) if *current == link => found.text.push(cell.c),
+                    (_, link) => {
+                        links.extend(run.take().map(|(_, found)| found));
+                        run = link.map(|link| {
+                            let found = Hyperlink { url: link.uri().to_string(), text: cell.c.to_string(), row, col };
+                            (link, found)
+                        });
+                    }
+                }
+            }
+            links.extend(run.map(|(_, found)| found));
+        }
+
+        links
+    }
+
+    fn take_bell_count(&mut self) -> usize {
+        self.bells.swap(0, Ordering::Relaxed)
+    }
+
+    fn take_wrapped_lines(&mut self) -> usize {
+        std::mem::take(&mut self.wrapped_lines)
+    }
+
+    fn take_margin_truncated(&mut self) -> bool {
+        std::mem::take(&mut self.margin_truncated)
+    }
+
     fn get_debug_entries(&self) -> Vec<UnhandledSequence> {
-        // alacritty_terminal handles most sequences, so we don't track unhandled ones
-        Vec::new()
+        // alacritty_terminal handles most sequences, so only parser panics are tracked
+        self.debug_buffer.get_entries().to_vec()
     }
 
     fn clear_debug_buffer(&mut self) {
-        // No-op for alacritty backend
+        self.debug_buffer.clear();
     }
 
     fn get_debug_dropped(&self) -> usize {
-        0
+        self.debug_buffer.get_dropped()
     }
 }
@@ -5,47 +5,86 @@
 
 use std::collections::VecDeque;
 use vte::Perform;
-use crate::terminal::{TerminalEmulator, UnhandledSequence};
+use crate::terminal::{decrqss_response, window_size_report, xtversion_response, CursorStyle, DebugBuffer, EscapeTracker, Hyperlink, MouseTracking, TerminalEmulator, UnhandledSequence};
 
-/// Ring buffer for tracking unhandled escape sequences
-struct DebugBuffer {
-    entries: Vec<UnhandledSequence>,
-    capacity: usize,
-    dropped: usize,
-}
+/// Max DCS payload bytes kept; the rest (e.g. sixel image data) is discarded
+const DCS_PAYLOAD_LIMIT: usize = 256;
 
-impl DebugBuffer {
-    fn new(capacity: usize) -> Self {
-        DebugBuffer {
-            entries: Vec::with_capacity(capacity),
-            capacity,
-            dropped: 0,
-        }
-    }
+/// Max original bytes kept per sequence by raw capture
+const RAW_CAPTURE_LIMIT: usize = 512;
 
-    fn push(&mut self, sequence: String, raw_bytes: &[u8]) {
-        let raw_hex = raw_bytes.iter().map(|b| format!("{:02x}", b)).collect();
-        let entry = UnhandledSequence { sequence, raw_hex };
+/// Max hyperlink runs remembered; the oldest are forgotten first
+const HYPERLINK_LIMIT: usize = 256;
 
-        if self.entries.len() >= self.capacity {
-            self.entries.remove(0);
-            self.dropped += 1;
-        }
-        self.entries.push(entry);
-    }
+/// DCS string collected between hook and unhook
+struct DcsString {
+    params: Vec<String>,
+    intermediates: Vec<u8>,
+    action: char,
+    payload: Vec<u8>,
+}
 
-    fn clear(&mut self) {
-        self.entries.clear();
-        self.dropped = 0;
-    }
+/// Character set designated into one of G0-G3
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Charset {
+    /// US ASCII (`ESC ( B`)
+    Ascii,
+    /// DEC special graphics (`ESC ( 0`): box drawing and a few symbols
+    LineDrawing,
+}
 
-    fn get_entries(&self) -> &[UnhandledSequence] {
-        &self.entries
+impl Charset {
+    fn map(self, c: char) -> char {
+        if self == Charset::Ascii {
+            return c;
+        }
+        // Same mapping as alacritty_terminal, so both backends agree
+        match c {
+            '_' => ' ',
+            '`' => '◆',
+            'a' => '▒',
+            'b' => '\u{2409}',
+            'c' => '\u{240c}',
+            'd' => '\u{240d}',
+            'e' => '\u{240a}',
+            'f' => '°',
+            'g' => '±',
+            'h' => '\u{2424}',
+            'i' => '\u{240b}',
+            'j' => '┘',
+            'k' => '┐',
+            'l' => '┌',
+            'm' => '└',
+            'n' => '┼',
+            'o' => '⎺',
+            'p' => '⎻',
+            'q' => '─',
+            'r' => '⎼',
+            's' => '⎽',
+            't' => '├',
+            'u' => '┤',
+            'v' => '┴',
+            'w' => '┬',
+            'x' => '│',
+            'y' => '≤',
+            'z' => '≥',
+            '{' => 'π',
+            '|' => '≠',
+            '}' => '£',
+            '~' => '·',
+            _ => c,
+        }
     }
+}
 
-    fn get_dropped(&self) -> usize {
-        self.dropped
-    }
+/// State saved by DECSC (ESC 7) or SCOSC (CSI s) and put back by DECRC / SCORC
+#[derive(Debug, Clone, Copy)]
+struct SavedCursor {
+    row: usize,
+    col: usize,
+    charsets: [Charset; 4],
+    active_charset: usize,
+    single_shift: Option<usize>,
 }
 
 /// Custom terminal screen buffer implementation
@@ -55,14 +94,76 @@ pub struct CustomScreen {
     cells: Vec<Vec<char>>,
     cursor_row: usize,
     cursor_col: usize,
-    last_char: char,
+    /// Last graphic character printed, repeated by REP (CSI b); None after a control character
+    last_char: Option<char>,
     debug_buffer: DebugBuffer,
     pending_responses: Vec<Vec<u8>>,
     parser: vte::Parser,
+    escape: EscapeTracker,
     /// Delayed wrap mode: when true, the next printable character will wrap to next line first
     pending_wrap: bool,
     scrollback: VecDeque<Vec<char>>,
     scrollback_capacity: usize,
+    /// DECCKM (?1): cursor keys send SS3 instead of CSI sequences
+    application_cursor_keys: bool,
+    /// DECKPAM (ESC =) / DECKPNM (ESC >): keypad sends SS3 sequences
+    application_keypad: bool,
+    /// DECAWM (?7): wrap at the right margin
+    autowrap: bool,
+    /// DECOM (?6): origin mode (tracked for DECRQM only, no scroll regions yet)
+    origin_mode: bool,
+    /// DECTCEM (?25): cursor visible
+    cursor_visible: bool,
+    /// DECSCUSR (CSI Ps SP q)
+    cursor_style: CursorStyle,
+    /// Focus in/out reporting (?1004)
+    focus_reporting: bool,
+    /// Bracketed paste (?2004)
+    bracketed_paste: bool,
+    /// Mouse click (?1000), drag (?1002) and motion (?1003) reporting; at most one is set
+    mouse_click: bool,
+    mouse_drag: bool,
+    mouse_motion: bool,
+    /// SGR mouse encoding (?1006)
+    sgr_mouse: bool,
+    /// Alternate screen (?47, ?1047, ?1049): tracked for status only, the
+    /// program draws on the one screen buffer either way
+    alt_screen: bool,
+    /// BEL characters received since last read
+    bell_count: usize,
+    /// Wraps at the right margin since last read
+    wrapped_lines: usize,
+    /// Characters overwritten at the right margin with autowrap off, since last read
+    margin_truncated: bool,
+    /// DCS string in progress
+    dcs: Option<DcsString>,
+    /// Cursor position and character sets saved by ESC 7 or CSI s,
+    /// restored by ESC 8 or CSI u
+    saved_cursor: Option<SavedCursor>,
+    /// Columns with a tab stop: every 8 columns until changed by HTS / TBC
+    tab_stops: Vec<bool>,
+    /// Bytes of the current escape sequence as received; None unless raw capture is on
+    raw_capture: Option<Vec<u8>>,
+    /// Bumped by process_bytes, resize and clear
+    generation: u64,
+    /// URL of the OSC 8 hyperlink being printed, if any
+    current_link: Option<String>,
+    /// Runs of text printed while a hyperlink was active. Cells don't record
+    /// their link, so a run is only reported while its text is still there.
+    links: Vec<Hyperlink>,
+    /// The last entry of `links` can still be extended by the next character
+    link_run_open: bool,
+    /// Sets designated into G0-G3 by ESC ( ) * +
+    charsets: [Charset; 4],
+    /// Set printed characters come from: G0 after SI, G1 after SO
+    active_charset: usize,
+    /// G2 or G3, invoked by SS2 / SS3 for the next character only
+    single_shift: Option<usize>,
+}
+
+/// Tab stops for columns `from..to`: every 8 columns, as on a freshly reset terminal
+fn default_tab_stops(from: usize, to: usize) -> Vec<bool> {
+    (from..to).map(|col| col % 8 == 0).collect()
 }
 
 impl CustomScreen {
@@ -82,63 +183,297 @@ impl CustomScreen {
             cells: vec![vec![' '; cols]; rows],
             cursor_row: 0,
             cursor_col: 0,
-            last_char: ' ',
+            last_char: None,
             debug_buffer: DebugBuffer::new(debug_buffer_size),
             pending_responses: Vec::new(),
             parser: vte::Parser::new(),
+            escape: EscapeTracker::default(),
             pending_wrap: false,
-            scrollback: VecDeque::with_capacity(scrollback_capacity),
+            scrollback: VecDeque::new(),
             scrollback_capacity,
+            application_cursor_keys: false,
+            application_keypad: false,
+            autowrap: true,
+            origin_mode: false,
+            cursor_visible: true,
+            cursor_style: CursorStyle::default(),
+            focus_reporting: false,
+            bracketed_paste: false,
+            mouse_click: false,
+            mouse_drag: false,
+            mouse_motion: false,
+            sgr_mouse: false,
+            alt_screen: false,
+            bell_count: 0,
+            wrapped_lines: 0,
+            margin_truncated: false,
+            dcs: None,
+            saved_cursor: None,
+            tab_stops: default_tab_stops(0, cols),
+            raw_capture: None,
+            generation: 0,
+            current_link: None,
+            links: Vec::new(),
+            link_run_open: false,
+            charsets: [Charset::Ascii; 4],
+            active_charset: 0,
+            single_shift: None,
         }
     }
 
+    fn save_cursor(&mut self) {
+        self.saved_cursor = Some(SavedCursor {
+            row: self.cursor_row,
+            col: self.cursor_col,
+            charsets: self.charsets,
+            active_charset: self.active_charset,
+            single_shift: self.single_shift,
+        });
+    }
+
+    /// Restore what save_cursor saved, or go home with ASCII in G0-G3 if
+    /// nothing was saved
+    fn restore_cursor(&mut self) {
+        let saved = self.saved_cursor.unwrap_or(SavedCursor {
+            row: 0,
+            col: 0,
+            charsets: [Charset::Ascii; 4],
+            active_charset: 0,
+            single_shift: None,
+        });
+        self.charsets = saved.charsets;
+        self.active_charset = saved.active_charset;
+        self.single_shift = saved.single_shift;
+        self.move_cursor(saved.row, saved.col);
+    }
+
+    /// RIS: blank the screen and put the cursor, modes, tab stops and
+    /// character sets back as on a fresh terminal. Scrollback is kept
+    fn reset(&mut self) {
+        self.cells = vec![vec![' '; self.cols]; self.rows];
+        self.last_char = None;
+        self.application_cursor_keys = false;
+        self.application_keypad = false;
+        self.autowrap = true;
+        self.origin_mode = false;
+        self.cursor_visible = true;
+        self.cursor_style = CursorStyle::default();
+        self.focus_reporting = false;
+        self.bracketed_paste = false;
+        self.mouse_click = false;
+        self.mouse_drag = false;
+        self.mouse_motion = false;
+        self.sgr_mouse = false;
+        self.alt_screen = false;
+        self.saved_cursor = None;
+        self.tab_stops = default_tab_stops(0, self.cols);
+        self.current_link = None;
+        self.links.clear();
+        self.link_run_open = false;
+        self.charsets = [Charset::Ascii; 4];
+        self.active_charset = 0;
+        self.single_shift = None;
+        self.move_cursor(0, 0);
+    }
+
     /// Move cursor to specified row, canceling pending wrap
     fn move_cursor_row(&mut self, row: usize) {
         self.pending_wrap = false;
-        self.cursor_row = row.min(self.rows - 1);
+        self.cursor_row = row.min(self.rows.saturating_sub(1));
     }
 
     /// Move cursor to specified column, canceling pending wrap
     fn move_cursor_col(&mut self, col: usize) {
         self.pending_wrap = false;
-        self.cursor_col = col.min(self.cols - 1);
+        self.cursor_col = col.min(self.cols.saturating_sub(1));
     }
 
     /// Move cursor to specified position, canceling pending wrap
     fn move_cursor(&mut self, row: usize, col: usize) {
         self.pending_wrap = false;
-        self.cursor_row = row.min(self.rows - 1);
-        self.cursor_col = col.min(self.cols - 1);
+        self.cursor_row = row.min(self.rows.saturating_sub(1));
+        self.cursor_col = col.min(self.cols.saturating_sub(1));
+    }
+
+    /// Column just past the right margin: the full width, as left/right
+    /// margins (DECSLRM) aren't supported
+    fn right_margin(&self) -> usize {
+        self.cols
+    }
+
+    /// Column of the next tab stop after `col`, or the right margin if there is none
+    fn next_tab_stop(&self, col: usize) -> usize {
+        let last = self.right_margin().saturating_sub(1);
+        (col + 1..last).find(|&c| self.tab_stops[c]).unwrap_or(last)
+    }
+
+    /// Column of the previous tab stop before `col`, or the left margin if there is none
+    fn prev_tab_stop(&self, col: usize) -> usize {
+        (1..col).rev().find(|&c| self.tab_stops[c]).unwrap_or(0)
     }
 
     fn to_ascii(&self) -> String {
         let mut result = String::new();
         for row in &self.cells {
             let line: String = row.iter().collect();
-            result.push_str(&line.trim_end());
+            result.push_str(line.trim_end());
             result.push('\n');
         }
         result
     }
 
+    /// Get or set a tracked DEC private mode. Returns None for modes we don't track.
+    fn private_mode(&mut self, mode: u16, set: Option<bool>) -> Option<bool> {
+        // Mouse tracking modes replace each other, as in xterm
+        if set == Some(true) && matches!(mode, 1000 | 1002 | 1003) {
+            self.mouse_click = false;
+            self.mouse_drag = false;
+            self.mouse_motion = false;
+        }
+        let flag = match mode {
+            1 => &mut self.application_cursor_keys,
+            6 => &mut self.origin_mode,
+            7 => &mut self.autowrap,
+            25 => &mut self.cursor_visible,
+            47 | 1047 | 1049 => &mut self.alt_screen,
+            1000 => &mut self.mouse_click,
+            1002 => &mut self.mouse_drag,
+            1003 => &mut self.mouse_motion,
+            1004 => &mut self.focus_reporting,
+            1006 => &mut self.sgr_mouse,
+            2004 => &mut self.bracketed_paste,
+            _ => return None,
+        };
+        if let Some(value) = set {
+            *flag = value;
+        }
+        Some(*flag)
+    }
+
+    fn record_unhandled_csi(&mut self, params: &vte::Params, intermediates: &[u8], action: char) {
+        let mut seq = String::from("\\e[");
+        for intermediate in intermediates {
+            seq.push(*intermediate as char);
+        }
+        let param_strs: Vec<String> = params.iter()
+            .map(|p| p.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(":"))
+            .collect();
+        seq.push_str(&param_strs.join(";"));
+        seq.push(action);
+
+        let mut raw = vec![0x1b, b'['];
+        raw.extend_from_slice(intermediates);
+        for (i, p) in params.iter().enumerate() {
+            if i > 0 { raw.push(b';'); }
+            for (j, v) in p.iter().enumerate() {
+                if j > 0 { raw.push(b':'); }
+                raw.extend_from_slice(v.to_string().as_bytes());
+            }
+        }
+        raw.push(action as u8);
+
+        self.debug_buffer.push(seq, &raw, self.raw_capture.as_deref());
+    }
+
+    /// Answer a finished DCS string, or record it in the debug buffer
+    fn dispatch_dcs(&mut self, dcs: DcsString) {
+        if dcs.intermediates == [b'$'] && dcs.action == 'q' {
+            // DECRQSS: we don't track attributes, so report the default for SGR
+            let setting = match dcs.payload.as_slice() {
+                b"m" => Some("0m".to_string()),
+                b" q" => Some(format!("{} q", self.cursor_style.decscusr())),
+                _ => None,
+            };
+            self.pending_responses.push(decrqss_response(setting.as_deref()));
+            return;
+        }
+
+        // vte reports a lone default parameter as 0; don't show it
+        let params = if dcs.params == ["0"] { String::new() } else { dcs.params.join(";") };
+
+        let mut seq = String::from("dcs \\eP");
+        seq.push_str(&params);
+        for intermediate in &dcs.intermediates {
+            seq.push(*intermediate as char);
+        }
+        seq.push(dcs.action);
+        for &byte in dcs.payload.iter().take(32) {
+            if byte.is_ascii_graphic() || byte == b' ' {
+                seq.push(byte as char);
+            } else {
+                seq.push_str(&format!("\\x{:02x}", byte));
+            }
+        }
+        if dcs.payload.len() > 32 {
+            seq.push_str("...");
+        }
+        seq.push_str("\\e\\\\");
+
+        let mut raw = vec![0x1b, b'P'];
+        raw.extend_from_slice(params.as_bytes());
+        raw.extend_from_slice(&dcs.intermediates);
+        raw.push(dcs.action as u8);
+        raw.extend_from_slice(&dcs.payload);
+
+        // Captured up to the ESC of the string terminator, which isn't finished yet
+        let original = self.raw_capture.as_deref().map(|b| b.strip_suffix(&[0x1b]).unwrap_or(b));
+        self.debug_buffer.push(seq, &raw, original);
+    }
+
+    /// Record that `c` was printed at (row, col) while a hyperlink is active
+    fn record_link_char(&mut self, row: usize, col: usize, c: char) {
+        let Some(url) = self.current_link.as_ref() else { return };
+        if self.link_run_open {
+            if let Some(last) = self.links.last_mut() {
+                if last.row == row && last.col + last.text.chars().count() == col {
+                    last.text.push(c);
+                    return;
+                }
+            }
+        }
+        if self.links.len() >= HYPERLINK_LIMIT {
+            self.links.remove(0);
+        }
+        self.links.push(Hyperlink { url: url.clone(), text: c.to_string(), row, col });
+        self.link_run_open = true;
+    }
+
     fn scroll_up(&mut self) {
+        // Links move up with their rows; those on the top row go with it
+        self.links.retain(|link| link.row > 0);
+        for link in &mut self.links {
+            link.row -= 1;
+        }
         let row = self.cells.remove(0);
-        if self.scrollback.len() >= self.scrollback_capacity {
-            self.scrollback.pop_front();
+        if self.scrollback_capacity > 0 {
+            if self.scrollback.len() >= self.scrollback_capacity {
+                self.scrollback.pop_front();
+            }
+            self.scrollback.push_back(row);
         }
-        self.scrollback.push_back(row);
         self.cells.push(vec![' '; self.cols]);
     }
 }
 
 impl TerminalEmulator for CustomScreen {
     fn process_bytes(&mut self, bytes: &[u8]) {
+        self.generation += 1;
         for byte in bytes {
+            if let Some(current) = self.raw_capture.as_mut() {
+                // ESC starts a new sequence, except when it ends a DCS string
+                if *byte == 0x1b && self.dcs.is_none() {
+                    current.clear();
+                }
+                if (*byte == 0x1b || !current.is_empty()) && current.len() < RAW_CAPTURE_LIMIT {
+                    current.push(*byte);
+                }
+            }
             // We need to use a trick here since vte::Parser::advance takes &mut self
             // and we need to pass self as the Perform implementor
             let mut parser = std::mem::take(&mut self.parser);
             parser.advance(self, *byte);
             self.parser = parser;
+            self.escape.advance(*byte);
         }
     }
 
@@ -146,6 +481,10 @@ impl TerminalEmulator for CustomScreen {
         self.to_ascii()
     }
 
+    fn get_screen_rows(&self) -> Vec<String> {
+        self.cells.iter().map(|row| row.iter().collect()).collect()
+    }
+
     fn cursor_position(&self) -> (usize, usize) {
         (self.cursor_row, self.cursor_col)
     }
@@ -154,21 +493,45 @@ impl TerminalEmulator for CustomScreen {
         (self.rows, self.cols)
     }
 
+    fn generation(&self) -> u64 {
+        self.generation
+    }
+
     fn resize(&mut self, rows: usize, cols: usize) {
+        self.generation += 1;
+        // Every cursor position needs at least one cell to point to
+        let rows = rows.max(1);
+        let cols = cols.max(1);
         // TODO: maybe drop content copying, the app redraws via SIGWINCH anyway
         // Alternative: just create fresh screen:
         // self.cells = vec![vec![' '; cols]; rows];
         let mut new_cells = vec![vec![' '; cols]; rows];
-        for row in 0..self.rows.min(rows) {
-            for col in 0..self.cols.min(cols) {
-                new_cells[row][col] = self.cells[row][col];
+        for (new_row, old_row) in new_cells.iter_mut().zip(&self.cells) {
+            for (new_cell, old_cell) in new_row.iter_mut().zip(old_row) {
+                *new_cell = *old_cell;
             }
         }
         self.cells = new_cells;
+        // Keep the stops that still fit; new columns get the default stops
+        let old_cols = self.tab_stops.len().min(cols);
+        self.tab_stops.truncate(cols);
+        self.tab_stops.extend(default_tab_stops(old_cols, cols));
         self.rows = rows;
         self.cols = cols;
-        self.cursor_row = self.cursor_row.min(rows.saturating_sub(1));
-        self.cursor_col = self.cursor_col.min(cols.saturating_sub(1));
+        self.cursor_row = self.cursor_row.min(rows - 1);
+        self.cursor_col = self.cursor_col.min(cols - 1);
+        // A wrap pending at the old right margin doesn't apply to the new one
+        self.pending_wrap = false;
+    }
+
+    fn clear(&mut self) {
+        self.generation += 1;
+        self.cells = vec![vec![' '; self.cols]; self.rows];
+        self.scrollback.clear();
+        self.last_char = None;
+        self.links.clear();
+        self.link_run_open = false;
+        self.move_cursor(0, 0);
     }
 
     fn take_pending_responses(&mut self) -> Vec<Vec<u8>> {
@@ -183,6 +546,12 @@ impl TerminalEmulator for CustomScreen {
         self.scrollback_capacity
     }
 
+    fn truncate_scrollback(&mut self, max_lines: usize) -> usize {
+        let excess = self.scrollback.len().saturating_sub(max_lines);
+        self.scrollback.drain(..excess);
+        excess
+    }
+
     fn get_scrollback_content(&self, lines: usize) -> String {
         let n = lines.min(self.scrollback.len());
         if n == 0 {
@@ -198,6 +567,83 @@ impl TerminalEmulator for CustomScreen {
         result
     }
 
+    fn bracketed_paste(&self) -> bool {
+        self.bracketed_paste
+    }
+
+    fn focus_reporting(&self) -> bool {
+        self.focus_reporting
+    }
+
+    fn application_cursor_keys(&self) -> bool {
+        self.application_cursor_keys
+    }
+
+    fn application_keypad(&self) -> bool {
+        self.application_keypad
+    }
+
+    fn mouse_tracking(&self) -> MouseTracking {
+        if self.mouse_motion {
+            MouseTracking::Motion
+        } else if self.mouse_drag {
+            MouseTracking::Drag
+        } else if self.mouse_click {
+            MouseTracking::Click
+        } else {
+            MouseTracking::Off
+        }
+    }
+
+    fn sgr_mouse(&self) -> bool {
+        self.sgr_mouse
+    }
+
+    fn alt_screen(&self) -> bool {
+        self.alt_screen
+    }
+
+    fn autowrap(&self) -> bool {
+        self.autowrap
+    }
+
+    fn origin_mode(&self) -> bool {
+        self.origin_mode
+    }
+
+    fn parser_in_escape(&self) -> bool {
+        self.escape.in_escape()
+    }
+
+    fn cursor_style(&self) -> CursorStyle {
+        self.cursor_style
+    }
+
+    fn hyperlinks(&self) -> Vec<Hyperlink> {
+        let mut links: Vec<Hyperlink> = self.links.iter()
+            .filter(|link| {
+                let Some(row) = self.cells.get(link.row) else { return false };
+                let shown = row.iter().skip(link.col).take(link.text.chars().count());
+                shown.copied().eq(link.text.chars())
+            })
+            .cloned()
+            .collect();
+        links.sort_by_key(|link| (link.row, link.col));
+        links
+    }
+
+    fn take_bell_count(&mut self) -> usize {
+        std::mem::take(&mut self.bell_count)
+    }
+
+    fn take_wrapped_lines(&mut self) -> usize {
+        std::mem::take(&mut self.wrapped_lines)
+    }
+
+    fn take_margin_truncated(&mut self) -> bool {
+        std::mem::take(&mut self.margin_truncated)
+    }
+
     fn get_debug_entries(&self) -> Vec<UnhandledSequence> {
         self.debug_buffer.get_entries().to_vec()
     }
@@ -209,44 +655,68 @@ impl TerminalEmulator for CustomScreen {
     fn get_debug_dropped(&self) -> usize {
         self.debug_buffer.get_dropped()
     }
+
+    fn enable_raw_capture(&mut self) {
+        self.raw_capture.get_or_insert_with(Vec::new);
+    }
+}
+
+/// Get CSI parameter `idx`, treating an omitted or zero value as `default`.
+/// vte reports an empty field (e.g. the row in `CSI ;5H`) as 0, and a
+/// missing trailing field (the column in `CSI 5H`) as absent.
+fn csi_param(params: &vte::Params, idx: usize, default: u16) -> u16 {
+    match params.iter().nth(idx).and_then(|p| p.first()).copied() {
+        None | Some(0) => default,
+        Some(n) => n,
+    }
 }
 
 impl Perform for CustomScreen {
     fn print(&mut self, c: char) {
-        self.last_char = c;
+        let charset = self.single_shift.take().unwrap_or(self.active_charset);
+        let c = self.charsets[charset].map(c);
+        self.last_char = Some(c);
 
         // Handle delayed wrap: if pending_wrap is set, wrap now before printing
-        if self.pending_wrap {
+        if self.pending_wrap && !self.autowrap {
+            // With autowrap off the character replaces the one in the last column
+            self.pending_wrap = false;
+            self.margin_truncated = true;
+        } else if self.pending_wrap {
             self.pending_wrap = false;
+            self.wrapped_lines += 1;
             self.cursor_col = 0;
             self.cursor_row += 1;
             if self.cursor_row >= self.rows {
                 self.scroll_up();
-                self.cursor_row = self.rows - 1;
+                self.cursor_row = self.rows.saturating_sub(1);
             }
         }
 
         if self.cursor_row < self.rows && self.cursor_col < self.cols {
             self.cells[self.cursor_row][self.cursor_col] = c;
+            self.record_link_char(self.cursor_row, self.cursor_col, c);
             self.cursor_col += 1;
             // If we've reached the right edge, set pending_wrap instead of wrapping immediately
+            // With autowrap off, the cursor stays put and later chars overwrite the last column
             if self.cursor_col >= self.cols {
-                self.cursor_col = self.cols - 1;  // Keep cursor at last column
+                self.cursor_col = self.cols.saturating_sub(1);  // Keep cursor at last column
                 self.pending_wrap = true;
             }
         }
     }
 
     fn execute(&mut self, byte: u8) {
-        // Control characters cancel pending wrap
+        // Control characters cancel pending wrap, and REP has nothing to repeat after them
         self.pending_wrap = false;
+        self.last_char = None;
 
         match byte {
             b'\n' => {
                 self.cursor_row += 1;
                 if self.cursor_row >= self.rows {
                     self.scroll_up();
-                    self.cursor_row = self.rows - 1;
+                    self.cursor_row = self.rows.saturating_sub(1);
                 }
                 self.cursor_col = 0;
             }
@@ -254,41 +724,82 @@ impl Perform for CustomScreen {
                 self.cursor_col = 0;
             }
             b'\t' => {
-                self.cursor_col = ((self.cursor_col / 8) + 1) * 8;
-                if self.cursor_col >= self.cols {
-                    self.cursor_col = self.cols - 1;
-                }
+                self.cursor_col = self.next_tab_stop(self.cursor_col);
             }
-            b'\x08' => {
-                if self.cursor_col > 0 {
-                    self.cursor_col -= 1;
-                }
+            b'\x08' if self.cursor_col > 0 => {
+                self.cursor_col -= 1;
+            }
+            b'\x07' => {
+                self.bell_count += 1;
+            }
+            b'\x0e' => {
+                // SO: invoke G1
+                self.active_charset = 1;
+            }
+            b'\x0f' => {
+                // SI: invoke G0
+                self.active_charset = 0;
             }
             _ => {}
         }
     }
 
-    fn hook(&mut self, _: &vte::Params, _: &[u8], _: bool, _: char) {}
-    fn put(&mut self, _: u8) {}
-    fn unhook(&mut self) {}
-    fn osc_dispatch(&mut self, _: &[&[u8]], _: bool) {}
+    fn hook(&mut self, params: &vte::Params, intermediates: &[u8], _ignore: bool, action: char) {
+        self.dcs = Some(DcsString {
+            params: params.iter()
+                .map(|p| p.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(":"))
+                .collect(),
+            intermediates: intermediates.to_vec(),
+            action,
+            payload: Vec::new(),
+        });
+    }
+
+    fn put(&mut self, byte: u8) {
+        if let Some(dcs) = self.dcs.as_mut() {
+            if dcs.payload.len() < DCS_PAYLOAD_LIMIT {
+                dcs.payload.push(byte);
+            }
+        }
+    }
+
+    fn unhook(&mut self) {
+        if let Some(dcs) = self.dcs.take() {
+            self.dispatch_dcs(dcs);
+        }
+    }
+    fn osc_dispatch(&mut self, params: &[&[u8]], _: bool) {
+        // OSC 8 ; params ; URL ST starts a hyperlink, an empty URL ends it.
+        // The URL may itself contain ';', which vte splits on.
+        if params.first() == Some(&&b"8"[..]) && params.len() > 2 {
+            let url = params[2..].iter()
+                .map(|part| String::from_utf8_lossy(part))
+                .collect::<Vec<_>>()
+                .join(";");
+            self.current_link = (!url.is_empty()).then_some(url);
+            self.link_run_open = false;
+        }
+    }
 
     fn csi_dispatch(&mut self, params: &vte::Params, intermediates: &[u8], _ignore: bool, action: char) {
         match action {
             'H' | 'f' => {
-                let row = params.iter().nth(0).and_then(|p| p.first()).copied().unwrap_or(1).saturating_sub(1) as usize;
-                let col = params.iter().nth(1).and_then(|p| p.first()).copied().unwrap_or(1).saturating_sub(1) as usize;
+                // CSI row;col H - either may be omitted and defaults to 1
+                let row = csi_param(params, 0, 1) as usize - 1;
+                let col = csi_param(params, 1, 1) as usize - 1;
                 self.move_cursor(row, col);
             }
             'A' => {
                 let n = params.iter().nth(0).and_then(|p| p.first()).copied().unwrap_or(1).max(1) as usize;
                 self.move_cursor_row(self.cursor_row.saturating_sub(n));
             }
-            'B' => {
+            // VPR (CSI e) moves like CUD; with no scroll region it stops at the bottom row
+            'B' | 'e' => {
                 let n = params.iter().nth(0).and_then(|p| p.first()).copied().unwrap_or(1).max(1) as usize;
                 self.move_cursor_row(self.cursor_row + n);
             }
-            'C' => {
+            // HPR (CSI a) moves like CUF
+            'C' | 'a' => {
                 let n = params.iter().nth(0).and_then(|p| p.first()).copied().unwrap_or(1).max(1) as usize;
                 self.move_cursor_col(self.cursor_col + n);
             }
@@ -296,7 +807,16 @@ impl Perform for CustomScreen {
                 let n = params.iter().nth(0).and_then(|p| p.first()).copied().unwrap_or(1).max(1) as usize;
                 self.move_cursor_col(self.cursor_col.saturating_sub(n));
             }
-            'G' => {
+            'E' => {
+                let n = params.iter().nth(0).and_then(|p| p.first()).copied().unwrap_or(1).max(1) as usize;
+                self.move_cursor(self.cursor_row + n, 0);
+            }
+            'F' => {
+                let n = params.iter().nth(0).and_then(|p| p.first()).copied().unwrap_or(1).max(1) as usize;
+                self.move_cursor(self.cursor_row.saturating_sub(n), 0);
+            }
+            // HPA (CSI `) positions like CHA
+            'G' | '`' => {
                 let col = params.iter().nth(0).and_then(|p| p.first()).copied().unwrap_or(1).saturating_sub(1) as usize;
                 self.move_cursor_col(col);
             }
@@ -325,6 +845,20 @@ impl Perform for CustomScreen {
                         }
                         self.move_cursor(0, 0);
                     }
+                    1 => {
+                        for row in 0..self.cursor_row {
+                            for col in 0..self.cols {
+                                self.cells[row][col] = ' ';
+                            }
+                        }
+                        for col in 0..=self.cursor_col {
+                            self.cells[self.cursor_row][col] = ' ';
+                        }
+                    }
+                    3 => {
+                        // Erase saved lines only; the visible screen is untouched
+                        self.scrollback.clear();
+                    }
                     _ => {}
                 }
             }
@@ -368,23 +902,25 @@ impl Perform for CustomScreen {
                 }
             }
             'P' => {
+                // DCH: shift the rest of the line up to the right margin left, blank-filling
                 let n = params.iter().nth(0).and_then(|p| p.first()).copied().unwrap_or(1).max(1) as usize;
-                let row = self.cursor_row;
-                for _ in 0..n {
-                    if self.cursor_col < self.cols {
-                        self.cells[row].remove(self.cursor_col);
-                        self.cells[row].push(' ');
-                    }
+                let (col, right) = (self.cursor_col, self.right_margin());
+                if col < right {
+                    let n = n.min(right - col);
+                    let line = &mut self.cells[self.cursor_row][col..right];
+                    line.rotate_left(n);
+                    line[right - col - n..].fill(' ');
                 }
             }
             '@' => {
+                // ICH: shift the rest of the line right, dropping what passes the right margin
                 let n = params.iter().nth(0).and_then(|p| p.first()).copied().unwrap_or(1).max(1) as usize;
-                let row = self.cursor_row;
-                for _ in 0..n {
-                    if self.cursor_col < self.cols {
-                        self.cells[row].pop();
-                        self.cells[row].insert(self.cursor_col, ' ');
-                    }
+                let (col, right) = (self.cursor_col, self.right_margin());
+                if col < right {
+                    let n = n.min(right - col);
+                    let line = &mut self.cells[self.cursor_row][col..right];
+                    line.rotate_right(n);
+                    line[..n].fill(' ');
                 }
             }
             'X' => {
@@ -413,82 +949,188 @@ impl Perform for CustomScreen {
                 let n = params.iter().nth(0).and_then(|p| p.first()).copied().unwrap_or(1).max(1) as usize;
                 let mut col = self.cursor_col;
                 for _ in 0..n {
-                    col = ((col / 8) + 1) * 8;
-                    if col >= self.cols {
-                        col = self.cols - 1;
-                        break;
-                    }
+                    col = self.next_tab_stop(col);
                 }
                 self.move_cursor_col(col);
             }
             'Z' => {
-                if self.cursor_col > 0 {
-                    self.move_cursor_col(((self.cursor_col - 1) / 8) * 8);
+                let n = params.iter().nth(0).and_then(|p| p.first()).copied().unwrap_or(1).max(1) as usize;
+                let mut col = self.cursor_col;
+                for _ in 0..n {
+                    col = self.prev_tab_stop(col);
                 }
+                self.move_cursor_col(col);
             }
             'b' => {
-                let n = params.iter().nth(0).and_then(|p| p.first()).copied().unwrap_or(1).max(1) as usize;
-                let c = self.last_char;
-                for _ in 0..n {
-                    self.print(c);
+                // REP: repeat the last graphic character, without wrapping past the line end
+                if let Some(c) = self.last_char {
+                    let n = params.iter().nth(0).and_then(|p| p.first()).copied().unwrap_or(1).max(1) as usize;
+                    let remaining = if self.pending_wrap { 0 } else { self.cols - self.cursor_col };
+                    for _ in 0..n.min(remaining) {
+                        self.print(c);
+                    }
                 }
             }
             'g' => {
-                // Clear Tab Stop - we use fixed 8-column tabs, so ignore
+                // TBC: 0 clears the stop at the cursor, 3 clears all stops
+                match params.iter().nth(0).and_then(|p| p.first()).copied().unwrap_or(0) {
+                    0 => self.tab_stops[self.cursor_col] = false,
+                    3 => self.tab_stops.fill(false),
+                    _ => {}
+                }
             }
             'm' => {
-                // SGR - ignore (colors/attributes)
+                // SGR - ignore (colors/attributes). Colors would need each
+                // param's colon sub-params too (38:2::r:g:b, not only 38;2;r;g;b)
             }
             'n' => {
                 let mode = params.iter().nth(0).and_then(|p| p.first()).copied().unwrap_or(0);
-                match mode {
-                    5 => {
+                match (intermediates, mode) {
+                    ([], 5) => {
                         self.pending_responses.push(b"\x1b[0n".to_vec());
                     }
-                    6 => {
+                    ([], 6) => {
                         let response = format!("\x1b[{};{}R", self.cursor_row + 1, self.cursor_col + 1);
                         self.pending_responses.push(response.into_bytes());
                     }
+                    ([b'?'], 6) => {
+                        // DECXCPR: the DEC form of the cursor position report
+                        let response = format!("\x1b[?{};{}R", self.cursor_row + 1, self.cursor_col + 1);
+                        self.pending_responses.push(response.into_bytes());
+                    }
+                    ([b'?'], 15) => {
+                        // Printer status: no printer
+                        self.pending_responses.push(b"\x1b[?13n".to_vec());
+                    }
                     _ => {}
                 }
             }
             'c' => {
                 let mode = params.iter().nth(0).and_then(|p| p.first()).copied().unwrap_or(0);
                 if mode == 0 {
-                    self.pending_responses.push(b"\x1b[?1;2c".to_vec());
+                    match intermediates {
+                        // Primary DA: VT100 with AVO
+                        [] => self.pending_responses.push(b"\x1b[?1;2c".to_vec()),
+                        // Secondary DA: terminal type 0 (VT100), version 95, no ROM cartridge
+                        [b'>'] => self.pending_responses.push(b"\x1b[>0;95;0c".to_vec()),
+                        _ => {}
+                    }
                 }
             }
-            _ => {
-                let mut seq = String::from("\\e[");
-                for intermediate in intermediates {
-                    seq.push(*intermediate as char);
-                }
-                let param_strs: Vec<String> = params.iter()
-                    .map(|p| p.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(":"))
-                    .collect();
-                seq.push_str(&param_strs.join(";"));
-                seq.push(action);
-
-                let mut raw = vec![0x1b, b'['];
-                raw.extend_from_slice(intermediates);
-                for (i, p) in params.iter().enumerate() {
-                    if i > 0 { raw.push(b';'); }
-                    for (j, v) in p.iter().enumerate() {
-                        if j > 0 { raw.push(b':'); }
-                        raw.extend_from_slice(v.to_string().as_bytes());
+            'h' | 'l' if intermediates == [b'?'] => {
+                // DECSET/DECRST - track the private modes we know, record the rest
+                let set = action == 'h';
+                let mut unknown = false;
+                for param in params.iter() {
+                    let mode = param.first().copied().unwrap_or(0);
+                    if self.private_mode(mode, Some(set)).is_none() {
+                        unknown = true;
                     }
                 }
-                raw.push(action as u8);
-
-                self.debug_buffer.push(seq, &raw);
+                if unknown {
+                    self.record_unhandled_csi(params, intermediates, action);
+                }
+            }
+            'p' if intermediates == [b'?', b'$'] => {
+                // DECRQM for private modes: reply 1 = set, 2 = reset, 0 = not recognized
+                let mode = params.iter().nth(0).and_then(|p| p.first()).copied().unwrap_or(0);
+                let state = match self.private_mode(mode, None) {
+                    Some(true) => 1,
+                    Some(false) => 2,
+                    None => 0,
+                };
+                let response = format!("\x1b[?{};{}$y", mode, state);
+                self.pending_responses.push(response.into_bytes());
+            }
+            'p' if intermediates == [b'$'] => {
+                // DECRQM for ANSI modes: none are tracked
+                let mode = params.iter().nth(0).and_then(|p| p.first()).copied().unwrap_or(0);
+                let response = format!("\x1b[{};0$y", mode);
+                self.pending_responses.push(response.into_bytes());
+            }
+            'q' if intermediates == [b'>'] => {
+                // XTVERSION
+                self.pending_responses.push(xtversion_response());
+            }
+            't' if intermediates.is_empty() => {
+                // XTWINOPS: answer the size reports; there is no window to
+                // move, resize or iconify, so the other operations do nothing
+                let op = params.iter().nth(0).and_then(|p| p.first()).copied().unwrap_or(0);
+                if let Some(reply) = window_size_report(op, self.rows, self.cols) {
+                    self.pending_responses.push(reply);
+                }
+            }
+            'q' if intermediates == [b' '] => {
+                // DECSCUSR: set cursor style
+                let code = params.iter().nth(0).and_then(|p| p.first()).copied().unwrap_or(0);
+                match CursorStyle::from_decscusr(code) {
+                    Some(style) => self.cursor_style = style,
+                    None => self.record_unhandled_csi(params, intermediates, action),
+                }
+            }
+            // CSI s is DECSLRM (set left/right margins) while DECLRMM (?69) is
+            // set, and SCOSC (save cursor) otherwise. Left/right margins are
+            // not supported, so it is always SCOSC; a DECLRMM flag belongs in
+            // this guard if they ever are.
+            's' if intermediates.is_empty() => {
+                self.save_cursor();
+            }
+            'u' if intermediates.is_empty() => {
+                // SCORC: restore, or go home if nothing was saved
+                self.restore_cursor();
+            }
+            _ => {
+                self.record_unhandled_csi(params, intermediates, action);
             }
         }
     }
 
     fn esc_dispatch(&mut self, intermediates: &[u8], _ignore: bool, byte: u8) {
         match byte {
-            b'H' => {
-                // Set Tab Stop (hts) - we use fixed 8-column tabs, ignore
+            b'H' if intermediates.is_empty() => {
+                // HTS: set a tab stop at the cursor column
+                self.tab_stops[self.cursor_col] = true;
+            }
+            b'=' if intermediates.is_empty() => {
+                // DECKPAM - application keypad
+                self.application_keypad = true;
+            }
+            b'>' if intermediates.is_empty() => {
+                // DECKPNM - numeric keypad
+                self.application_keypad = false;
+            }
+            b'\\' if intermediates.is_empty() => {
+                // String Terminator (ST) - the DCS/OSC string was already handled
+            }
+            b'7' if intermediates.is_empty() => {
+                // DECSC: save cursor and character sets
+                self.save_cursor();
+            }
+            b'8' if intermediates.is_empty() => {
+                // DECRC: restore them
+                self.restore_cursor();
+            }
+            b'c' if intermediates.is_empty() => {
+                // RIS: full reset
+                self.reset();
+            }
+            b'B' | b'0' if matches!(intermediates, [b'(' | b')' | b'*' | b'+']) => {
+                // SCS: designate ASCII or line drawing into G0-G3
+                let set = match intermediates[0] {
+                    b'(' => 0,
+                    b')' => 1,
+                    b'*' => 2,
+                    _ => 3,
+                };
+                self.charsets[set] = if byte == b'0' { Charset::LineDrawing } else { Charset::Ascii };
+            }
+            b'N' if intermediates.is_empty() => {
+                // SS2: the next character comes from G2
+                self.single_shift = Some(2);
+            }
+            b'O' if intermediates.is_empty() => {
+                // SS3: the next character comes from G3
+                self.single_shift = Some(3);
             }
             _ => {
                 let mut seq = String::from("\\e");
@@ -501,7 +1143,7 @@ impl Perform for CustomScreen {
                 raw.extend_from_slice(intermediates);
                 raw.push(byte);
 
-                self.debug_buffer.push(seq, &raw);
+                self.debug_buffer.push(seq, &raw, self.raw_capture.as_deref());
             }
         }
     }
@@ -0,0 +1,9 @@
+// Terminal emulation, usable without the daemon
+//
+// The interminai binary drives these modules from a PTY; `render` drives
+// them directly, so escape-sequence handling can be tested synchronously.
+
+pub mod terminal;
+pub mod custom_screen;
+pub mod alacritty_backend;
+pub mod render;
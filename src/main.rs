@@ -4,41 +4,34 @@
 //
 // A PTY-based tool for interacting with terminal applications (Rust version).
 
-mod terminal;
-mod custom_screen;
-mod alacritty_backend;
+mod protocol;
 
-use clap::{Parser as ClapParser, Subcommand};
+use clap::{Parser as ClapParser, Subcommand, ValueEnum};
 use anyhow::{Result, Context, bail};
 use std::process::{Command as ProcessCommand};
 use std::os::unix::process::CommandExt;
+use std::ffi::CString;
 use tempfile::Builder;
 use serde::{Deserialize, Serialize};
 use std::os::unix::net::{UnixListener, UnixStream};
 use std::io::{BufRead, BufReader, Write, Read};
 use std::sync::{Arc, Mutex};
 use std::thread;
+use std::collections::VecDeque;
 use std::time::Duration;
 use nix::pty::{openpty, Winsize};
 use nix::unistd::{setsid, Pid};
 use nix::sys::wait::{waitpid, WaitStatus, WaitPidFlag};
-use nix::sys::signal::{kill, Signal};
-use nix::sys::termios::{tcgetattr, LocalFlags, InputFlags, OutputFlags, SpecialCharacterIndices};
-use std::os::fd::{AsRawFd, OwnedFd};
+use nix::sys::signal::{kill, killpg, Signal};
+use nix::sys::termios::{tcgetattr, tcsendbreak, LocalFlags, InputFlags, OutputFlags, SpecialCharacterIndices};
+use std::os::fd::{AsRawFd, OwnedFd, RawFd};
 use std::fs;
 use std::path::Path;
 
-use terminal::TerminalEmulator;
-
-/// Terminal emulator backend
-#[derive(Clone, Copy, Debug, Default, clap::ValueEnum)]
-pub enum Emulator {
-    /// Full xterm-256color terminal emulation (alacritty backend)
-    #[default]
-    Xterm,
-    /// Basic ANSI terminal emulation (custom backend)
-    Custom,
-}
+use interminai::{alacritty_backend, custom_screen, render, terminal};
+use render::Emulator;
+use terminal::{MouseTracking, TerminalEmulator};
+use protocol::ScrollbackPolicy;
 
 #[derive(ClapParser)]
 #[command(name = "interminai")]
@@ -46,6 +39,50 @@ pub enum Emulator {
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+
+    /// Only print machine-relevant output: start prints just the socket path,
+    /// status just sets the exit status (0 if running, 1 if exited),
+    /// wait waits for exit and prints only the exit code
+    #[arg(long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// Print extra information, e.g. the size before a resize
+    #[arg(long, global = true)]
+    verbose: bool,
+}
+
+/// How much informational output the client prints (global --quiet/--verbose)
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Verbosity {
+    Quiet,
+    Normal,
+    Verbose,
+}
+
+impl Verbosity {
+    fn from_flags(quiet: bool, verbose: bool) -> Self {
+        if quiet {
+            Verbosity::Quiet
+        } else if verbose {
+            Verbosity::Verbose
+        } else {
+            Verbosity::Normal
+        }
+    }
+
+    /// Print an informational line, suppressed by --quiet
+    fn info(self, message: impl std::fmt::Display) {
+        if self != Verbosity::Quiet {
+            println!("{}", message);
+        }
+    }
+
+    /// Print a line only with --verbose
+    fn detail(self, message: impl std::fmt::Display) {
+        if self == Verbosity::Verbose {
+            println!("{}", message);
+        }
+    }
 }
 
 #[derive(Subcommand)]
@@ -56,9 +93,25 @@ enum Commands {
         #[arg(long)]
         socket: Option<String>,
 
-        /// Terminal size (e.g., 80x24)
-        #[arg(long, default_value = "80x24")]
-        size: String,
+        /// Replace the socket even if a daemon is still listening on it
+        #[arg(long, requires = "socket")]
+        force: bool,
+
+        /// Terminal size (e.g., 80x24) [default: 80x24]
+        #[arg(long, conflicts_with_all = ["rows", "cols"])]
+        size: Option<String>,
+
+        /// Terminal rows, an alternative to --size [default: 24]
+        #[arg(long)]
+        rows: Option<u16>,
+
+        /// Terminal columns, an alternative to --size [default: 80]
+        #[arg(long)]
+        cols: Option<u16>,
+
+        /// Accept sizes above 2000x1000
+        #[arg(long)]
+        allow_large: bool,
 
         /// Terminal emulator backend (xterm or custom)
         #[arg(long, value_enum, default_value = "xterm")]
@@ -68,19 +121,165 @@ enum Commands {
         #[arg(long)]
         no_daemon: bool,
 
+        /// Print only the socket path instead of the banner
+        #[arg(long)]
+        print_socket: bool,
+
         /// Dump all raw PTY output to this file (for debugging)
         #[arg(long)]
         pty_dump: Option<String>,
 
-        /// Scrollback buffer capacity in lines (default: 10000)
-        #[arg(long, default_value = "10000")]
-        scrollback: usize,
+        /// Append a timestamped hex/text log of all PTY output to this file (for debugging)
+        #[arg(long)]
+        log: Option<String>,
+
+        /// Also log bytes written to the PTY (requires --log)
+        #[arg(long, requires = "log")]
+        log_input: bool,
+
+        /// Log each INPUT request's payload and how much of it reached the PTY (requires --log)
+        #[arg(long, requires = "log")]
+        trace_input: bool,
+
+        /// Append one timestamped line per chunk written to (IN) or read from
+        /// (OUT) the PTY to this file, in the order the daemon saw them
+        #[arg(long, value_name = "PATH")]
+        log_merged: Option<String>,
+
+        /// Keep the original bytes of unhandled escape sequences for `debug --raw`
+        #[arg(long)]
+        debug_raw: bool,
+
+        /// Scrollback buffer capacity: a number of lines, none, or unlimited
+        /// (still bounded by --max-output-bytes)
+        #[arg(long, value_name = "N|none|unlimited", default_value = "10000")]
+        scrollback: ScrollbackPolicy,
+
+        /// Cap on buffered output history (scrollback plus unread raw output) in bytes;
+        /// the oldest history is dropped first, the visible screen is always kept
+        #[arg(long, value_name = "N", default_value_t = DEFAULT_MAX_OUTPUT_BYTES,
+              value_parser = clap::value_parser!(u64).range(1..))]
+        max_output_bytes: u64,
+
+        /// Bytes read from the PTY per read call
+        #[arg(long, value_name = "N", default_value_t = DEFAULT_READ_CHUNK_SIZE,
+              value_parser = clap::value_parser!(u64).range(1..=1024 * 1024))]
+        read_chunk_size: u64,
+
+        /// Most PTY output processed before requests get a turn, in bytes;
+        /// lower keeps input/output responsive under heavy output
+        #[arg(long, value_name = "N", default_value_t = DEFAULT_READ_LIMIT,
+              value_parser = clap::value_parser!(u64).range(1..))]
+        read_limit: u64,
+
+        /// TERM value for the command (default: xterm-256color for xterm, ansi for custom)
+        #[arg(long)]
+        term: Option<String>,
+
+        /// Run this shell command in the background whenever the command exits,
+        /// with INTERMINAI_EXIT_CODE and INTERMINAI_SOCKET set
+        #[arg(long, value_name = "CMD")]
+        on_exit: Option<String>,
+
+        /// Start the command again (on a fresh screen) whenever it exits
+        #[arg(long)]
+        keepalive: bool,
+
+        /// With --keepalive: stop restarting after this many restarts (default: unlimited)
+        #[arg(long, value_name = "N", requires = "keepalive")]
+        max_restarts: Option<u32>,
+
+        /// Keep inherited file descriptor N (3 or above) open in the command;
+        /// repeatable. Other inherited descriptors above 2 are closed
+        #[arg(long = "pass-fd", value_name = "N")]
+        pass_fds: Vec<RawFd>,
+
+        /// Write this input to the command once it is running, before any
+        /// client request is served. Supports the same escapes as `input --text`
+        #[arg(long, value_name = "TEXT")]
+        initial_input: Option<String>,
+
+        /// Wait this many milliseconds after starting the command before
+        /// writing --initial-input
+        #[arg(long, value_name = "MS", requires = "initial_input")]
+        initial_input_delay_ms: Option<u64>,
+
+        /// Regex matching the command's prompt, e.g. '\$ $'; `wait --ready`
+        /// returns once the text before the cursor matches it
+        #[arg(long, value_name = "REGEX")]
+        prompt_regex: Option<regex::Regex>,
+
+        /// Stop the command (SIGTERM, then SIGKILL) and shut the daemon down
+        /// this many seconds after start, if the command is still running
+        #[arg(long, value_name = "SECS")]
+        max_runtime: Option<f64>,
+
+        /// Command to run
+        #[arg(required = true, last = true)]
+        command: Vec<String>,
+    },
+
+    /// Run a command in a terminal until it exits, print its final screen and
+    /// exit with its exit code
+    Exec {
+        /// Terminal size (e.g., 80x24)
+        #[arg(long, default_value = "80x24")]
+        size: String,
+
+        /// Accept sizes above 2000x1000
+        #[arg(long)]
+        allow_large: bool,
+
+        /// Terminal emulator backend (xterm or custom)
+        #[arg(long, value_enum, default_value = "xterm")]
+        emulator: Emulator,
+
+        /// TERM value for the command (default: xterm-256color for xterm, ansi for custom)
+        #[arg(long)]
+        term: Option<String>,
+
+        /// Stop the command after this many seconds and exit with status 124
+        #[arg(long, value_name = "SECS")]
+        timeout: Option<f64>,
 
         /// Command to run
         #[arg(required = true, last = true)]
         command: Vec<String>,
     },
 
+    /// Feed recorded terminal output (e.g. from start --pty-dump) to an
+    /// emulator and print the resulting screen; no process is run
+    Render {
+        /// Read the bytes from this file instead of stdin
+        #[arg(long)]
+        file: Option<String>,
+
+        /// Terminal size (e.g., 80x24)
+        #[arg(long, default_value = "80x24")]
+        size: String,
+
+        /// Accept sizes above 2000x1000
+        #[arg(long)]
+        allow_large: bool,
+
+        /// Terminal emulator backend (xterm or custom)
+        #[arg(long, value_enum, default_value = "xterm")]
+        emulator: Emulator,
+
+        /// Keep colors as ANSI escape codes
+        #[arg(long)]
+        color: bool,
+
+        /// Cursor display: none, inverse (highlight the cursor cell), print
+        /// ("Cursor: row R, col C" first, 1-based) or both
+        #[arg(long, default_value = "none", value_parser = ["none", "inverse", "print", "both"])]
+        cursor: String,
+    },
+
+    /// Print the interminai version, git commit, protocol version and
+    /// emulator backends of this binary; no session is needed
+    Version,
+
     /// Send input to running session
     Input {
         /// Unix socket path (required)
@@ -96,6 +295,68 @@ enum Commands {
         /// Automatically appends \r (Enter) after input
         #[arg(long)]
         password: bool,
+
+        /// Send a focus event (in or out) as reported by a terminal with focus reporting
+        #[arg(long, value_parser = ["in", "out"])]
+        focus: Option<String>,
+
+        /// Send the terminal's current EOF character (VEOF, usually Ctrl-D)
+        #[arg(long)]
+        eof: bool,
+
+        /// Send a control character by name, e.g. C-c or C-d
+        #[arg(long, value_name = "C-KEY")]
+        ctrl: Option<String>,
+
+        /// Send a named key (up, down, left, right, home, end, pageup, pagedown,
+        /// insert, delete, kp0-kp9, kp-enter, kp-plus, kp-minus, kp-star, kp-slash,
+        /// kp-period), encoded for the keypad modes the application has set
+        #[arg(long, value_name = "NAME")]
+        key: Option<String>,
+
+        /// Send a mouse event: BUTTON is left, middle, right, wheel-up, wheel-down
+        /// or none (with move), ROW and COL are 1-based and ACTION is press (the
+        /// default), release or move. Encoded for the mouse mode the application
+        /// enabled; fails if it hasn't enabled one
+        #[arg(long, value_name = "BUTTON,ROW,COL[,ACTION]",
+              conflicts_with_all = ["text", "password", "focus", "eof", "ctrl", "key", "echo", "enter"])]
+        mouse: Option<String>,
+
+        /// Send a break condition (tcsendbreak) instead of input, as a serial
+        /// terminal's Break key does; unlike C-c it is not a character
+        #[arg(long = "break",
+              conflicts_with_all = ["text", "password", "focus", "eof", "ctrl", "key", "mouse", "echo", "enter"])]
+        send_break: bool,
+
+        /// Before writing, process and discard the output the program has
+        /// already produced: it stays on screen, but `wait` and `output
+        /// --format raw` then only report what came after this input
+        #[arg(long, conflicts_with = "send_break")]
+        drain_first: bool,
+
+        /// Run a JSON array of steps from FILE (- for stdin), in order:
+        /// {"text": "..."} with the --text escapes, {"key": "NAME"} with the
+        /// --key names plus esc, enter, tab, backspace and space,
+        /// {"ctrl": "C-x"} and {"sleep_ms": N}
+        #[arg(long, value_name = "FILE",
+              conflicts_with_all = ["text", "password", "focus", "eof", "ctrl", "key", "mouse", "send_break", "echo", "enter"])]
+        from_json: Option<String>,
+
+        /// Also show the printable text on screen right away, for programs that
+        /// don't echo; display only, the program receives the same bytes
+        #[arg(long, conflicts_with_all = ["password", "focus", "eof", "ctrl", "key"])]
+        echo: bool,
+
+        /// Press Enter after the text: append \r (cr, the default, what the Enter
+        /// key sends) or \n (lf)
+        #[arg(long, value_name = "cr|lf", num_args = 0..=1, default_missing_value = "cr",
+              value_parser = ["cr", "lf"], conflicts_with_all = ["password", "focus", "eof", "ctrl", "key"])]
+        enter: Option<String>,
+
+        /// Fail with a "PTY busy" error if the program reads none of the input
+        /// for this many seconds (default: 5); the error says how much was sent
+        #[arg(long, value_name = "SECS")]
+        timeout: Option<f64>,
     },
 
     /// Get screen output from running session
@@ -116,10 +377,15 @@ enum Commands {
         #[arg(short = 'n', long = "number")]
         number: bool,
 
-        /// Cursor display mode (none, inverse, print, both)
-        #[arg(long, default_value = "none")]
+        /// Cursor display mode (none, inverse, print, both, json).
+        /// json prints {"cursor":{"row":R,"col":C}} as the first line
+        #[arg(long, default_value = "none", value_parser = ["none", "inverse", "print", "both", "json"])]
         cursor: String,
 
+        /// Number the printed cursor row/col from 0 or 1 (the protocol is always 0-based)
+        #[arg(long, default_value_t = 1, value_parser = clap::value_parser!(u64).range(0..=1))]
+        cursor_base: u64,
+
         /// Start output from this line (negative=scrollback, positive=screen, 0=boundary, "-"=all scrollback)
         #[arg(long, allow_hyphen_values = true)]
         from: Option<String>,
@@ -127,6 +393,54 @@ enum Commands {
         /// End output at this line (negative=scrollback, positive=screen, 0=boundary)
         #[arg(long, allow_hyphen_values = true)]
         to: Option<i64>,
+
+        /// Drop trailing blank lines (blank lines between content are kept)
+        #[arg(long)]
+        trim: bool,
+
+        /// Leave out the newline after the last line, for exact comparisons
+        /// in scripts
+        #[arg(long, conflicts_with_all = ["links", "cursor_style"])]
+        no_trailing_newline: bool,
+
+        /// Hard-wrap lines longer than N columns, whatever the terminal width
+        #[arg(long, value_name = "N", value_parser = clap::value_parser!(u64).range(1..))]
+        max_width: Option<u64>,
+
+        /// Output format: text, jsonl (one {"row":N,"text":"..."} object per screen row),
+        /// or raw (PTY bytes received since the last raw read, written unmodified)
+        #[arg(long, default_value = "text", value_parser = ["text", "jsonl", "raw"])]
+        format: String,
+
+        /// With --format raw: drop escape sequences and control characters,
+        /// leaving the printed text
+        #[arg(long)]
+        strip_ansi: bool,
+
+        /// With --format raw: return the bytes after this stream offset instead
+        /// of those since the last raw read, and print the next offset to stderr
+        #[arg(long, value_name = "OFFSET")]
+        since: Option<u64>,
+
+        /// With --format raw: turn CR-LF and lone CR line endings into LF
+        #[arg(long)]
+        normalize_newlines: bool,
+
+        /// With --format raw: have the daemon gzip the data, to cut the size
+        /// of large captures (ignored by daemons that can't)
+        #[arg(long)]
+        compress: bool,
+
+        /// After the screen, list the OSC 8 hyperlinks on it, one
+        /// "Link: row R, col C: TEXT -> URL" line each (1-based)
+        #[arg(long)]
+        links: bool,
+
+        /// Pass on the cursor style the application set (DECSCUSR): as the
+        /// `CSI Ps SP q` sequence after a colored screen, or a
+        /// "Cursor style: STYLE" line with --no-color
+        #[arg(long)]
+        cursor_style: bool,
     },
 
     /// Stop running session
@@ -134,6 +448,14 @@ enum Commands {
         /// Unix socket path (required)
         #[arg(long, required = true)]
         socket: String,
+
+        /// Leave the socket file in place, even if start generated it
+        #[arg(long, conflicts_with = "remove_socket")]
+        keep_socket: bool,
+
+        /// Delete the socket file, even if it was given to start with --socket
+        #[arg(long)]
+        remove_socket: bool,
     },
 
     /// Get session status
@@ -142,9 +464,13 @@ enum Commands {
         #[arg(long, required = true)]
         socket: String,
 
-        /// Quiet mode: just exit status (0 if running, 1 if exited)
+        /// First wait until the program has produced output (or exited)
         #[arg(long)]
-        quiet: bool,
+        wait_ready: bool,
+
+        /// With --wait-ready: give up after this many seconds
+        #[arg(long, value_name = "SECS", default_value_t = 10.0, requires = "wait_ready")]
+        timeout: f64,
     },
 
     /// Wait until session exits or activity occurs
@@ -153,10 +479,6 @@ enum Commands {
         #[arg(long, required = true)]
         socket: String,
 
-        /// Quiet mode: wait for exit only, print exit code
-        #[arg(long)]
-        quiet: bool,
-
         /// Wait until content of this line number changes (1-based)
         #[arg(long = "line", value_name = "LINE")]
         line: Option<usize>,
@@ -168,17 +490,40 @@ enum Commands {
         /// With --line: wait until line contains this pattern
         #[arg(long = "contains", value_name = "PATTERN")]
         contains: Option<String>,
+
+        /// Wait for exit and exit with the child's exit code (128+N if killed by signal N)
+        #[arg(long, conflicts_with = "line")]
+        propagate_exit: bool,
+
+        /// Wait for exit or activity, then print {"reason":"exit"|"activity",...} as JSON
+        #[arg(long, conflicts_with_all = ["line", "propagate_exit"])]
+        any: bool,
+
+        /// Wait for exit and succeed only if the exit code is N. With
+        /// start --keepalive, keep waiting through restarts until it is
+        #[arg(long, value_name = "N", allow_hyphen_values = true, conflicts_with_all = ["line", "propagate_exit", "any"])]
+        exit_code: Option<i32>,
+
+        /// Wait until the prompt from start --prompt-regex is before the
+        /// cursor, then print the cursor position (1-based). Fails if the
+        /// program exits first
+        #[arg(long, conflicts_with_all = ["line", "propagate_exit", "any", "exit_code"])]
+        ready: bool,
     },
 
     /// Send signal to running process
     Kill {
-        /// Unix socket path (required)
-        #[arg(long, required = true)]
-        socket: String,
+        /// Unix socket path (required unless --list)
+        #[arg(long, required_unless_present = "list")]
+        socket: Option<String>,
 
         /// Signal to send (named like SIGTERM, SIGKILL, SIGINT or numeric like 9, 15, 2)
         #[arg(long, default_value = "SIGTERM")]
         signal: String,
+
+        /// List supported signal names and numbers, then exit
+        #[arg(long)]
+        list: bool,
     },
 
     /// Resize the terminal
@@ -188,8 +533,70 @@ enum Commands {
         socket: String,
 
         /// New terminal size (e.g., 120x40)
+        #[arg(long, required_unless_present_any = ["auto", "rows", "cols"], conflicts_with_all = ["auto", "rows", "cols"])]
+        size: Option<String>,
+
+        /// Match the size of the terminal this command runs in
+        #[arg(long, conflicts_with_all = ["rows", "cols"])]
+        auto: bool,
+
+        /// New number of rows, or +N/-N relative to the current size
+        #[arg(long, allow_hyphen_values = true)]
+        rows: Option<String>,
+
+        /// New number of columns, or +N/-N relative to the current size
+        #[arg(long, allow_hyphen_values = true)]
+        cols: Option<String>,
+
+        /// Accept sizes above 2000x1000
         #[arg(long)]
-        size: String,
+        allow_large: bool,
+
+        /// Report how many rows rewrapping to the new width moved into (or out of)
+        /// scrollback, where `output --from` can read them (--emulator xterm only)
+        #[arg(long)]
+        reflow: bool,
+    },
+
+    /// Clear the screen and scrollback without affecting the running program
+    Clear {
+        /// Unix socket path (required)
+        #[arg(long, required = true)]
+        socket: String,
+    },
+
+    /// Print the current view (screen, scrollback, cursor, modes) as an
+    /// opaque string for `restore`; the program is not affected
+    Snapshot {
+        /// Unix socket path (required)
+        #[arg(long, required = true)]
+        socket: String,
+    },
+
+    /// Put back the view saved by `snapshot`; the program keeps running and
+    /// its next output draws over the restored screen
+    Restore {
+        /// Unix socket path (required)
+        #[arg(long, required = true)]
+        socket: String,
+
+        /// Read the snapshot from this file instead of stdin
+        #[arg(long)]
+        file: Option<String>,
+    },
+
+    /// Show how the session was started: command, backend, size, TERM
+    Info {
+        /// Unix socket path (required)
+        #[arg(long, required = true)]
+        socket: String,
+    },
+
+    /// List the processes running in the session (Linux only)
+    Children {
+        /// Unix socket path (required)
+        #[arg(long, required = true)]
+        socket: String,
     },
 
     /// Show unhandled escape sequences (for debugging)
@@ -201,6 +608,15 @@ enum Commands {
         /// Clear the buffer after reading
         #[arg(long)]
         clear: bool,
+
+        /// Also show the bytes of each sequence exactly as received (needs start --debug-raw)
+        #[arg(long)]
+        raw: bool,
+
+        /// Explain the latest SGR (color and attribute) sequences the program
+        /// sent, e.g. "\e[1;31m  SGR: bold, fg=red"
+        #[arg(long)]
+        decode_sgr: bool,
     },
 }
 
@@ -223,10 +639,11 @@ struct Response {
 }
 
 impl Response {
-    fn ok(data: serde_json::Value) -> Self {
+    /// `data` is one of the protocol response types (or plain JSON)
+    fn ok(data: impl Serialize) -> Self {
         Response {
             status: "ok".to_string(),
-            data: Some(data),
+            data: Some(serde_json::to_value(data).expect("Response data should serialize to JSON")),
             error: None,
         }
     }
@@ -248,146 +665,1176 @@ fn create_terminal(rows: usize, cols: usize, emulator: Emulator, scrollback: usi
     }
 }
 
-struct DaemonState {
-    master_fd: OwnedFd,
-    child_pid: Pid,
-    terminal: Box<dyn TerminalEmulator>,
-    exit_code: Option<i32>,
-    socket_path: String,
-    socket_was_auto_generated: bool,
-    should_shutdown: bool,
-    pty_dump: Option<std::fs::File>,
-    /// Activity flag: set when PTY output is received
-    activity: bool,
+/// Options for the session started by `start`
+struct SessionOptions {
+    emulator: Emulator,
+    pty_dump: Option<String>,
+    log: Option<String>,
+    log_input: bool,
+    trace_input: bool,
+    log_merged: Option<String>,
+    debug_raw: bool,
+    scrollback: ScrollbackPolicy,
+    max_output_bytes: u64,
+    read_chunk_size: u64,
+    read_limit: u64,
+    term: Option<String>,
+    on_exit: Option<String>,
+    keepalive: bool,
+    max_restarts: Option<u32>,
+    /// Inherited descriptors the command keeps, from `start --pass-fd`
+    pass_fds: Vec<RawFd>,
+    /// Bytes written to the command before any client connection is accepted,
+    /// from `start --initial-input`, and how long to wait before writing them
+    initial_input: Option<Vec<u8>>,
+    initial_input_delay: Duration,
+    /// From `start --prompt-regex`
+    prompt_regex: Option<regex::Regex>,
+    /// From `start --max-runtime`
+    max_runtime: Option<Duration>,
+    command: Vec<String>,
 }
 
-impl DaemonState {
-    fn check_child_status(&mut self) {
-        if self.exit_code.is_some() {
-            return;
+/// Fixed facts about the session, recorded at start for INFO
+struct SessionInfo {
+    command: Vec<String>,
+    /// Seconds since the Unix epoch
+    started_at: u64,
+    emulator: Emulator,
+    /// Size given to `start` as (cols, rows); RESIZE doesn't change it
+    cols: u16,
+    rows: u16,
+    term: String,
+    /// PTY slave device, if the system could name it
+    pty: Option<String>,
+}
+
+/// What `start --keepalive` needs to run the command again
+struct Keepalive {
+    /// PTY slave, kept open so each new child can use it
+    slave: OwnedFd,
+    command: Vec<String>,
+    term: String,
+    emulator: Emulator,
+    max_restarts: Option<u32>,
+    /// Kept open in the daemon so each new child gets them too
+    pass_fds: Vec<RawFd>,
+}
+
+/// Max PTY bytes kept for `output --format raw` between reads
+const RAW_OUTPUT_CAPACITY: usize = 1024 * 1024;
+
+/// Default for `start --max-output-bytes`
+const DEFAULT_MAX_OUTPUT_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Byte budgets for buffered output history under `start --max-output-bytes`:
+/// the raw buffer gets at most half, scrollback (counted as one byte per
+/// cell) gets the rest
+struct OutputLimit {
+    raw_capacity: usize,
+    scrollback_bytes: usize,
+}
+
+impl OutputLimit {
+    fn new(max_output_bytes: u64) -> Self {
+        let max = usize::try_from(max_output_bytes).unwrap_or(usize::MAX);
+        let raw_capacity = RAW_OUTPUT_CAPACITY.min(max / 2);
+        OutputLimit {
+            raw_capacity,
+            scrollback_bytes: max - raw_capacity,
         }
+    }
 
-        match waitpid(self.child_pid, Some(WaitPidFlag::WNOHANG)) {
-            Ok(WaitStatus::Exited(_, code)) => {
-                self.exit_code = Some(code);
-            }
-            Ok(WaitStatus::Signaled(_, sig, _)) => {
-                self.exit_code = Some(128 + sig as i32);
-            }
-            _ => {}
+    /// Scrollback lines that fit the budget at the given width
+    fn scrollback_lines(&self, cols: usize) -> usize {
+        self.scrollback_bytes / cols.max(1)
+    }
+}
+
+/// Bounded buffer of PTY output for `output --format raw`; oldest bytes are
+/// dropped when it overflows. Bytes are numbered by their offset in the whole
+/// output stream, so readers can resume from an offset (`output --since`)
+struct RawOutputBuffer {
+    bytes: std::collections::VecDeque<u8>,
+    capacity: usize,
+    /// Stream offset of the oldest byte kept
+    start: u64,
+    /// Stream offset up to which `take` has returned bytes
+    taken: u64,
+}
+
+impl RawOutputBuffer {
+    fn new(capacity: usize) -> Self {
+        RawOutputBuffer {
+            bytes: std::collections::VecDeque::new(),
+            capacity,
+            start: 0,
+            taken: 0,
         }
     }
 
-    fn read_pty_output(&mut self) {
-        let mut buf = [0u8; 4096];
-        loop {
-            match nix::unistd::read(self.master_fd.as_raw_fd(), &mut buf) {
-                Ok(0) => break,
-                Ok(n) => {
-                    // Any output from PTY is activity
-                    self.activity = true;
-                    // Dump raw bytes if pty_dump is enabled
-                    if let Some(ref mut dump) = self.pty_dump {
-                        let _ = dump.write_all(&buf[..n]);
-                    }
-                    self.terminal.process_bytes(&buf[..n]);
-                }
-                Err(_) => break,
-            }
+    fn push(&mut self, data: &[u8]) {
+        self.bytes.extend(data);
+        if self.bytes.len() > self.capacity {
+            let excess = self.bytes.len() - self.capacity;
+            self.bytes.drain(..excess);
+            self.start += excess as u64;
         }
+    }
 
-        // Send any pending responses back to the PTY (e.g., cursor position reports)
-        for response in self.terminal.take_pending_responses() {
-            let _ = nix::unistd::write(self.master_fd.as_raw_fd(), &response);
+    /// Stream offset just past the newest byte
+    fn end(&self) -> u64 {
+        self.start + self.bytes.len() as u64
+    }
+
+    /// Bytes from `offset` on, and how many bytes after `offset` were already
+    /// dropped (the gap). None if `offset` is past the end of the stream.
+    fn read_since(&self, offset: u64) -> Option<(Vec<u8>, usize)> {
+        if offset > self.end() {
+            return None;
         }
+        let from = offset.max(self.start);
+        let bytes = self.bytes.range((from - self.start) as usize..).copied().collect();
+        Some((bytes, (from - offset) as usize))
     }
 
+    /// Bytes not yet returned by `take`, and the count of those dropped before
+    /// they could be
+    fn take(&mut self) -> (Vec<u8>, usize) {
+        let unread = self.read_since(self.taken).expect("taken offset is never past the end");
+        self.taken = self.end();
+        unread
+    }
 }
 
-fn parse_terminal_size(size: &str) -> Result<(u16, u16)> {
-    let parts: Vec<&str> = size.split('x').collect();
-    if parts.len() != 2 {
-        bail!("Invalid size format, expected WxH like 80x24");
+/// How many SGR sequences `debug --decode-sgr` can show
+const RECENT_SGR_LIMIT: usize = 32;
+
+/// Totals of PTY output seen by the daemon, independent of the
+/// emulator backend, so `debug` can show whether output arrived at all
+struct OutputCounters {
+    parser: vte::Parser,
+    bytes_read: u64,
+    csi_count: u64,
+    esc_count: u64,
+    print_count: u64,
+    /// Parameters of the latest SGR sequences, as "1;38:5:196"
+    recent_sgr: VecDeque<String>,
+}
+
+impl OutputCounters {
+    fn new() -> Self {
+        OutputCounters {
+            parser: vte::Parser::new(),
+            bytes_read: 0,
+            csi_count: 0,
+            esc_count: 0,
+            print_count: 0,
+            recent_sgr: VecDeque::new(),
+        }
+    }
+
+    fn count(&mut self, bytes: &[u8]) {
+        self.bytes_read += bytes.len() as u64;
+        let mut parser = std::mem::replace(&mut self.parser, vte::Parser::new());
+        for byte in bytes {
+            parser.advance(self, *byte);
+        }
+        self.parser = parser;
     }
-    let cols = parts[0].parse::<u16>().context("Invalid columns")?;
-    let rows = parts[1].parse::<u16>().context("Invalid rows")?;
-    Ok((cols, rows))
 }
 
-/// Unescape C-style escape sequences in a string.
-/// Supports: \n \r \t \a \b \f \v \\ \e \xHH
-fn unescape(s: &str) -> Result<String> {
-    let mut result = String::new();
-    let mut chars = s.chars().peekable();
+impl vte::Perform for OutputCounters {
+    fn print(&mut self, _c: char) {
+        self.print_count += 1;
+    }
 
-    while let Some(c) = chars.next() {
-        if c == '\\' {
-            match chars.next() {
-                Some('n') => result.push('\n'),
-                Some('r') => result.push('\r'),
-                Some('t') => result.push('\t'),
-                Some('a') => result.push('\x07'),  // bell
-                Some('b') => result.push('\x08'),  // backspace
-                Some('f') => result.push('\x0C'),  // form feed (Ctrl+L)
-                Some('v') => result.push('\x0B'),  // vertical tab
-                Some('\\') => result.push('\\'),
-                Some('e') | Some('E') => result.push('\x1B'),  // ESC
-                Some('x') => {
-                    // Parse two hex digits
-                    let h1 = chars.next().ok_or_else(|| anyhow::anyhow!("incomplete \\x escape"))?;
-                    let h2 = chars.next().ok_or_else(|| anyhow::anyhow!("incomplete \\x escape"))?;
-                    let hex_str: String = [h1, h2].iter().collect();
-                    let byte = u8::from_str_radix(&hex_str, 16)
-                        .context(format!("invalid hex escape: \\x{}", hex_str))?;
-                    result.push(byte as char);
-                }
-                Some(other) => {
-                    // Unknown escape - keep as-is
-                    result.push('\\');
-                    result.push(other);
-                }
-                None => result.push('\\'),
+    fn csi_dispatch(&mut self, params: &vte::Params, intermediates: &[u8], _ignore: bool, action: char) {
+        self.csi_count += 1;
+        if action == 'm' && intermediates.is_empty() {
+            if self.recent_sgr.len() == RECENT_SGR_LIMIT {
+                self.recent_sgr.pop_front();
             }
-        } else {
-            result.push(c);
+            let groups: Vec<String> = params.iter()
+                .map(|group| group.iter().map(u16::to_string).collect::<Vec<_>>().join(":"))
+                .collect();
+            self.recent_sgr.push_back(groups.join(";"));
         }
     }
 
-    Ok(result)
+    fn esc_dispatch(&mut self, _intermediates: &[u8], _ignore: bool, _byte: u8) {
+        self.esc_count += 1;
+    }
 }
 
-fn parse_signal(sig: &str) -> Result<Signal> {
-    // Try parsing as number first
-    if let Ok(num) = sig.parse::<i32>() {
-        return Signal::try_from(num).context("Invalid signal number");
+/// Human-readable meaning of an SGR sequence's parameters ("1;31" gives
+/// "bold, fg=red"), for `debug --decode-sgr`
+fn describe_sgr(params: &str) -> String {
+    const COLORS: [&str; 8] = ["black", "red", "green", "yellow", "blue", "magenta", "cyan", "white"];
+    // Extended colors come as 38;5;N or 38:5:N (and 38;2;R;G;B or 38:2::R:G:B)
+    fn extended_color(args: &[u16]) -> (String, usize) {
+        match args {
+            [5, n, ..] => (format!("color {}", n), 2),
+            [2, r, g, b, ..] => (format!("#{:02x}{:02x}{:02x}", r, g, b), 4),
+            _ => ("?".to_string(), args.len()),
+        }
     }
 
-    // Parse named signals
-    let sig_upper = sig.to_uppercase();
-    let sig_name = if sig_upper.starts_with("SIG") {
-        sig_upper.as_str()
-    } else {
-        // Add SIG prefix if not present
-        return parse_signal(&format!("SIG{}", sig));
-    };
-
-    match sig_name {
-        "SIGHUP" => Ok(Signal::SIGHUP),
-        "SIGINT" => Ok(Signal::SIGINT),
-        "SIGQUIT" => Ok(Signal::SIGQUIT),
-        "SIGKILL" => Ok(Signal::SIGKILL),
-        "SIGTERM" => Ok(Signal::SIGTERM),
-        "SIGUSR1" => Ok(Signal::SIGUSR1),
-        "SIGUSR2" => Ok(Signal::SIGUSR2),
-        _ => bail!("Unknown signal: {}", sig),
+    let groups: Vec<Vec<u16>> = params.split(';')
+        .map(|group| group.split(':').map(|p| p.parse().unwrap_or(0)).collect())
+        .collect();
+    let mut parts = Vec::new();
+    let mut i = 0;
+    while i < groups.len() {
+        let group = &groups[i];
+        i += 1;
+        let code = group[0];
+        let part = match code {
+            0 => "reset".to_string(),
+            1 => "bold".to_string(),
+            2 => "dim".to_string(),
+            3 => "italic".to_string(),
+            4 => match group.get(1) {
+                Some(0) => "no underline".to_string(),
+                Some(2) => "double underline".to_string(),
+                Some(3) => "curly underline".to_string(),
+                Some(4) => "dotted underline".to_string(),
+                Some(5) => "dashed underline".to_string(),
+                _ => "underline".to_string(),
+            },
+            5 | 6 => "blink".to_string(),
+            7 => "inverse".to_string(),
+            8 => "hidden".to_string(),
+            9 => "strikethrough".to_string(),
+            21 => "double underline".to_string(),
+            22 => "normal intensity".to_string(),
+            23 => "not italic".to_string(),
+            24 => "not underlined".to_string(),
+            25 => "not blinking".to_string(),
+            27 => "not inverse".to_string(),
+            28 => "not hidden".to_string(),
+            29 => "not strikethrough".to_string(),
+            30..=37 => format!("fg={}", COLORS[(code - 30) as usize]),
+            40..=47 => format!("bg={}", COLORS[(code - 40) as usize]),
+            90..=97 => format!("fg=bright {}", COLORS[(code - 90) as usize]),
+            100..=107 => format!("bg=bright {}", COLORS[(code - 100) as usize]),
+            39 => "fg=default".to_string(),
+            49 => "bg=default".to_string(),
+            59 => "underline color=default".to_string(),
+            53 => "overline".to_string(),
+            55 => "not overlined".to_string(),
+            38 | 48 | 58 => {
+                let target = match code { 38 => "fg", 48 => "bg", _ => "underline color" };
+                let color = if group.len() > 1 {
+                    // Colon form: 38:2::R:G:B has an empty color space slot
+                    let args = &group[1..];
+                    match args {
+                        [2, _, r, g, b] => extended_color(&[2, *r, *g, *b]).0,
+                        _ => extended_color(args).0,
+                    }
+                } else {
+                    let args: Vec<u16> = groups[i..].iter().map(|g| g[0]).collect();
+                    let (color, used) = extended_color(&args);
+                    i += used;
+                    color
+                };
+                format!("{}={}", target, color)
+            }
+            _ => format!("unknown {}", code),
+        };
+        parts.push(part);
     }
+    parts.join(", ")
+}
+
+/// Collects what `input --echo` shows: printable text, with CR and LF as
+/// a new line; control characters and escape sequences are dropped
+struct EchoText(String);
+
+impl vte::Perform for EchoText {
+    fn print(&mut self, c: char) {
+        self.0.push(c);
+    }
+
+    fn execute(&mut self, byte: u8) {
+        if byte == b'\r' || byte == b'\n' {
+            self.0.push_str("\r\n");
+        }
+    }
+}
+
+/// The screen bytes that echo `input` the way a terminal with echo on would
+fn echo_text(input: &[u8]) -> Vec<u8> {
+    let mut echo = EchoText(String::new());
+    let mut parser = vte::Parser::new();
+    for byte in input {
+        parser.advance(&mut echo, *byte);
+    }
+    echo.0.into_bytes()
+}
+
+/// Collects the text of `output --format raw --strip-ansi`: what was
+/// printed, with line breaks and tabs; escape sequences and other control
+/// characters are dropped
+#[derive(Default)]
+struct PlainText {
+    text: String,
+    /// A CR not (yet) followed by LF
+    pending_cr: bool,
+}
+
+impl vte::Perform for PlainText {
+    fn print(&mut self, c: char) {
+        // A lone CR rewrites the line in place; keep both versions
+        if std::mem::take(&mut self.pending_cr) {
+            self.text.push('\n');
+        }
+        self.text.push(c);
+    }
+
+    fn execute(&mut self, byte: u8) {
+        match byte {
+            b'\r' => self.pending_cr = true,
+            b'\n' => {
+                self.pending_cr = false;
+                self.text.push('\n');
+            }
+            b'\t' => self.text.push('\t'),
+            _ => {}
+        }
+    }
+}
+
+/// Raw PTY bytes as a plain text transcript
+fn plain_text(bytes: &[u8]) -> String {
+    let mut plain = PlainText::default();
+    let mut parser = vte::Parser::new();
+    for byte in bytes {
+        parser.advance(&mut plain, *byte);
+    }
+    plain.text
+}
+
+/// Raw PTY bytes with CR-LF and lone CR line endings turned into LF. Escape
+/// sequences are left alone.
+fn normalize_newlines(bytes: &[u8]) -> Vec<u8> {
+    let mut normalized = Vec::with_capacity(bytes.len());
+    let mut iter = bytes.iter().peekable();
+    while let Some(&byte) = iter.next() {
+        if byte == b'\r' {
+            iter.next_if_eq(&&b'\n');
+            normalized.push(b'\n');
+        } else {
+            normalized.push(byte);
+        }
+    }
+    normalized
+}
+
+/// Timestamped hex/text log of PTY traffic (`start --log`)
+struct SessionLog {
+    file: std::fs::File,
+    start: std::time::Instant,
+    log_input: bool,
+    trace_input: bool,
+}
+
+impl SessionLog {
+    /// Log bytes read from the PTY
+    fn output(&mut self, bytes: &[u8]) {
+        self.record("output", bytes);
+    }
+
+    /// Log bytes written to the PTY, if --log-input was given
+    fn input(&mut self, direction: &str, bytes: &[u8]) {
+        if self.log_input {
+            self.record(direction, bytes);
+        }
+    }
+
+    /// Log an INPUT request's payload, if --trace-input was given
+    fn trace(&mut self, payload: &[u8], written: usize) {
+        if self.trace_input {
+            let entry = format_trace_entry(self.start.elapsed(), payload, written);
+            self.write_entry(&entry);
+        }
+    }
+
+    fn record(&mut self, direction: &str, bytes: &[u8]) {
+        let entry = format_log_entry(self.start.elapsed(), direction, bytes);
+        self.write_entry(&entry);
+    }
+
+    fn write_entry(&mut self, entry: &str) {
+        let _ = self.file.write_all(entry.as_bytes());
+        let _ = self.file.flush();
+    }
+}
+
+/// One-line-per-chunk timeline of PTY traffic in both directions (`start --log-merged`)
+struct MergedLog {
+    file: std::fs::File,
+    start: std::time::Instant,
+}
+
+impl MergedLog {
+    /// Log a chunk: "IN" for bytes written to the PTY, "OUT" for bytes read from it
+    fn record(&mut self, tag: &str, bytes: &[u8]) {
+        let entry = format_merged_entry(self.start.elapsed(), tag, bytes);
+        let _ = self.file.write_all(entry.as_bytes());
+        let _ = self.file.flush();
+    }
+}
+
+/// Format one `--log-merged` entry: timestamp, tag, byte count and the bytes as an escaped string
+fn format_merged_entry(elapsed: Duration, tag: &str, bytes: &[u8]) -> String {
+    let escaped: String = bytes.iter().flat_map(|&b| std::ascii::escape_default(b)).map(char::from).collect();
+    format!("[{:>12.6}] {:<3} {} \"{}\"\n", elapsed.as_secs_f64(), tag, bytes.len(), escaped)
+}
+
+/// Format one `--trace-input` entry: the payload as hex and as an escaped string
+fn format_trace_entry(elapsed: Duration, payload: &[u8], written: usize) -> String {
+    let hex: Vec<String> = payload.iter().map(|b| format!("{:02x}", b)).collect();
+    let escaped: String = payload.iter().flat_map(|&b| std::ascii::escape_default(b)).map(char::from).collect();
+    format!("[{:>12.6}] trace {} bytes, {} written: {} \"{}\"\n",
+        elapsed.as_secs_f64(), payload.len(), written, hex.join(" "), escaped)
+}
+
+/// Format one log entry: a timestamped header, then 16 bytes per line as hex and text
+fn format_log_entry(elapsed: Duration, direction: &str, bytes: &[u8]) -> String {
+    let mut entry = format!("[{:>12.6}] {} {} bytes\n", elapsed.as_secs_f64(), direction, bytes.len());
+    for chunk in bytes.chunks(16) {
+        let hex: Vec<String> = chunk.iter().map(|b| format!("{:02x}", b)).collect();
+        let text: String = chunk.iter()
+            .map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' })
+            .collect();
+        entry.push_str(&format!("    {:<47}  |{}|\n", hex.join(" "), text));
+    }
+    entry
+}
+
+struct DaemonState {
+    master_fd: OwnedFd,
+    child_pid: Pid,
+    terminal: Box<dyn TerminalEmulator>,
+    exit_code: Option<i32>,
+    /// Set while the child is suspended (SIGSTOP, SIGTSTP, ...)
+    stopped: bool,
+    socket_path: String,
+    socket_was_auto_generated: bool,
+    /// From `stop --keep-socket` / `--remove-socket`; None removes only
+    /// auto-generated sockets
+    remove_socket: Option<bool>,
+    should_shutdown: bool,
+    pty_dump: Option<std::fs::File>,
+    log: Option<SessionLog>,
+    log_merged: Option<MergedLog>,
+    /// PTY output not yet read with `output --format raw`
+    raw_output: RawOutputBuffer,
+    /// Bytes and sequences read from the PTY, for `debug`
+    output_counters: OutputCounters,
+    /// Buffer for one PTY read, `start --read-chunk-size` long
+    read_buf: Vec<u8>,
+    /// Set with `start --read-limit`
+    read_limit: usize,
+    /// Set with `start --scrollback`
+    scrollback: ScrollbackPolicy,
+    /// Budget from `start --max-output-bytes`
+    output_limit: OutputLimit,
+    /// Scrollback lines dropped to stay within output_limit
+    scrollback_dropped: u64,
+    /// Activity flag: set when PTY output is received
+    activity: bool,
+    /// Set once the child has produced any PTY output; never cleared
+    ready: bool,
+    /// Set when the child failed to exec the command
+    exec_error: Option<String>,
+    /// Set with `start --keepalive`
+    keepalive: Option<Keepalive>,
+    /// Times the command was restarted by --keepalive
+    restarts: u32,
+    /// Times the command exited (more than once only with --keepalive)
+    exits: u64,
+    /// Exit codes of the last RECENT_EXIT_CODES_LIMIT exits, oldest first
+    recent_exit_codes: std::collections::VecDeque<i32>,
+    /// How the session was started
+    info: SessionInfo,
+    /// Set with `start --debug-raw`
+    debug_raw: bool,
+    /// Shell command from `start --on-exit`
+    on_exit: Option<String>,
+    /// From `start --prompt-regex`
+    prompt_regex: Option<regex::Regex>,
+    /// Why the daemon killed the command, e.g. "max_runtime"
+    killed_by: Option<&'static str>,
+    /// When the reader saw the PTY hang up
+    pty_hangup: Option<std::time::Instant>,
+    /// Why the screen is no longer updated, e.g. the PTY reader thread panicked
+    degraded: Option<String>,
+    /// Why `start --initial-input` could not be written to the command
+    initial_input_error: Option<String>,
+}
+
+/// Exit codes remembered for `wait --exit-code`, which can miss a few
+/// --keepalive restarts between its checks
+const RECENT_EXIT_CODES_LIMIT: usize = 16;
+
+/// Lock the daemon state, even if a thread panicked while holding it: a
+/// dead reader is reported in STATUS rather than taking the daemon down
+fn lock_state(state: &Mutex<DaemonState>) -> std::sync::MutexGuard<'_, DaemonState> {
+    state.lock().unwrap_or_else(std::sync::PoisonError::into_inner)
+}
+
+/// Lock the daemon state after check_child_status. Once the PTY has hung up
+/// the child is exiting: give it until EXIT_AFTER_HANGUP_GRACE past the
+/// hangup to be reaped, so nobody sees the final output with the command
+/// still running. The lock is released between polls
+fn lock_state_reaped(state: &Mutex<DaemonState>) -> std::sync::MutexGuard<'_, DaemonState> {
+    loop {
+        let mut locked = lock_state(state);
+        locked.check_child_status();
+        let deadline = locked.pty_hangup.map(|hangup| hangup + EXIT_AFTER_HANGUP_GRACE);
+        if locked.exit_code.is_some() || deadline.is_none_or(|deadline| std::time::Instant::now() >= deadline) {
+            return locked;
+        }
+        drop(locked);
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
+impl DaemonState {
+    fn check_child_status(&mut self) {
+        if self.exit_code.is_some() {
+            return;
+        }
+
+        // Stop and continue events queue up, so collect all of them
+        let flags = WaitPidFlag::WNOHANG | WaitPidFlag::WUNTRACED | WaitPidFlag::WCONTINUED;
+        while self.exit_code.is_none() {
+            match waitpid(self.child_pid, Some(flags)) {
+                Ok(WaitStatus::Exited(_, code)) => {
+                    self.exit_code = Some(code);
+                    self.stopped = false;
+                }
+                Ok(WaitStatus::Signaled(_, sig, _)) => {
+                    self.exit_code = Some(128 + sig as i32);
+                    self.stopped = false;
+                }
+                Ok(WaitStatus::Stopped(..)) => self.stopped = true,
+                Ok(WaitStatus::Continued(_)) => self.stopped = false,
+                _ => break,
+            }
+        }
+
+        if let Some(code) = self.exit_code {
+            self.exits += 1;
+            if self.recent_exit_codes.len() >= RECENT_EXIT_CODES_LIMIT {
+                self.recent_exit_codes.pop_front();
+            }
+            self.recent_exit_codes.push_back(code);
+            self.run_exit_hook(code);
+            self.restart_child();
+        }
+    }
+
+    /// Run the `start --on-exit` command in its own process group, reaped
+    /// from a thread so it can't hold up the daemon
+    fn run_exit_hook(&self, code: i32) {
+        let Some(hook) = &self.on_exit else {
+            return;
+        };
+        let spawned = ProcessCommand::new("sh")
+            .arg("-c")
+            .arg(hook)
+            .env("INTERMINAI_EXIT_CODE", code.to_string())
+            .env("INTERMINAI_SOCKET", &self.socket_path)
+            .stdin(std::process::Stdio::null())
+            .stdout(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .process_group(0)
+            .spawn();
+        match spawned {
+            Ok(mut child) => {
+                thread::spawn(move || {
+                    let _ = child.wait();
+                });
+            }
+            Err(e) => eprintln!("Failed to run --on-exit command: {}", e),
+        }
+    }
+
+    /// With --keepalive, run the command again on a fresh screen, unless the
+    /// daemon is stopping or the restart limit is reached
+    fn restart_child(&mut self) {
+        if self.should_shutdown || self.killed_by.is_some() {
+            return;
+        }
+        match &self.keepalive {
+            Some(keepalive) if keepalive.max_restarts.is_none_or(|max| self.restarts < max) => {}
+            _ => return,
+        }
+
+        // Don't let the old child's last output land on the new screen
+        self.drain_pty_output();
+        let Some(keepalive) = &self.keepalive else {
+            return;
+        };
+        let (rows, cols) = self.terminal.dimensions();
+        self.terminal = create_terminal(rows, cols, keepalive.emulator, self.scrollback.capacity());
+        if self.debug_raw {
+            self.terminal.enable_raw_capture();
+        }
+
+        match spawn_child(&keepalive.slave, &keepalive.command, &keepalive.term, &keepalive.pass_fds) {
+            Ok((pid, exec_error)) => {
+                self.child_pid = pid;
+                self.exit_code = None;
+                self.exec_error = exec_error;
+                self.restarts += 1;
+            }
+            Err(e) => eprintln!("Failed to restart command: {}", e),
+        }
+    }
+
+    /// Scrollback lines kept at most: the --scrollback capacity, or less if
+    /// --max-output-bytes allows fewer at the current width
+    fn scrollback_capacity(&self) -> usize {
+        let (_, cols) = self.terminal.dimensions();
+        self.terminal.scrollback_capacity().min(self.output_limit.scrollback_lines(cols))
+    }
+
+    /// Process pending PTY output, up to read_limit bytes; returns true
+    /// if the limit was hit and more output may be waiting
+    fn read_pty_output(&mut self) -> bool {
+        let mut buf = std::mem::take(&mut self.read_buf);
+        let mut total = 0;
+        while total < self.read_limit {
+            match nix::unistd::read(self.master_fd.as_raw_fd(), &mut buf) {
+                Ok(0) => break,
+                Ok(n) => {
+                    total += n;
+                    // Any output from PTY is activity
+                    self.activity = true;
+                    self.ready = true;
+                    // Dump raw bytes if pty_dump is enabled
+                    if let Some(ref mut dump) = self.pty_dump {
+                        let _ = dump.write_all(&buf[..n]);
+                    }
+                    if let Some(ref mut log) = self.log {
+                        log.output(&buf[..n]);
+                    }
+                    if let Some(ref mut merged) = self.log_merged {
+                        merged.record("OUT", &buf[..n]);
+                    }
+                    self.raw_output.push(&buf[..n]);
+                    self.output_counters.count(&buf[..n]);
+                    render::feed(self.terminal.as_mut(), &buf[..n]);
+                }
+                Err(_) => break,
+            }
+        }
+        self.read_buf = buf;
+
+        // Keep scrollback within --max-output-bytes at the current width
+        let (_, cols) = self.terminal.dimensions();
+        let max_lines = self.output_limit.scrollback_lines(cols);
+        self.scrollback_dropped += self.terminal.truncate_scrollback(max_lines) as u64;
+
+        // Send any pending responses back to the PTY (e.g., cursor position reports)
+        for response in self.terminal.take_pending_responses() {
+            if let Some(ref mut log) = self.log {
+                log.input("reply", &response);
+            }
+            if let Some(ref mut merged) = self.log_merged {
+                merged.record("IN", &response);
+            }
+            let _ = nix::unistd::write(self.master_fd.as_raw_fd(), &response);
+        }
+
+        total >= self.read_limit
+    }
+
+    /// Process all pending PTY output, e.g. what a child wrote before exiting
+    fn drain_pty_output(&mut self) {
+        while self.read_pty_output() {}
+    }
+
+    /// The cursor position, if the text before the cursor on its row
+    /// matches `start --prompt-regex`
+    fn prompt_cursor(&self) -> Option<(usize, usize)> {
+        let regex = self.prompt_regex.as_ref()?;
+        let snapshot = self.terminal.snapshot(false, 0);
+        let (row, col) = snapshot.cursor;
+        let before_cursor: String = snapshot.rows.get(row)?.chars().take(col).collect();
+        regex.is_match(&before_cursor).then_some((row, col))
+    }
+
+    /// Write all of `bytes` to the PTY. The master is non-blocking, so a
+    /// large input can be written in pieces: while the PTY is full, drain
+    /// its output (the child may be blocked echoing) and retry, failing
+    /// with a "PTY busy" error once no progress has been made for
+    /// `stall_timeout`. Nothing is dropped silently: returns the number of
+    /// bytes written, which on failure comes with the error.
+    fn write_input(&mut self, bytes: &[u8], stall_timeout: Duration) -> std::result::Result<usize, (usize, String)> {
+        let mut written = 0;
+        let mut last_progress = std::time::Instant::now();
+        while written < bytes.len() {
+            match nix::unistd::write(self.master_fd.as_raw_fd(), &bytes[written..]) {
+                Ok(n) if n > 0 => {
+                    if let Some(ref mut merged) = self.log_merged {
+                        merged.record("IN", &bytes[written..written + n]);
+                    }
+                    written += n;
+                    last_progress = std::time::Instant::now();
+                }
+                Ok(_) | Err(nix::errno::Errno::EAGAIN) => {
+                    if last_progress.elapsed() >= stall_timeout {
+                        return Err((written, format!(
+                            "PTY busy: the program read no input for {}s; {} of {} bytes were written, the rest was not sent",
+                            stall_timeout.as_secs_f64(), written, bytes.len())));
+                    }
+                    self.read_pty_output();
+                    thread::sleep(Duration::from_millis(10));
+                }
+                Err(nix::errno::Errno::EINTR) => {}
+                Err(e) => return Err((written, format!("Failed to write to PTY: {}", e))),
+            }
+        }
+        Ok(written)
+    }
+}
+
+/// How long a PTY write may make no progress before INPUT gives up, unless
+/// the request sets its own `timeout`
+const PTY_WRITE_STALL_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Default for `start --read-chunk-size`
+const DEFAULT_READ_CHUNK_SIZE: u64 = 4096;
+
+/// Default for `start --read-limit`: most PTY output processed per
+/// read_pty_output call, so a program that never stops printing can't keep
+/// the state locked against requests
+const DEFAULT_READ_LIMIT: u64 = 64 * 1024;
+
+/// A positive number of seconds from a flag or request field as a Duration;
+/// values too large for one are rejected, as Duration::from_secs_f64 panics
+fn positive_seconds(secs: f64) -> std::result::Result<Duration, &'static str> {
+    if !(secs.is_finite() && secs > 0.0) {
+        return Err("must be a positive number of seconds");
+    }
+    Duration::try_from_secs_f64(secs).map_err(|_| "is too large")
+}
+
+fn parse_terminal_size(size: &str, allow_large: bool) -> Result<(u16, u16)> {
+    let parts: Vec<&str> = size.split('x').collect();
+    if parts.len() != 2 {
+        bail!("Invalid size format, expected WxH like 80x24");
+    }
+    let cols = parts[0].parse::<u16>().context("Invalid columns")?;
+    let rows = parts[1].parse::<u16>().context("Invalid rows")?;
+    validate_terminal_size(cols, rows, allow_large)
+}
+
+/// Check a (cols, rows) terminal size, however it was given
+fn validate_terminal_size(cols: u16, rows: u16, allow_large: bool) -> Result<(u16, u16)> {
+    if cols == 0 || rows == 0 {
+        bail!("Invalid size {}x{}: columns and rows must be at least 1", cols, rows);
+    }
+    if !allow_large && exceeds_size_limit(cols, rows) {
+        bail!("Size {}x{} is larger than {}x{}, which is almost certainly a typo (use --allow-large if it isn't)",
+            cols, rows, MAX_COLS, MAX_ROWS);
+    }
+    Ok((cols, rows))
+}
+
+/// Largest size accepted without --allow-large: the screen holds rows x cols
+/// cells, so a typo like 9999x9999 would allocate ~100M of them
+const MAX_COLS: u16 = 2000;
+const MAX_ROWS: u16 = 1000;
+
+fn exceeds_size_limit(cols: u16, rows: u16) -> bool {
+    cols > MAX_COLS || rows > MAX_ROWS
+}
+
+/// Smallest size a relative `resize --rows`/`--cols` adjustment can shrink to
+const MIN_RESIZE_ROWS: u16 = 2;
+const MIN_RESIZE_COLS: u16 = 10;
+
+/// Resolve a `resize --rows`/`--cols` value: `N` is absolute, `+N`/`-N` is
+/// relative to `current` and clamped to `min`
+fn resolve_dimension(spec: &str, current: u16, min: u16) -> Result<u16> {
+    if spec.starts_with('+') || spec.starts_with('-') {
+        let delta = spec.parse::<i32>().context("Expected N, +N or -N")?;
+        let resolved = (current as i32 + delta).clamp(min as i32, u16::MAX as i32);
+        Ok(resolved as u16)
+    } else {
+        spec.parse::<u16>().context("Expected N, +N or -N")
+    }
+}
+
+/// Unescape C-style escape sequences in a string into the bytes to send.
+/// Supports: \n \r \t \a \b \f \v \\ \e \xHH
+/// Literal characters are UTF-8 encoded; \xHH is a single raw byte.
+fn unescape(s: &str) -> Result<Vec<u8>> {
+    fn push_char(result: &mut Vec<u8>, c: char) {
+        result.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+    }
+
+    let mut result = Vec::new();
+    let mut chars = s.chars();
+
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n') => result.push(b'\n'),
+                Some('r') => result.push(b'\r'),
+                Some('t') => result.push(b'\t'),
+                Some('a') => result.push(0x07),  // bell
+                Some('b') => result.push(0x08),  // backspace
+                Some('f') => result.push(0x0C),  // form feed (Ctrl+L)
+                Some('v') => result.push(0x0B),  // vertical tab
+                Some('\\') => result.push(b'\\'),
+                Some('e') | Some('E') => result.push(0x1B),  // ESC
+                Some('x') => {
+                    // Parse two hex digits
+                    let h1 = chars.next().ok_or_else(|| anyhow::anyhow!("incomplete \\x escape"))?;
+                    let h2 = chars.next().ok_or_else(|| anyhow::anyhow!("incomplete \\x escape"))?;
+                    let hex_str: String = [h1, h2].iter().collect();
+                    let byte = u8::from_str_radix(&hex_str, 16)
+                        .context(format!("invalid hex escape: \\x{}", hex_str))?;
+                    result.push(byte);
+                }
+                Some(other) => {
+                    // Unknown escape - keep as-is
+                    result.push(b'\\');
+                    push_char(&mut result, other);
+                }
+                None => result.push(b'\\'),
+            }
+        } else {
+            push_char(&mut result, c);
+        }
+    }
+
+    Ok(result)
+}
+
+/// Parse a control key name like "C-d" into its control byte.
+/// Accepts C-<letter> (either case), C-@ C-[ C-\ C-] C-^ C-_ and C-? (DEL).
+fn parse_ctrl_key(key: &str) -> Result<u8> {
+    let name = key.strip_prefix("C-")
+        .ok_or_else(|| anyhow::anyhow!("Invalid control key '{}': expected C-<key>, e.g. C-c", key))?;
+    let mut chars = name.chars();
+    match (chars.next(), chars.next()) {
+        (Some('?'), None) => Ok(0x7f),
+        (Some(c), None) if c.is_ascii_alphabetic() => Ok(c.to_ascii_uppercase() as u8 & 0x1f),
+        (Some(c @ ('@' | '[' | '\\' | ']' | '^' | '_')), None) => Ok(c as u8 & 0x1f),
+        _ => bail!("Invalid control key '{}': expected C-<key>, e.g. C-c", key),
+    }
+}
+
+/// INPUT request for literal bytes: text goes as a JSON string, anything
+/// else (e.g. \xff) as base64
+fn input_request(input: Vec<u8>) -> serde_json::Value {
+    match String::from_utf8(input) {
+        Ok(text) => serde_json::json!({
+            "type": "INPUT",
+            "data": text
+        }),
+        Err(e) => {
+            use base64::Engine;
+            serde_json::json!({
+                "type": "INPUT",
+                "data_b64": base64::engine::general_purpose::STANDARD.encode(e.into_bytes())
+            })
+        }
+    }
+}
+
+/// One step of an `input --from-json` program
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct InputStep {
+    text: Option<String>,
+    key: Option<String>,
+    ctrl: Option<String>,
+    sleep_ms: Option<u64>,
+}
+
+/// Keys with a fixed encoding, sent as plain input; the rest go to the
+/// daemon, which knows the keypad modes
+fn plain_key(name: &str) -> Option<&'static [u8]> {
+    match name {
+        "esc" | "escape" => Some(b"\x1b"),
+        "enter" | "return" => Some(b"\r"),
+        "tab" => Some(b"\t"),
+        "backspace" => Some(b"\x7f"),
+        "space" => Some(b" "),
+        _ => None,
+    }
+}
+
+/// One checked step of an `input --from-json` program
+enum InputAction {
+    Send(serde_json::Value),
+    Sleep(Duration),
+}
+
+/// Run an `input --from-json` program: check every step first, so a bad
+/// one sends nothing, then send them in order over INPUT, stopping at the
+/// first one the daemon rejects
+fn run_input_program(socket: &str, program: &str, timeout: Option<f64>, drain_first: bool) -> Result<()> {
+    let steps: Vec<InputStep> = serde_json::from_str(program)
+        .context("Invalid input program: expected a JSON array of steps")?;
+    let mut actions = Vec::with_capacity(steps.len());
+    for (i, step) in steps.into_iter().enumerate() {
+        let step_number = i + 1;
+        let action = match step {
+            InputStep { text: Some(text), key: None, ctrl: None, sleep_ms: None } => {
+                InputAction::Send(input_request(unescape(&text).with_context(|| format!("Step {}", step_number))?))
+            }
+            InputStep { text: None, key: Some(key), ctrl: None, sleep_ms: None } => {
+                let name = key.to_ascii_lowercase();
+                match plain_key(&name) {
+                    Some(bytes) => InputAction::Send(input_request(bytes.to_vec())),
+                    // The daemon picks the bytes, as they depend on the terminal modes
+                    None if key_sequence(&name, false, false).is_some() => InputAction::Send(serde_json::json!({
+                        "type": "INPUT",
+                        "key": name
+                    })),
+                    None => bail!("Step {}: Unknown key '{}'", step_number, key),
+                }
+            }
+            InputStep { text: None, key: None, ctrl: Some(ctrl), sleep_ms: None } => {
+                InputAction::Send(input_request(vec![parse_ctrl_key(&ctrl).with_context(|| format!("Step {}", step_number))?]))
+            }
+            InputStep { text: None, key: None, ctrl: None, sleep_ms: Some(ms) } => {
+                InputAction::Sleep(Duration::from_millis(ms))
+            }
+            _ => bail!("Step {}: expected exactly one of text, key, ctrl or sleep_ms", step_number),
+        };
+        actions.push((step_number, action));
+    }
+
+    let mut drain_first = drain_first;
+    for (step_number, action) in actions {
+        let mut request = match action {
+            InputAction::Send(request) => request,
+            InputAction::Sleep(duration) => {
+                thread::sleep(duration);
+                continue;
+            }
+        };
+        if let Some(secs) = timeout {
+            request["timeout"] = serde_json::json!(secs);
+        }
+        // Only the first step discards earlier output
+        if std::mem::take(&mut drain_first) {
+            request["drain_first"] = serde_json::json!(true);
+        }
+        let response = send_request(socket, request)?;
+        if response.status == "error" {
+            bail!("Step {}: {}", step_number, response.error.unwrap_or_default());
+        }
+    }
+    Ok(())
+}
+
+/// Bytes a terminal sends for a named key. Cursor keys send SS3 (ESC O)
+/// in application cursor key mode, and also in application keypad mode as
+/// many apps only set that (`ESC =`); otherwise CSI. Keypad keys send SS3
+/// in application keypad mode and their plain characters in numeric mode.
+fn key_sequence(name: &str, application_cursor_keys: bool, application_keypad: bool) -> Option<Vec<u8>> {
+    let cursor = match name {
+        "up" => Some(b'A'),
+        "down" => Some(b'B'),
+        "right" => Some(b'C'),
+        "left" => Some(b'D'),
+        "home" => Some(b'H'),
+        "end" => Some(b'F'),
+        _ => None,
+    };
+    if let Some(final_byte) = cursor {
+        let prefix = if application_cursor_keys || application_keypad { b'O' } else { b'[' };
+        return Some(vec![0x1b, prefix, final_byte]);
+    }
+
+    let tilde = match name {
+        "insert" => Some("2"),
+        "delete" => Some("3"),
+        "pageup" => Some("5"),
+        "pagedown" => Some("6"),
+        _ => None,
+    };
+    if let Some(code) = tilde {
+        return Some(format!("\x1b[{}~", code).into_bytes());
+    }
+
+    // (numeric keypad character, application keypad SS3 final byte)
+    let (numeric, application) = match name {
+        "kp-enter" => (b'\r', b'M'),
+        "kp-star" => (b'*', b'j'),
+        "kp-plus" => (b'+', b'k'),
+        "kp-minus" => (b'-', b'm'),
+        "kp-period" => (b'.', b'n'),
+        "kp-slash" => (b'/', b'o'),
+        _ => match name.strip_prefix("kp").map(str::as_bytes) {
+            Some(&[digit @ b'0'..=b'9']) => (digit, b'p' + (digit - b'0')),
+            _ => return None,
+        },
+    };
+    if application_keypad {
+        Some(vec![0x1b, b'O', application])
+    } else {
+        Some(vec![numeric])
+    }
+}
+
+/// A mouse event for INPUT `mouse`, with 1-based screen coordinates
+#[derive(Debug, Deserialize)]
+struct MouseEvent {
+    button: String,
+    row: u32,
+    col: u32,
+    #[serde(default = "default_mouse_action")]
+    action: String,
+}
+
+fn default_mouse_action() -> String {
+    "press".to_string()
+}
+
+/// Bytes a terminal sends for a mouse event, in SGR encoding (CSI < b;x;y M/m)
+/// or the legacy one (CSI M b x y, coordinates offset by 32). Fails unless the
+/// application enabled reporting of this kind of event.
+fn mouse_sequence(event: &MouseEvent, tracking: MouseTracking, sgr: bool) -> Result<Vec<u8>, String> {
+    let button = match event.button.as_str() {
+        "left" => 0,
+        "middle" => 1,
+        "right" => 2,
+        "none" => 3,
+        "wheel-up" => 64,
+        "wheel-down" => 65,
+        other => return Err(format!("Unknown mouse button '{}' (left, middle, right, wheel-up, wheel-down or none)", other)),
+    };
+    let (needed, code) = match (event.action.as_str(), button) {
+        ("press" | "release", 3) => return Err("Mouse button 'none' only goes with 'move'".to_string()),
+        ("release", 64 | 65) => return Err("Wheel buttons have no release".to_string()),
+        ("press" | "release", _) => (MouseTracking::Click, button),
+        ("move", 3) => (MouseTracking::Motion, button + 32),
+        ("move", _) => (MouseTracking::Drag, button + 32),
+        (other, _) => return Err(format!("Unknown mouse action '{}' (press, release or move)", other)),
+    };
+    if event.row == 0 || event.col == 0 {
+        return Err("Mouse row and column start at 1".to_string());
+    }
+    if tracking == MouseTracking::Off {
+        return Err("The application has not enabled mouse reporting".to_string());
+    }
+    if tracking < needed {
+        let events = if needed == MouseTracking::Drag {
+            "mouse movement with a button held (CSI ?1002h)"
+        } else {
+            "mouse movement with no button held (CSI ?1003h)"
+        };
+        return Err(format!("The application has not enabled reporting of {}", events));
+    }
+
+    let release = event.action == "release";
+    if sgr {
+        let end = if release { 'm' } else { 'M' };
+        return Ok(format!("\x1b[<{};{};{}{}", code, event.col, event.row, end).into_bytes());
+    }
+    // Each value is sent as one byte, plus 32
+    if event.row > 223 || event.col > 223 {
+        return Err("Mouse positions past row or column 223 need SGR mouse mode (CSI ?1006h)".to_string());
+    }
+    // The legacy encoding doesn't say which button was released
+    let code = if release { 3 } else { code };
+    Ok(vec![0x1b, b'[', b'M', 32 + code as u8, 32 + event.col as u8, 32 + event.row as u8])
+}
+
+fn parse_signal(sig: &str) -> Result<Signal> {
+    // Try parsing as number first
+    if let Ok(num) = sig.parse::<i32>() {
+        return Signal::try_from(num).context("Invalid signal number");
+    }
+
+    // Parse named signals, adding SIG prefix if not present
+    let sig_upper = sig.to_uppercase();
+    let sig_name = if sig_upper.starts_with("SIG") {
+        sig_upper
+    } else {
+        format!("SIG{}", sig_upper)
+    };
+
+    sig_name.parse::<Signal>()
+        .map_err(|_| anyhow::anyhow!("Unknown signal: {}", sig))
+}
+
+/// Get the window size (cols, rows) of the terminal the client runs in
+fn caller_terminal_size() -> Result<(u16, u16)> {
+    use rustix::termios::{isatty, tcgetwinsize};
+    use std::os::fd::AsFd;
+
+    let stdin = std::io::stdin();
+    let stdout = std::io::stdout();
+    for fd in [stdin.as_fd(), stdout.as_fd()] {
+        if isatty(fd) {
+            let winsize = tcgetwinsize(fd).context("Failed to get terminal size")?;
+            if winsize.ws_col == 0 || winsize.ws_row == 0 {
+                bail!("Terminal reports an unusable size {}x{}", winsize.ws_col, winsize.ws_row);
+            }
+            return Ok((winsize.ws_col, winsize.ws_row));
+        }
+    }
+    bail!("--auto requires stdin or stdout to be a terminal")
+}
+
+/// Poll STATUS until the child has produced output or exited
+fn wait_until_ready(socket: &str, timeout: Duration) -> Result<()> {
+    let start = std::time::Instant::now();
+    loop {
+        // Don't ask for activity: that would clear the flag the final status reports
+        let response = send_request(socket, serde_json::json!({ "type": "STATUS" }))?;
+        if response.status == "error" {
+            bail!("Failed to get status: {}", response.error.unwrap_or_default());
+        }
+        let data = response.data.unwrap_or_default();
+        let ready = data.get("ready").and_then(|v| v.as_bool()).unwrap_or(false);
+        let running = data.get("running").and_then(|v| v.as_bool()).unwrap_or(false);
+        if ready || !running {
+            return Ok(());
+        }
+        if start.elapsed() >= timeout {
+            bail!("Timed out after {:.1}s waiting for the program to produce output", timeout.as_secs_f64());
+        }
+        thread::sleep(Duration::from_millis(50));
+    }
+}
+
+/// Get the current (cols, rows) of the daemon's terminal
+fn current_terminal_size(socket: &str) -> Result<(u16, u16)> {
+    let response = send_request(socket, serde_json::json!({ "type": "OUTPUT" }))?;
+    if response.status == "error" {
+        bail!("Failed to get current size: {}", response.error.unwrap_or_default());
+    }
+    let size = response.data.as_ref().and_then(|data| data.get("size")).context("Response missing size")?;
+    let cols = size.get("cols").and_then(|v| v.as_u64()).context("Response missing cols")?;
+    let rows = size.get("rows").and_then(|v| v.as_u64()).context("Response missing rows")?;
+    Ok((cols as u16, rows as u16))
 }
 
 fn auto_generate_socket_path() -> Result<String> {
-    // Create a temporary directory with proper prefix
-    let temp_dir = Builder::new()
-        .prefix("interminai-")
-        .tempdir()?;
+    // Create a temporary directory with proper prefix, preferring the
+    // user-private $XDG_RUNTIME_DIR over $TMPDIR (or /tmp)
+    let mut builder = Builder::new();
+    builder.prefix("interminai-");
+    let temp_dir = match std::env::var_os("XDG_RUNTIME_DIR").filter(|dir| !dir.is_empty()) {
+        // Not writable (or gone): fall back rather than fail
+        Some(runtime_dir) => builder.tempdir_in(runtime_dir).or_else(|_| builder.tempdir())?,
+        None => builder.tempdir()?,
+    };
 
     // Get the path and keep it persistent (leak the TempDir)
     let socket_path = temp_dir.path().join("socket").to_string_lossy().to_string();
@@ -398,24 +1845,42 @@ fn auto_generate_socket_path() -> Result<String> {
     Ok(socket_path)
 }
 
-fn cmd_start(socket: Option<String>, size: String, emulator: Emulator, daemon: bool, pty_dump: Option<String>, scrollback: usize, command: Vec<String>) -> Result<()> {
+fn cmd_start(socket: Option<String>, (cols, rows): (u16, u16), daemon: bool, options: SessionOptions, verbosity: Verbosity) -> Result<()> {
+    // clap requires a command, but an empty program name can still get through
+    if options.command.first().is_none_or(|program| program.is_empty()) {
+        bail!("No command given: specify the program to run after --, e.g. start -- bash");
+    }
+
     let socket_was_auto_generated = socket.is_none();
     let socket_path = match socket {
         Some(path) => path,
         None => auto_generate_socket_path()?,
     };
 
-    let (cols, rows) = parse_terminal_size(&size)?;
+    // With --quiet only the socket path is printed, so SOCK=$(interminai start --quiet ...) works
+    let print_socket = || {
+        if verbosity == Verbosity::Quiet {
+            println!("{}", socket_path);
+        } else {
+            println!("Socket: {}", socket_path);
+        }
+    };
 
     if !daemon {
         // Run in foreground (default for now)
-        println!("Socket: {}", socket_path);
-        println!("PID: {}", std::process::id());
-        println!("Auto-generated: {}", socket_was_auto_generated);
+        print_socket();
+        verbosity.info(format_args!("PID: {}", std::process::id()));
+        verbosity.info(format_args!("Auto-generated: {}", socket_was_auto_generated));
+        verbosity.detail(format_args!("Size: {}x{}", cols, rows));
 
-        return run_daemon(socket_path, socket_was_auto_generated, rows, cols, emulator, pty_dump, scrollback, command);
+        return run_daemon(socket_path, socket_was_auto_generated, rows, cols, options, None);
     }
 
+    // The daemon writes a newline here once its socket is listening, or the
+    // reason it failed to start
+    let (ready_read, ready_write) = rustix::pipe::pipe_with(rustix::pipe::PipeFlags::CLOEXEC)
+        .context("Failed to create readiness pipe")?;
+
     // Double-fork to properly daemonize
     // Use fork crate which provides a safe wrapper around libc::fork()
     use fork::{fork as safe_fork, Fork};
@@ -427,18 +1892,31 @@ fn cmd_start(socket: Option<String>, size: String, emulator: Emulator, daemon: b
             use nix::unistd::Pid;
             let _ = waitpid(Pid::from_raw(child), None);
 
+            // Return only once clients can connect
+            drop(ready_write);
+            let mut line = String::new();
+            BufReader::new(fs::File::from(ready_read)).read_line(&mut line)
+                .context("Failed to wait for the daemon")?;
+            match line.strip_suffix('\n') {
+                Some("") => {}
+                Some(error) => bail!("Daemon failed to start: {}", error),
+                None => bail!("Daemon failed to start"),
+            }
+
             // The intermediate child has printed the grandchild PID to stdout
             // Now print the rest of the info
-            println!("Socket: {}", socket_path);
-            println!("Auto-generated: {}", socket_was_auto_generated);
+            print_socket();
+            verbosity.info(format_args!("Auto-generated: {}", socket_was_auto_generated));
+            verbosity.detail(format_args!("Size: {}x{}", cols, rows));
             Ok(())
         }
         Ok(Fork::Child) => {
+            drop(ready_read);
             // Intermediate child: fork again, print grandchild PID, and exit
             match safe_fork() {
                 Ok(Fork::Parent(grandchild_pid)) => {
                     // Intermediate parent: print grandchild PID to stdout and exit
-                    println!("PID: {}", grandchild_pid);
+                    verbosity.info(format_args!("PID: {}", grandchild_pid));
                     std::process::exit(0);
                 }
                 Ok(Fork::Child) => {
@@ -475,9 +1953,14 @@ fn cmd_start(socket: Option<String>, size: String, emulator: Emulator, daemon: b
                         }
                     }
 
-                    // Run daemon
-                    if let Err(e) = run_daemon(socket_path, socket_was_auto_generated, rows, cols, emulator, pty_dump, scrollback, command) {
-                        // Daemon errors go to /dev/null in daemon mode, which is fine
+                    // Run daemon; errors before the socket is listening are
+                    // passed back to `start`, the rest go to /dev/null
+                    let ready = fs::File::from(ready_write);
+                    let mut report = ready.try_clone().ok();
+                    if let Err(e) = run_daemon(socket_path, socket_was_auto_generated, rows, cols, options, Some(ready)) {
+                        if let Some(ref mut report) = report {
+                            let _ = writeln!(report, "{}", format!("{:#}", e).replace('\n', " "));
+                        }
                         eprintln!("Daemon error: {}", e);
                         std::process::exit(1);
                     }
@@ -495,7 +1978,98 @@ fn cmd_start(socket: Option<String>, size: String, emulator: Emulator, daemon: b
     }
 }
 
-fn run_daemon(socket_path: String, socket_was_auto_generated: bool, rows: u16, cols: u16, emulator: Emulator, pty_dump: Option<String>, scrollback: usize, command: Vec<String>) -> Result<()> {
+/// Exit status of `exec` when --timeout expires, as with timeout(1)
+const EXEC_TIMEOUT_EXIT_CODE: i32 = 124;
+
+/// Run a session in this process until the command exits (or --timeout),
+/// print the final screen and return the exit status to exit with
+fn cmd_exec((cols, rows): (u16, u16), emulator: Emulator, term: Option<String>, timeout: Option<Duration>, command: Vec<String>) -> Result<i32> {
+    if command.first().is_none_or(|program| program.is_empty()) {
+        bail!("No command given: specify the program to run after --, e.g. exec -- make");
+    }
+
+    // The session is private to this process: an auto-generated socket is
+    // removed when the daemon loop exits
+    let socket_path = auto_generate_socket_path()?;
+    let options = SessionOptions {
+        emulator,
+        pty_dump: None,
+        log: None,
+        log_input: false,
+        trace_input: false,
+        log_merged: None,
+        debug_raw: false,
+        scrollback: ScrollbackPolicy::Lines(10_000),
+        max_output_bytes: DEFAULT_MAX_OUTPUT_BYTES,
+        read_chunk_size: DEFAULT_READ_CHUNK_SIZE,
+        read_limit: DEFAULT_READ_LIMIT,
+        term,
+        on_exit: None,
+        keepalive: false,
+        max_restarts: None,
+        pass_fds: Vec::new(),
+        initial_input: None,
+        initial_input_delay: Duration::ZERO,
+        prompt_regex: None,
+        max_runtime: None,
+        command,
+    };
+    let daemon = {
+        let socket_path = socket_path.clone();
+        thread::spawn(move || run_daemon(socket_path, true, rows, cols, options, None))
+    };
+
+    // The daemon binds its socket once the child is running
+    while UnixStream::connect(&socket_path).is_err() {
+        if daemon.is_finished() {
+            daemon.join().map_err(|_| anyhow::anyhow!("Session thread panicked"))??;
+            bail!("Session ended before its socket was ready");
+        }
+        thread::sleep(Duration::from_millis(20));
+    }
+
+    let start = std::time::Instant::now();
+    let exit_code = loop {
+        let response = send_request(&socket_path, serde_json::json!({ "type": "STATUS" }))?;
+        let data = response.data.unwrap_or_default();
+        if !data.get("running").and_then(|v| v.as_bool()).unwrap_or(false) {
+            break data.get("exit_code").and_then(|v| v.as_i64()).map(|code| code as i32);
+        }
+        if timeout.is_some_and(|timeout| start.elapsed() >= timeout) {
+            break None;
+        }
+        thread::sleep(Duration::from_millis(50));
+    };
+
+    let response = send_request(&socket_path, serde_json::json!({ "type": "OUTPUT", "format": "ascii" }))?;
+    let screen = response.data.as_ref()
+        .and_then(|data| data.get("screen"))
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .to_string();
+
+    send_request(&socket_path, serde_json::json!({ "type": "STOP" }))?;
+    daemon.join().map_err(|_| anyhow::anyhow!("Session thread panicked"))??;
+
+    print!("{}", trim_trailing_blank_lines(&screen));
+    match exit_code {
+        Some(code) => {
+            eprintln!("Exit code: {}", code);
+            Ok(code)
+        }
+        None => {
+            eprintln!("Timed out after {:.1}s; the command was stopped", timeout.unwrap_or_default().as_secs_f64());
+            Ok(EXEC_TIMEOUT_EXIT_CODE)
+        }
+    }
+}
+
+/// Run a session until it shuts down; `ready` gets a newline once the
+/// socket is listening
+fn run_daemon(socket_path: String, socket_was_auto_generated: bool, rows: u16, cols: u16, options: SessionOptions, ready: Option<fs::File>) -> Result<()> {
+    let SessionOptions { emulator, pty_dump, log, log_input, trace_input, log_merged, debug_raw, scrollback, max_output_bytes, read_chunk_size, read_limit, term, on_exit, keepalive, max_restarts, pass_fds, initial_input, initial_input_delay, prompt_regex, max_runtime, command } = options;
+    let output_limit = OutputLimit::new(max_output_bytes);
+
     // Create PTY
     let winsize = Winsize {
         ws_row: rows,
@@ -506,186 +2080,581 @@ fn run_daemon(socket_path: String, socket_was_auto_generated: bool, rows: u16, c
 
     let pty = openpty(Some(&winsize), None)?;
 
-    // Fork to spawn child in PTY
-    // Use fork crate which provides a safe wrapper around libc::fork()
-    use fork::{fork as safe_fork, Fork};
+    // Children must only inherit the slave as stdin/stdout/stderr
+    set_cloexec(&pty.master)?;
+    set_cloexec(&pty.slave)?;
 
-    match safe_fork() {
-        Ok(Fork::Parent(child)) => {
-            // Close slave side in parent by dropping it (not using close() to avoid double-close)
-            drop(pty.slave);
-
-            // Set master to non-blocking
-            // Use nix's safe fcntl wrapper (requires 'fs' feature)
-            use nix::fcntl::{fcntl, FcntlArg, OFlag};
-
-            let flags = fcntl(pty.master.as_raw_fd(), FcntlArg::F_GETFL)
-                .context("Failed to get PTY flags")?;
-            let mut oflags = OFlag::from_bits_truncate(flags);
-            oflags.insert(OFlag::O_NONBLOCK);
-            fcntl(pty.master.as_raw_fd(), FcntlArg::F_SETFL(oflags))
-                .context("Failed to set PTY non-blocking")?;
-
-            // Open PTY dump file if specified
-            let pty_dump_file = match &pty_dump {
-                Some(path) => Some(std::fs::OpenOptions::new()
-                    .create(true)
-                    .append(true)
-                    .open(path)
-                    .context("Failed to open PTY dump file")?),
-                None => None,
-            };
+    // Set TERM as requested, or based on the terminal emulator backend:
+    // xterm (alacritty) supports full xterm-256color capabilities
+    // custom uses basic ANSI escape sequences
+    let term = term.unwrap_or_else(|| match emulator {
+        Emulator::Xterm => "xterm-256color".to_string(),
+        Emulator::Custom => "ansi".to_string(),
+    });
 
-            // Create state
-            let state = Arc::new(Mutex::new(DaemonState {
-                master_fd: pty.master,
-                child_pid: Pid::from_raw(child),
-                terminal: create_terminal(rows as usize, cols as usize, emulator, scrollback),
-                exit_code: None,
-                socket_path: socket_path.clone(),
-                socket_was_auto_generated,
-                should_shutdown: false,
-                pty_dump: pty_dump_file,
-                activity: false,
-            }));
-
-            // Start PTY reader thread - use poll() for efficient event-driven I/O
-            let state_clone = state.clone();
-            // Dup the fd so the thread owns its own copy for polling
-            let poll_fd = rustix::io::dup(&state.lock().unwrap().master_fd)?;
-            thread::spawn(move || {
-                use rustix::event::{poll, PollFd, PollFlags};
-                let mut pty_closed = false;
-                loop {
-                    if pty_closed {
-                        // PTY closed but child may still be running - poll child status only
-                        let mut state = state_clone.lock().unwrap();
-                        state.check_child_status();
-                        if state.exit_code.is_some() {
-                            break;
-                        }
-                        drop(state);
-                        thread::sleep(Duration::from_millis(100));
-                        continue;
-                    }
+    let (child_pid, exec_error) = spawn_child(&pty.slave, &command, &term, &pass_fds)?;
+
+    let info = SessionInfo {
+        command: command.clone(),
+        started_at: std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0),
+        emulator,
+        cols,
+        rows,
+        term: term.clone(),
+        pty: nix::unistd::ttyname(pty.slave.as_raw_fd()).ok().map(|path| path.to_string_lossy().into_owned()),
+    };
 
-                    // Wait for PTY events using poll()
-                    let mut poll_fds = [PollFd::new(&poll_fd, PollFlags::IN | PollFlags::HUP)];
-                    if poll(&mut poll_fds, None).is_err() {
-                        break;
-                    }
+    // Keep the slave open for restarts; otherwise close it here so the
+    // child holds the only copy
+    let keepalive = if keepalive {
+        Some(Keepalive { slave: pty.slave, command, term, emulator, max_restarts, pass_fds })
+    } else {
+        drop(pty.slave);
+        None
+    };
 
-                    let mut state = state_clone.lock().unwrap();
-                    let revents = poll_fds[0].revents();
-                    if revents.contains(PollFlags::IN) {
-                        state.read_pty_output();
-                    }
-                    if revents.intersects(PollFlags::HUP | PollFlags::ERR) {
-                        state.read_pty_output();
-                        pty_closed = true;
-                    }
-                    state.check_child_status();
-                    if state.exit_code.is_some() {
-                        break;
-                    }
-                }
-            });
+    // Set master to non-blocking
+    // Use nix's safe fcntl wrapper (requires 'fs' feature)
+    use nix::fcntl::{fcntl, FcntlArg, OFlag};
+
+    let flags = fcntl(pty.master.as_raw_fd(), FcntlArg::F_GETFL)
+        .context("Failed to get PTY flags")?;
+    let mut oflags = OFlag::from_bits_truncate(flags);
+    oflags.insert(OFlag::O_NONBLOCK);
+    fcntl(pty.master.as_raw_fd(), FcntlArg::F_SETFL(oflags))
+        .context("Failed to set PTY non-blocking")?;
+
+    // Open PTY dump file if specified
+    let pty_dump_file = match &pty_dump {
+        Some(path) => Some(std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .context("Failed to open PTY dump file")?),
+        None => None,
+    };
 
-            // Create socket and listen
-            let _ = fs::remove_file(&socket_path); // Clean up if exists
-            let listener = UnixListener::bind(&socket_path)?;
+    let session_log = match &log {
+        Some(path) => Some(SessionLog {
+            file: std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .context("Failed to open session log file")?,
+            start: std::time::Instant::now(),
+            log_input,
+            trace_input,
+        }),
+        None => None,
+    };
 
-            // Set socket to non-blocking so we can check shutdown flag
-            listener.set_nonblocking(true)?;
+    let merged_log = match &log_merged {
+        Some(path) => Some(MergedLog {
+            file: std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .context("Failed to open merged log file")?,
+            start: std::time::Instant::now(),
+        }),
+        None => None,
+    };
 
-            // Accept connections
-            loop {
-                // Check if we should exit
-                {
-                    let state_locked = state.lock().unwrap();
-                    if state_locked.should_shutdown {
-                        break;
-                    }
-                }
+    let mut terminal = create_terminal(rows as usize, cols as usize, emulator, scrollback.capacity());
+    if debug_raw {
+        terminal.enable_raw_capture();
+    }
 
-                match listener.accept() {
-                    Ok((stream, _)) => {
-                        // Process commands sequentially - no parallelism
-                        if let Err(e) = handle_client(stream, state.clone()) {
-                            eprintln!("Client handler error: {}", e);
-                        }
-                    }
-                    Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
-                        // No connection available, sleep and try again
-                        thread::sleep(Duration::from_millis(50));
-                    }
-                    Err(e) => {
-                        eprintln!("Connection error: {}", e);
-                    }
+    // Create state
+    let state = Arc::new(Mutex::new(DaemonState {
+        master_fd: pty.master,
+        child_pid,
+        terminal,
+        exit_code: None,
+        stopped: false,
+        socket_path: socket_path.clone(),
+        socket_was_auto_generated,
+        remove_socket: None,
+        should_shutdown: false,
+        pty_dump: pty_dump_file,
+        log: session_log,
+        log_merged: merged_log,
+        raw_output: RawOutputBuffer::new(output_limit.raw_capacity),
+        output_counters: OutputCounters::new(),
+        read_buf: vec![0; read_chunk_size as usize],
+        read_limit: usize::try_from(read_limit).unwrap_or(usize::MAX),
+        scrollback,
+        output_limit,
+        scrollback_dropped: 0,
+        activity: false,
+        ready: false,
+        exec_error,
+        keepalive,
+        restarts: 0,
+        exits: 0,
+        recent_exit_codes: std::collections::VecDeque::new(),
+        info,
+        debug_raw,
+        on_exit,
+        prompt_regex,
+        killed_by: None,
+        pty_hangup: None,
+        degraded: None,
+        initial_input_error: None,
+    }));
+
+    // Start PTY reader thread - use poll() for efficient event-driven I/O
+    let state_clone = state.clone();
+    // Dup the fd so the thread owns its own copy for polling
+    let poll_fd = rustix::io::fcntl_dupfd_cloexec(&lock_state(&state).master_fd, 0)?;
+    // With --keepalive the daemon holds the slave open, so a child exit
+    // never shows up as HUP: wake up regularly to notice it
+    let keepalive = lock_state(&state).keepalive.is_some();
+    // Closed on shutdown to wake the reader thread out of poll()
+    let (shutdown_read, shutdown_write) = rustix::pipe::pipe_with(rustix::pipe::PipeFlags::CLOEXEC)
+        .context("Failed to create shutdown pipe")?;
+    let reader = thread::spawn(move || {
+        let run = std::panic::AssertUnwindSafe(|| read_pty_loop(&state_clone, poll_fd, shutdown_read, keepalive));
+        if let Err(panic) = std::panic::catch_unwind(run) {
+            // Keep serving clients, but say the screen is no longer updated
+            let message = panic.downcast_ref::<&str>().map(|s| s.to_string())
+                .or_else(|| panic.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "unknown panic".to_string());
+            lock_state(&state_clone).degraded = Some(format!("PTY reader thread panicked: {}", message));
+        }
+    });
+
+    if let Some(max_runtime) = max_runtime {
+        let state = state.clone();
+        thread::spawn(move || {
+            thread::sleep(max_runtime);
+            enforce_max_runtime(&state);
+        });
+    }
+
+    // Create socket and listen
+    let _ = fs::remove_file(&socket_path); // Clean up if exists
+    let listener = UnixListener::bind(&socket_path)
+        .with_context(|| format!("Failed to bind socket {}", socket_path))?;
+    if let Some(mut ready) = ready {
+        let _ = ready.write_all(b"\n");
+    }
+
+    // Send --initial-input before the first accept: clients can already
+    // connect, but no client input can arrive ahead of it
+    if let Some(input) = initial_input {
+        thread::sleep(initial_input_delay);
+        send_initial_input(&state, &input);
+    }
+
+    // Set socket to non-blocking so we can check shutdown flag
+    listener.set_nonblocking(true)?;
+
+    // Accept connections
+    loop {
+        // Check if we should exit
+        {
+            let state_locked = lock_state(&state);
+            if state_locked.should_shutdown {
+                break;
+            }
+        }
+
+        match listener.accept() {
+            Ok((stream, _)) => {
+                // Process commands sequentially - no parallelism
+                if let Err(e) = handle_client(stream, state.clone()) {
+                    eprintln!("Client handler error: {}", e);
                 }
             }
+            Err(ref e) if e.kind() == std::io::ErrorKind::WouldBlock => {
+                // No connection available, sleep and try again
+                thread::sleep(Duration::from_millis(50));
+            }
+            Err(e) => {
+                eprintln!("Connection error: {}", e);
+            }
+        }
+    }
+
+    // Give the child STOP_GRACE_PERIOD to exit after the hangup
+    let deadline = std::time::Instant::now() + STOP_GRACE_PERIOD;
+    loop {
+        let mut state_locked = lock_state(&state);
+        state_locked.check_child_status();
+        if state_locked.exit_code.is_some() || std::time::Instant::now() >= deadline {
+            break;
+        }
+        drop(state_locked);
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    // Stop the reader before cleaning up, so it can't be halfway through
+    // processing output while the socket goes away
+    drop(shutdown_write);
+    let _ = reader.join();
+
+    // Cleanup
+    let mut state_locked = lock_state(&state);
+    // The child ignored the hangup: fall back to SIGTERM
+    state_locked.check_child_status();
+    if state_locked.exit_code.is_none() {
+        let _ = kill(state_locked.child_pid, Signal::SIGTERM);
+    }
+    // Close the session logs
+    state_locked.log = None;
+    state_locked.log_merged = None;
+    if state_locked.remove_socket.unwrap_or(state_locked.socket_was_auto_generated) {
+        let _ = fs::remove_file(&state_locked.socket_path);
+        // Also remove the parent directory (the temp dir)
+        if state_locked.socket_was_auto_generated {
+            if let Some(parent) = Path::new(&state_locked.socket_path).parent() {
+                let _ = fs::remove_dir(parent);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Write `start --initial-input`, logged like an INPUT request; a failure
+/// is kept for STATUS, as nobody reads the daemon's stderr
+fn send_initial_input(state: &Mutex<DaemonState>, input: &[u8]) {
+    let mut state = lock_state(state);
+    if let Some(ref mut log) = state.log {
+        log.input("input", input);
+    }
+    let result = state.write_input(input, PTY_WRITE_STALL_TIMEOUT);
+    let written = match result {
+        Ok(written) | Err((written, _)) => written,
+    };
+    if let Some(ref mut log) = state.log {
+        log.trace(input, written);
+    }
+    if let Err((_, e)) = result {
+        state.initial_input_error = Some(e);
+    }
+}
+
+/// Feed PTY output to the emulator until the PTY hangs up and the child
+/// exits, or `shutdown_read` is closed
+fn read_pty_loop(shared: &Mutex<DaemonState>, poll_fd: OwnedFd, shutdown_read: OwnedFd, keepalive: bool) {
+    use rustix::event::{poll, PollFd, PollFlags, Timespec};
+    let poll_timeout = Timespec { tv_sec: 0, tv_nsec: 100_000_000 };
+    let mut pty_closed = false;
+    // Test hook, debug builds only: lets tests check a dead reader is
+    // reported in STATUS
+    #[cfg(debug_assertions)]
+    let panic_on_output = std::env::var_os("INTERMINAI_TEST_READER_PANIC").is_some();
+    loop {
+        if pty_closed {
+            // PTY closed but child may still be running - poll child status only
+            let mut state = lock_state(shared);
+            state.check_child_status();
+            if state.exit_code.is_some() || state.should_shutdown {
+                break;
+            }
+            drop(state);
+            thread::sleep(Duration::from_millis(100));
+            continue;
+        }
+
+        // Wait for PTY events using poll()
+        let mut poll_fds = [
+            PollFd::new(&poll_fd, PollFlags::IN | PollFlags::HUP),
+            PollFd::new(&shutdown_read, PollFlags::IN),
+        ];
+        if poll(&mut poll_fds, keepalive.then_some(&poll_timeout)).is_err() {
+            break;
+        }
+        if !poll_fds[1].revents().is_empty() {
+            break;
+        }
+
+        let mut state = lock_state(shared);
+        let revents = poll_fds[0].revents();
+        let mut more_output = false;
+        if revents.contains(PollFlags::IN) {
+            more_output = state.read_pty_output();
+            #[cfg(debug_assertions)]
+            if panic_on_output {
+                panic!("INTERMINAI_TEST_READER_PANIC is set");
+            }
+        }
+        if revents.intersects(PollFlags::HUP | PollFlags::ERR) {
+            state.drain_pty_output();
+            state.pty_hangup = Some(std::time::Instant::now());
+            pty_closed = true;
+        }
+        drop(state);
+        if lock_state_reaped(shared).exit_code.is_some() {
+            break;
+        }
+        // Output is still pending: let a waiting request take the lock
+        // before locking again
+        if more_output {
+            thread::yield_now();
+        }
+    }
+}
+
+/// How long STOP waits for the child to exit after SIGHUP before sending SIGTERM
+const STOP_GRACE_PERIOD: Duration = Duration::from_millis(200);
+
+/// How long after the PTY hangs up the child is expected to be reaped;
+/// a child that closed its terminal and kept running isn't waited for
+const EXIT_AFTER_HANGUP_GRACE: Duration = Duration::from_millis(50);
+
+/// How long the daemon stays up after `start --max-runtime` killed the
+/// command, so clients can still see why in STATUS
+const MAX_RUNTIME_LINGER: Duration = Duration::from_secs(1);
+
+/// Run from its own thread once `start --max-runtime` has passed: SIGTERM
+/// the command, SIGKILL it after STOP_GRACE_PERIOD, then shut down. Client
+/// requests can block the accept loop (WAIT), so it can't check the deadline.
+/// A command that already exited is left alone, as is the daemon.
+fn enforce_max_runtime(state: &Arc<Mutex<DaemonState>>) {
+    {
+        let mut state = lock_state(state);
+        if state.should_shutdown {
+            return;
+        }
+        state.check_child_status();
+        if state.exit_code.is_some() {
+            return;
+        }
+        state.killed_by = Some("max_runtime");
+        let _ = kill(state.child_pid, Signal::SIGTERM);
+    }
+
+    let deadline = std::time::Instant::now() + STOP_GRACE_PERIOD;
+    loop {
+        let mut state = lock_state(state);
+        state.check_child_status();
+        if state.exit_code.is_some() {
+            break;
+        }
+        if std::time::Instant::now() >= deadline {
+            let _ = kill(state.child_pid, Signal::SIGKILL);
+        }
+        drop(state);
+        thread::sleep(Duration::from_millis(10));
+    }
+
+    thread::sleep(MAX_RUNTIME_LINGER);
+    lock_state(state).should_shutdown = true;
+}
+
+fn set_cloexec(fd: &OwnedFd) -> Result<()> {
+    use nix::fcntl::{fcntl, FcntlArg, FdFlag};
+    fcntl(fd.as_raw_fd(), FcntlArg::F_SETFD(FdFlag::FD_CLOEXEC))
+        .context("Failed to set close-on-exec")?;
+    Ok(())
+}
+
+/// Check that each `start --pass-fd` descriptor is open and not one of
+/// the standard streams, which become the terminal
+fn validate_pass_fds(fds: &[RawFd]) -> Result<()> {
+    use nix::fcntl::{fcntl, FcntlArg};
+    for &fd in fds {
+        if fd <= 2 {
+            bail!("--pass-fd {}: descriptors 0-2 are the terminal; pass 3 or above", fd);
+        }
+        if fcntl(fd, FcntlArg::F_GETFD).is_err() {
+            bail!("--pass-fd {}: not an open file descriptor", fd);
+        }
+    }
+    Ok(())
+}
+
+/// The daemon's open descriptors above 2, listed before forking since
+/// reading /proc allocates, which the child of a threaded process must not
+fn open_fds() -> Vec<RawFd> {
+    match fs::read_dir("/proc/self/fd") {
+        Ok(entries) => entries
+            .filter_map(|entry| entry.ok()?.file_name().to_str()?.parse().ok())
+            .filter(|&fd| fd > 2)
+            .collect(),
+        // Without /proc, try the usual range
+        Err(_) => (3..1024).collect(),
+    }
+}
+
+/// In the forked child: mark every descriptor in `open_fds` close-on-exec,
+/// except `pass_fds`, which are made inheritable. This closes whatever the
+/// daemon inherited from the shell that started it, and backs up the
+/// daemon's own descriptors (master, listener, logs) being opened
+/// close-on-exec, so the child never keeps the socket alive after `stop`.
+/// Only makes fcntl calls, so it is safe between fork and exec.
+fn close_unpassed_fds(open_fds: &[RawFd], pass_fds: &[RawFd]) {
+    use nix::fcntl::{fcntl, FcntlArg, FdFlag};
+    for &fd in open_fds {
+        let flags = if pass_fds.contains(&fd) { FdFlag::empty() } else { FdFlag::FD_CLOEXEC };
+        let _ = fcntl(fd, FcntlArg::F_SETFD(flags));
+    }
+}
+
+/// `command` ready for execve, with TERM set in its environment. Built
+/// before forking: the daemon may be running other threads, so the child
+/// must not allocate or take locks before it execs.
+struct ExecArgs {
+    path: CString,
+    /// Printed if exec fails
+    program: String,
+    _args: Vec<CString>,
+    _env: Vec<CString>,
+    argv: Vec<*const libc::c_char>,
+    envp: Vec<*const libc::c_char>,
+}
+
+impl ExecArgs {
+    fn new(command: &[String], term: &str) -> Result<Self> {
+        use std::os::unix::ffi::{OsStrExt, OsStringExt};
+        let program = command[0].clone();
+        let path = CString::new(resolve_program(&program).into_os_string().into_vec())
+            .context("The command can't contain NUL bytes")?;
+        let args = command.iter()
+            .map(|arg| CString::new(arg.as_bytes()))
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .context("Command arguments can't contain NUL bytes")?;
+        let mut env: Vec<CString> = std::env::vars_os()
+            .filter(|(key, _)| key.as_bytes() != b"TERM")
+            .filter_map(|(key, value)| {
+                let mut entry = key.into_vec();
+                entry.push(b'=');
+                entry.extend(value.into_vec());
+                CString::new(entry).ok()
+            })
+            .collect();
+        env.push(CString::new(format!("TERM={}", term)).context("--term can't contain NUL bytes")?);
+        let argv = args.iter().map(|arg| arg.as_ptr()).chain(std::iter::once(std::ptr::null())).collect();
+        let envp = env.iter().map(|entry| entry.as_ptr()).chain(std::iter::once(std::ptr::null())).collect();
+        Ok(ExecArgs { path, program, _args: args, _env: env, argv, envp })
+    }
+}
+
+/// Where execvp would find `program`: itself if it contains a slash,
+/// otherwise the first executable file of that name on PATH. Falls back
+/// to `program`, so exec fails with the usual "No such file or directory".
+fn resolve_program(program: &str) -> std::path::PathBuf {
+    use std::os::unix::fs::PermissionsExt;
+    if program.contains('/') {
+        return program.into();
+    }
+    let path = std::env::var_os("PATH").unwrap_or_else(|| "/usr/bin:/bin".into());
+    std::env::split_paths(&path)
+        .map(|dir| dir.join(program))
+        .find(|candidate| fs::metadata(candidate).is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0))
+        .unwrap_or_else(|| program.into())
+}
+
+/// The step that failed in the forked child, sent to the parent along
+/// with errno: the child can't format a message itself
+#[derive(Clone, Copy)]
+#[repr(u8)]
+enum ExecFailure {
+    Setsid = 1,
+    Dup2 = 2,
+    Exec = 3,
+}
 
-            // Give time for final requests to complete
-            thread::sleep(Duration::from_millis(200));
+/// Fork and exec `command` in a new session with the PTY slave as its
+/// controlling terminal, keeping `pass_fds` open. Returns the child's pid
+/// and, if it could not exec, why.
+fn spawn_child(slave: &OwnedFd, command: &[String], term: &str, pass_fds: &[RawFd]) -> Result<(Pid, Option<String>)> {
+    let exec = ExecArgs::new(command, term)?;
+    // Close-on-exec pipe: it reads EOF once the child execs, or carries
+    // the failed step and errno if it can't
+    let (exec_err_read, exec_err_write) = rustix::pipe::pipe_with(rustix::pipe::PipeFlags::CLOEXEC)
+        .context("Failed to create exec status pipe")?;
+    let open_fds = open_fds();
 
-            // Cleanup
-            let state_locked = state.lock().unwrap();
-            if state_locked.socket_was_auto_generated {
-                let _ = fs::remove_file(&state_locked.socket_path);
-                // Also remove the parent directory (the temp dir)
-                if let Some(parent) = Path::new(&state_locked.socket_path).parent() {
-                    let _ = fs::remove_dir(parent);
-                }
-            }
+    // Fork to spawn child in PTY
+    // Use fork crate which provides a safe wrapper around libc::fork()
+    use fork::{fork as safe_fork, Fork};
 
-            Ok(())
+    match safe_fork() {
+        Ok(Fork::Parent(child)) => {
+            // Wait for the child to exec (EOF) or report why it couldn't
+            drop(exec_err_write);
+            let mut report = Vec::new();
+            let _ = std::fs::File::from(exec_err_read).read_to_end(&mut report);
+            let exec_error = match report[..] {
+                [step, a, b, c, d] => {
+                    let err = std::io::Error::from_raw_os_error(i32::from_le_bytes([a, b, c, d]));
+                    let message = match step {
+                        s if s == ExecFailure::Setsid as u8 => format!("Failed to create new session: {}", err),
+                        s if s == ExecFailure::Dup2 as u8 => format!("Failed to attach to the terminal: {}", err),
+                        _ => format!("Failed to execute '{}': {}", exec.program, err),
+                    };
+                    // Show it on screen, as the command's own error would be
+                    let _ = nix::unistd::write(slave.as_raw_fd(), format!("interminai: {}\n", message).as_bytes());
+                    Some(message)
+                }
+                _ => None,
+            };
+            Ok((Pid::from_raw(child), exec_error))
         }
         Ok(Fork::Child) => {
-            // Close master side in child by dropping it
-            drop(pty.master);
+            drop(exec_err_read);
 
-            // Create new session - this makes the child a session leader
-            // This is required for the PTY slave to become the controlling terminal
-            setsid().context("Failed to create new session")?;
+            // exec_on_slave only returns on failure: tell the parent
+            let (step, errno) = exec_on_slave(slave, &exec, &open_fds, pass_fds);
+            let mut report = [step as u8, 0, 0, 0, 0];
+            report[1..].copy_from_slice(&errno.to_le_bytes());
+            let _ = rustix::io::write(&exec_err_write, &report);
 
-            // Redirect stdin/stdout/stderr to slave using nix
-            use nix::unistd::dup2;
-            let slave_fd = pty.slave.as_raw_fd();
-            dup2(slave_fd, 0).context("Failed to dup2 stdin")?;
-            dup2(slave_fd, 1).context("Failed to dup2 stdout")?;
-            dup2(slave_fd, 2).context("Failed to dup2 stderr")?;
-
-            // Make the PTY slave the controlling terminal for this session
-            // TIOCSCTTY = "set controlling tty" - this must be done AFTER setsid()
-            // and AFTER making stdin/stdout/stderr point to the slave
-            if let Err(e) = rustix::process::ioctl_tiocsctty(&pty.slave) {
-                // Non-fatal - continue anyway
-                eprintln!("Warning: Failed to set controlling terminal: {}", e);
-            }
+            // Skip atexit handlers and stdio flushing, which aren't safe here
+            unsafe { libc::_exit(1) }
+        }
+        Err(e) => bail!("Failed to fork for child: {}", e),
+    }
+}
 
-            // Drop slave after dup2 (automatically closes it)
-            drop(pty.slave);
+/// In the forked child: attach to the PTY slave and exec the command.
+/// Only returns if that fails, with the failed step and errno. Makes
+/// async-signal-safe calls only.
+fn exec_on_slave(slave: &OwnedFd, exec: &ExecArgs, open_fds: &[RawFd], pass_fds: &[RawFd]) -> (ExecFailure, i32) {
+    // Create new session - this makes the child a session leader
+    // This is required for the PTY slave to become the controlling terminal
+    if let Err(e) = setsid() {
+        return (ExecFailure::Setsid, e as i32);
+    }
 
-            // Set TERM based on the terminal emulator backend
-            // xterm (alacritty) supports full xterm-256color capabilities
-            // custom uses basic ANSI escape sequences
-            match emulator {
-                Emulator::Xterm => std::env::set_var("TERM", "xterm-256color"),
-                Emulator::Custom => std::env::set_var("TERM", "ansi"),
-            }
+    // Redirect stdin/stdout/stderr to slave using nix
+    // (the slave itself is close-on-exec, so only these copies survive exec)
+    use nix::unistd::dup2;
+    let slave_fd = slave.as_raw_fd();
+    for fd in 0..=2 {
+        if let Err(e) = dup2(slave_fd, fd) {
+            return (ExecFailure::Dup2, e as i32);
+        }
+    }
 
-            // Exec command
-            let program = &command[0];
-            let args = &command[1..];
+    // Make the PTY slave the controlling terminal for this session
+    // TIOCSCTTY = "set controlling tty" - this must be done AFTER setsid()
+    // and AFTER making stdin/stdout/stderr point to the slave
+    if rustix::process::ioctl_tiocsctty(slave).is_err() {
+        // Non-fatal - continue anyway
+        let warning = b"Warning: Failed to set controlling terminal\n";
+        unsafe { libc::write(2, warning.as_ptr().cast(), warning.len()) };
+    }
 
-            let _ = ProcessCommand::new(program)
-                .args(args)
-                .exec();
+    close_unpassed_fds(open_fds, pass_fds);
 
-            std::process::exit(1);
-        }
-        Err(e) => bail!("Failed to fork for child: {}", e),
+    // The daemon ignores SIGPIPE; the command gets the default, as
+    // std::process::Command would give it, and no blocked signals
+    unsafe {
+        libc::signal(libc::SIGPIPE, libc::SIG_DFL);
+        let mut empty = std::mem::zeroed();
+        libc::sigemptyset(&mut empty);
+        libc::pthread_sigmask(libc::SIG_SETMASK, &empty, std::ptr::null_mut());
+        libc::execve(exec.path.as_ptr(), exec.argv.as_ptr(), exec.envp.as_ptr());
     }
+    (ExecFailure::Exec, nix::errno::Errno::last() as i32)
 }
 
 fn handle_client(mut stream: UnixStream, state: Arc<Mutex<DaemonState>>) -> Result<()> {
@@ -714,8 +2683,16 @@ fn handle_client(mut stream: UnixStream, state: Arc<Mutex<DaemonState>>) -> Resu
         "STATUS" => handle_running(request.data, &state),
         "WAIT" => handle_wait(request.data.clone(), &state, &stream),
         "KILL" => handle_kill(request.data, &state),
-        "STOP" => handle_stop(&state),
+        "STOP" => handle_stop(request.data, &state),
         "RESIZE" => handle_resize(request.data, &state),
+        "CLEAR" => handle_clear(&state),
+        "SNAPSHOT" => handle_snapshot(&state),
+        "RESTORE" => handle_restore(request.data, &state),
+        "INFO" => handle_info(&state),
+        "CHILDREN" => handle_children(&state),
+        "PING" => Response::ok(protocol::PingResponse {
+            compression: vec![protocol::COMPRESSION_GZIP.to_string()],
+        }),
         "DEBUG" => handle_debug(request.data, &state),
         _ => Response::error(format!("Unknown command: {}", request.req_type)),
     };
@@ -734,53 +2711,183 @@ fn write_response(stream: &mut UnixStream, response: &Response) -> Result<()> {
 }
 
 fn handle_input(data: serde_json::Value, state: &Arc<Mutex<DaemonState>>) -> Response {
-    let input_data = match data.get("data").and_then(|v| v.as_str()) {
-        Some(s) => s,
-        None => return Response::error("Missing 'data' field".to_string()),
+    let eof = data.get("eof").and_then(|v| v.as_bool()).unwrap_or(false);
+    let key = data.get("key").and_then(|v| v.as_str());
+    let echo = data.get("echo").and_then(|v| v.as_bool()).unwrap_or(false);
+    let drain_first = data.get("drain_first").and_then(|v| v.as_bool()).unwrap_or(false);
+    let stall_timeout = match data.get("timeout").map(|v| v.as_f64().ok_or("must be a positive number of seconds").and_then(positive_seconds)) {
+        None => PTY_WRITE_STALL_TIMEOUT,
+        Some(Ok(timeout)) => timeout,
+        Some(Err(e)) => return Response::error(format!("'timeout' {}", e)),
+    };
+    let mouse = match data.get("mouse").map(|v| serde_json::from_value::<MouseEvent>(v.clone())) {
+        Some(Ok(event)) => Some(event),
+        Some(Err(e)) => return Response::error(format!("Invalid 'mouse': {}", e)),
+        None => None,
+    };
+
+    // A break is a line condition rather than bytes, so nothing is written
+    if data.get("break").and_then(|v| v.as_bool()).unwrap_or(false) {
+        let state = lock_state(state);
+        return match tcsendbreak(&state.master_fd, 0) {
+            Ok(()) => Response::ok(protocol::EmptyResponse {}),
+            Err(e @ (nix::errno::Errno::ENOTTY | nix::errno::Errno::ENOSYS | nix::errno::Errno::EOPNOTSUPP)) =>
+                Response::error(format!("Sending a break is not supported on this platform: {}", e)),
+            Err(e) => Response::error(format!("Failed to send break: {}", e)),
+        };
+    }
+
+    // data_b64 carries arbitrary bytes; data is the plain text form
+    let input_data = if eof || key.is_some() || mouse.is_some() {
+        Vec::new()
+    } else if let Some(encoded) = data.get("data_b64").and_then(|v| v.as_str()) {
+        use base64::Engine;
+        match base64::engine::general_purpose::STANDARD.decode(encoded) {
+            Ok(bytes) => bytes,
+            Err(e) => return Response::error(format!("Invalid base64 in 'data_b64': {}", e)),
+        }
+    } else {
+        match data.get("data").and_then(|v| v.as_str()) {
+            Some(s) => s.as_bytes().to_vec(),
+            None => return Response::error("Missing 'data' field".to_string()),
+        }
+    };
+
+    let mut state = lock_state(state);
+
+    // Bring the screen up to date, then forget the output as already seen
+    if drain_first {
+        state.drain_pty_output();
+        state.raw_output.take();
+        state.activity = false;
+    }
+
+    // The program may have remapped VEOF, so ask the PTY rather than assume ^D
+    let input_data = if eof {
+        match tcgetattr(&state.master_fd) {
+            Ok(termios) => match termios.control_chars[SpecialCharacterIndices::VEOF as usize] {
+                0 => return Response::error("EOF character is disabled on the terminal".to_string()),
+                veof => vec![veof],
+            },
+            Err(e) => return Response::error(format!("Failed to read terminal attributes: {}", e)),
+        }
+    } else if let Some(key) = key {
+        let application_cursor_keys = state.terminal.application_cursor_keys();
+        let application_keypad = state.terminal.application_keypad();
+        match key_sequence(key, application_cursor_keys, application_keypad) {
+            Some(bytes) => bytes,
+            None => return Response::error(format!("Unknown key '{}'", key)),
+        }
+    } else if let Some(event) = mouse {
+        match mouse_sequence(&event, state.terminal.mouse_tracking(), state.terminal.sgr_mouse()) {
+            Ok(bytes) => bytes,
+            Err(e) => return Response::error(e),
+        }
+    } else {
+        input_data
     };
 
-    let state = state.lock().unwrap();
+    if let Some(ref mut log) = state.log {
+        log.input("input", &input_data);
+    }
+
+    let result = state.write_input(&input_data, stall_timeout);
+    let written = match result {
+        Ok(written) | Err((written, _)) => written,
+    };
+    if let Some(ref mut log) = state.log {
+        log.trace(&input_data, written);
+    }
+    if let Err((_, e)) = result {
+        return Response::error(e);
+    }
 
-    match nix::unistd::write(state.master_fd.as_raw_fd(), input_data.as_bytes()) {
-        Ok(_) => Response::ok(serde_json::json!({})),
-        Err(e) => Response::error(format!("Failed to write to PTY: {}", e)),
+    if echo {
+        // Show what the program already printed first, so the text lands after it
+        state.read_pty_output();
+        state.terminal.process_bytes(&echo_text(&input_data));
     }
+
+    Response::ok(protocol::EmptyResponse {})
+}
+
+fn gzip(bytes: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+    encoder.write_all(bytes)?;
+    encoder.finish()
+}
+
+fn gunzip(bytes: &[u8]) -> std::io::Result<Vec<u8>> {
+    let mut decoded = Vec::new();
+    flate2::read::GzDecoder::new(bytes).read_to_end(&mut decoded)?;
+    Ok(decoded)
+}
+
+/// Whether the daemon can gzip raw OUTPUT (daemons that predate it list
+/// no compression in their PING answer)
+fn daemon_supports_gzip(socket: &str) -> Result<bool> {
+    let response = send_request(socket, serde_json::json!({ "type": "PING" }))?;
+    let ping: protocol::PingResponse = response.data
+        .map(serde_json::from_value)
+        .transpose()
+        .context("Invalid PING response")?
+        .unwrap_or_default();
+    Ok(ping.compression.iter().any(|c| c == protocol::COMPRESSION_GZIP))
 }
 
 fn handle_output(data: serde_json::Value, state: &Arc<Mutex<DaemonState>>) -> Response {
     let format = data.get("format").and_then(|v| v.as_str()).unwrap_or("ascii");
 
-    let mut state = state.lock().unwrap();
+    let mut state = lock_state(state);
     state.read_pty_output();
 
-    let (rows, cols) = state.terminal.dimensions();
-    let scrollback_available = state.terminal.scrollback_lines();
-    let scrollback_capacity = state.terminal.scrollback_capacity();
+    if format == "raw" {
+        use base64::Engine;
+        // since: read from a stream offset without draining, so each client
+        // can keep its own position
+        let (bytes, dropped) = match data.get("since").and_then(|v| v.as_u64()) {
+            Some(offset) => match state.raw_output.read_since(offset) {
+                Some(read) => read,
+                None => return Response::error(format!(
+                    "Offset {} is past the end of the output ({})", offset, state.raw_output.end())),
+            },
+            None => state.raw_output.take(),
+        };
+        let (data_b64, data_gz) = match data.get("compression").and_then(|v| v.as_str()) {
+            None => (Some(base64::engine::general_purpose::STANDARD.encode(bytes)), None),
+            Some(protocol::COMPRESSION_GZIP) => match gzip(&bytes) {
+                Ok(compressed) => (None, Some(base64::engine::general_purpose::STANDARD.encode(compressed))),
+                Err(e) => return Response::error(format!("Failed to compress output: {}", e)),
+            },
+            Some(other) => return Response::error(format!("Unsupported compression '{}'", other)),
+        };
+        return Response::ok(protocol::RawOutputResponse {
+            data_b64,
+            data_gz,
+            dropped,
+            gap: dropped > 0,
+            offset: state.raw_output.end(),
+        });
+    }
 
     // from: 0/null = boundary (screen line 1), negative = scrollback, "-" = all scrollback
     // to: null = end of screen, 0 = boundary, negative = scrollback
-    let from_val = match data.get("from") {
-        Some(serde_json::Value::String(s)) if s == "-" => -(scrollback_available as i64),
-        Some(v) => v.as_i64().unwrap_or(0),
-        None => 0,
-    };
+    let all_scrollback = matches!(data.get("from"), Some(serde_json::Value::String(s)) if s == "-");
+    let from_val = if all_scrollback { 0 } else { data.get("from").and_then(|v| v.as_i64()).unwrap_or(0) };
     let to_val: Option<i64> = data.get("to").and_then(|v| v.as_i64());
+    let sb_wanted = if all_scrollback { usize::MAX } else { usize::try_from(-from_val).unwrap_or(0) };
 
-    let sb_lines = if from_val < 0 { (-from_val) as usize } else { 0 };
+    // Screen, scrollback, cursor and size from one read, so they can't disagree
+    let snapshot = state.terminal.snapshot(format == "ansi", sb_wanted);
+    let (rows, cols) = snapshot.size;
+    let scrollback_available = snapshot.scrollback_available;
+    let scrollback_capacity = state.scrollback_capacity();
 
-    let screen_text = match format {
-        "ansi" => state.terminal.get_screen_content_ansi(),
-        _ => state.terminal.get_screen_content(),
-    };
+    let from_val = if all_scrollback { -(scrollback_available as i64) } else { from_val };
+    let sb_lines = if from_val < 0 { (-from_val) as usize } else { 0 };
 
-    let scrollback_text = if sb_lines > 0 {
-        match format {
-            "ansi" => state.terminal.get_scrollback_content_ansi(sb_lines),
-            _ => state.terminal.get_scrollback_content(sb_lines),
-        }
-    } else {
-        String::new()
-    };
+    let screen_text = snapshot.screen;
+    let scrollback_text = snapshot.scrollback;
 
     let combined = if scrollback_text.is_empty() {
         screen_text
@@ -807,7 +2914,7 @@ fn handle_output(data: serde_json::Value, state: &Arc<Mutex<DaemonState>>) -> Re
         combined
     };
 
-    let (cursor_row, cursor_col) = state.terminal.cursor_position();
+    let (cursor_row, cursor_col) = snapshot.cursor;
 
     // Compute effective from/to for response
     let effective_from = if from_val < 0 {
@@ -821,71 +2928,85 @@ fn handle_output(data: serde_json::Value, state: &Arc<Mutex<DaemonState>>) -> Re
         rows as i64
     };
 
-    let data = serde_json::json!({
-        "screen": combined,
-        "cursor": {
-            "row": cursor_row,
-            "col": cursor_col
-        },
-        "size": {
-            "rows": rows,
-            "cols": cols
-        },
-        "from": effective_from,
-        "to": effective_to,
-        "scrollback_available": scrollback_available,
-        "scrollback_capacity": scrollback_capacity
-    });
-
-    Response::ok(data)
+    Response::ok(protocol::OutputResponse {
+        screen: combined,
+        cursor: protocol::Cursor { row: cursor_row, col: cursor_col },
+        cursor_style: snapshot.cursor_style,
+        size: protocol::Size { rows, cols },
+        from: effective_from,
+        to: effective_to,
+        scrollback_available,
+        scrollback_capacity,
+        generation: snapshot.generation,
+        alt_screen: snapshot.alt_screen,
+        parser_in_escape: snapshot.parser_in_escape,
+        wrapped_lines: state.terminal.take_wrapped_lines(),
+        truncated: state.terminal.take_margin_truncated(),
+        links: state.terminal.hyperlinks(),
+        rows: (format == "jsonl").then_some(snapshot.rows),
+    })
 }
 
 fn handle_running(data: serde_json::Value, state: &Arc<Mutex<DaemonState>>) -> Response {
     let activity_mode = data.get("activity").and_then(|v| v.as_bool()).unwrap_or(false);
-    let mut state = state.lock().unwrap();
-    state.check_child_status();
+    let mut state = lock_state_reaped(state);
 
     let running = state.exit_code.is_none();
-    let (rows, cols) = state.terminal.dimensions();
-    let scrollback_available = state.terminal.scrollback_lines();
-    let scrollback_capacity = state.terminal.scrollback_capacity();
+    let snapshot = state.terminal.snapshot(false, 0);
+    let (rows, cols) = snapshot.size;
+    let scrollback_capacity = state.scrollback_capacity();
+
+    let mut response = protocol::StatusResponse {
+        running,
+        stopped: state.stopped,
+        ready: state.ready,
+        alt_screen: snapshot.alt_screen,
+        parser_in_escape: snapshot.parser_in_escape,
+        cursor_style: snapshot.cursor_style,
+        size: protocol::Size { rows, cols },
+        scrollback_available: snapshot.scrollback_available,
+        scrollback_capacity,
+        scrollback_dropped: state.scrollback_dropped,
+        scrollback_policy: state.scrollback,
+        modes: protocol::Modes {
+            bracketed_paste: state.terminal.bracketed_paste(),
+            focus_reporting: state.terminal.focus_reporting(),
+            application_cursor_keys: state.terminal.application_cursor_keys(),
+            application_keypad: state.terminal.application_keypad(),
+            mouse_tracking: state.terminal.mouse_tracking(),
+            sgr_mouse: state.terminal.sgr_mouse(),
+        },
+        activity: None,
+        bell_count: None,
+        exit_code: state.exit_code,
+        restarts: state.keepalive.is_some().then_some(state.restarts),
+        exec_failed: state.exec_error.is_some().then_some(true),
+        exec_error: state.exec_error.clone(),
+        killed_by: state.killed_by.map(str::to_string),
+        degraded: state.degraded.clone(),
+        initial_input_error: state.initial_input_error.clone(),
+    };
 
     if activity_mode {
-        let activity = state.activity;
+        response.activity = Some(state.activity);
         state.activity = false;  // Clear the flag after reading
-        let mut response = serde_json::json!({
-            "running": running,
-            "activity": activity,
-            "size": { "rows": rows, "cols": cols },
-            "scrollback_available": scrollback_available,
-            "scrollback_capacity": scrollback_capacity
-        });
-        if let Some(exit_code) = state.exit_code {
-            response["exit_code"] = serde_json::json!(exit_code);
-        }
-        Response::ok(response)
-    } else if let Some(exit_code) = state.exit_code {
-        Response::ok(serde_json::json!({
-            "running": false,
-            "exit_code": exit_code,
-            "size": { "rows": rows, "cols": cols },
-            "scrollback_available": scrollback_available,
-            "scrollback_capacity": scrollback_capacity
-        }))
-    } else {
-        Response::ok(serde_json::json!({
-            "running": true,
-            "size": { "rows": rows, "cols": cols },
-            "scrollback_available": scrollback_available,
-            "scrollback_capacity": scrollback_capacity
-        }))
+        response.bell_count = Some(state.terminal.take_bell_count());
     }
+
+    Response::ok(response)
 }
 
 fn handle_wait(data: serde_json::Value, state: &Arc<Mutex<DaemonState>>, stream: &UnixStream) -> Response {
     use rustix::net::{recv, RecvFlags};
 
     let activity_mode = data.get("activity").and_then(|v| v.as_bool()).unwrap_or(false);
+    let expected_exit = data.get("exit_code").and_then(|v| v.as_i64());
+    let ready_mode = data.get("ready").and_then(|v| v.as_bool()).unwrap_or(false);
+    if ready_mode && lock_state(state).prompt_regex.is_none() {
+        return Response::error("No prompt regex: start the session with --prompt-regex".to_string());
+    }
+    // With --keepalive, only exits after this request count
+    let mut exits_seen = lock_state(state).exits;
 
     loop {
         // Check if client disconnected using recv with MSG_PEEK | MSG_DONTWAIT
@@ -909,10 +3030,53 @@ fn handle_wait(data: serde_json::Value, state: &Arc<Mutex<DaemonState>>, stream:
         }
 
         {
-            let mut state = state.lock().unwrap();
-            state.check_child_status();
-
-            if activity_mode {
+            let mut state = lock_state_reaped(state);
+
+            if ready_mode {
+                // The prompt wins over an exit: it is on screen either way
+                if let Some((row, col)) = state.prompt_cursor() {
+                    return Response::ok(protocol::WaitResponse {
+                        activity: None,
+                        exited: None,
+                        exit_code: None,
+                        matched: Some(true),
+                        cursor: Some(protocol::Cursor { row, col }),
+                    });
+                }
+                if let Some(exit_code) = state.exit_code {
+                    return Response::ok(protocol::WaitResponse {
+                        activity: None,
+                        exited: Some(true),
+                        exit_code: Some(exit_code),
+                        matched: Some(false),
+                        cursor: None,
+                    });
+                }
+            } else if let Some(expected) = expected_exit {
+                // Still set after check_child_status: the command won't be restarted
+                if let Some(exit_code) = state.exit_code {
+                    return Response::ok(protocol::WaitResponse {
+                        activity: None,
+                        exited: None,
+                        exit_code: Some(exit_code),
+                        matched: Some(i64::from(exit_code) == expected),
+                        cursor: None,
+                    });
+                }
+                // Restarted by --keepalive: succeed on the wanted code, or wait for the next exit
+                let new_exits = usize::try_from(state.exits - exits_seen).unwrap_or(usize::MAX);
+                let skip = state.recent_exit_codes.len().saturating_sub(new_exits);
+                if let Some(&exit_code) = state.recent_exit_codes.iter().skip(skip).find(|&&code| i64::from(code) == expected) {
+                    return Response::ok(protocol::WaitResponse {
+                        activity: None,
+                        exited: None,
+                        exit_code: Some(exit_code),
+                        matched: Some(true),
+                        cursor: None,
+                    });
+                }
+                exits_seen = state.exits;
+            } else if activity_mode {
                 // Activity mode: return as soon as activity or exit is detected
                 // Get separate flags for PTY activity vs process exit
                 let pty_activity = state.activity;
@@ -920,17 +3084,24 @@ fn handle_wait(data: serde_json::Value, state: &Arc<Mutex<DaemonState>>, stream:
                 if pty_activity || exited {
                     // Clear the PTY activity flag
                     state.activity = false;
-                    return Response::ok(serde_json::json!({
-                        "activity": pty_activity,
-                        "exited": exited
-                    }));
+                    return Response::ok(protocol::WaitResponse {
+                        activity: Some(pty_activity),
+                        exited: Some(exited),
+                        exit_code: state.exit_code,
+                        matched: None,
+                        cursor: None,
+                    });
                 }
             } else {
                 // Normal mode: wait for exit
                 if let Some(exit_code) = state.exit_code {
-                    return Response::ok(serde_json::json!({
-                        "exit_code": exit_code
-                    }));
+                    return Response::ok(protocol::WaitResponse {
+                        activity: None,
+                        exited: None,
+                        exit_code: Some(exit_code),
+                        matched: None,
+                        cursor: None,
+                    });
                 }
             }
         }
@@ -950,44 +3121,69 @@ fn handle_kill(data: serde_json::Value, state: &Arc<Mutex<DaemonState>>) -> Resp
         Err(e) => return Response::error(format!("Invalid signal: {}", e)),
     };
 
-    let state = state.lock().unwrap();
+    let state = lock_state(state);
 
     match kill(state.child_pid, signal) {
-        Ok(_) => Response::ok(serde_json::json!({
-            "signal_sent": signal_str
-        })),
+        Ok(_) => Response::ok(protocol::KillResponse {
+            signal_sent: signal_str.to_string(),
+        }),
         Err(e) => Response::error(format!("Failed to send signal: {}", e)),
     }
 }
 
-fn handle_stop(state: &Arc<Mutex<DaemonState>>) -> Response {
-    let mut state = state.lock().unwrap();
+fn handle_stop(data: serde_json::Value, state: &Arc<Mutex<DaemonState>>) -> Response {
+    let mut state = lock_state(state);
+    state.remove_socket = data.get("remove_socket").and_then(|v| v.as_bool());
 
-    // Kill child if still running
+    // Hang up the terminal like a real one would: SIGHUP the foreground
+    // process group and the session leader (a shell forwards it to its jobs).
+    // Anything still running after the grace period gets SIGTERM on exit.
+    state.check_child_status();
     if state.exit_code.is_none() {
-        let _ = kill(state.child_pid, Signal::SIGTERM);
+        if let Ok(pgrp) = nix::unistd::tcgetpgrp(state.master_fd.as_raw_fd()) {
+            let _ = killpg(pgrp, Signal::SIGHUP);
+        }
+        let _ = kill(state.child_pid, Signal::SIGHUP);
     }
 
     // Set shutdown flag to exit daemon loop
     state.should_shutdown = true;
 
-    Response::ok(serde_json::json!({
-        "message": "Shutting down"
-    }))
+    Response::ok(protocol::StopResponse {
+        message: "Shutting down".to_string(),
+    })
 }
 
 fn handle_resize(data: serde_json::Value, state: &Arc<Mutex<DaemonState>>) -> Response {
     let cols = match data.get("cols").and_then(|v| v.as_u64()) {
-        Some(c) => c as u16,
+        Some(c) => c,
         None => return Response::error("Missing 'cols' field".to_string()),
     };
 
     let rows = match data.get("rows").and_then(|v| v.as_u64()) {
-        Some(r) => r as u16,
+        Some(r) => r,
         None => return Response::error("Missing 'rows' field".to_string()),
     };
 
-    let mut state = state.lock().unwrap();
+    // The emulators can't hold a screen without cells
+    let (cols, rows) = match (u16::try_from(cols), u16::try_from(rows)) {
+        (Ok(cols), Ok(rows)) if cols > 0 && rows > 0 => (cols, rows),
+        _ => return Response::error(format!("Invalid size {}x{}: cols and rows must be 1-65535", cols, rows)),
+    };
+
+    let allow_large = data.get("allow_large").and_then(|v| v.as_bool()).unwrap_or(false);
+    if !allow_large && exceeds_size_limit(cols, rows) {
+        return Response::error(format!("Size {}x{} is larger than {}x{} (set allow_large to allow it)",
+            cols, rows, MAX_COLS, MAX_ROWS));
+    }
+
+    let reflow = data.get("reflow").and_then(|v| v.as_bool()).unwrap_or(false);
+
+    let mut state = lock_state(state);
+    let (old_rows, old_cols) = state.terminal.dimensions();
+    if reflow && !state.terminal.reflows_on_resize() {
+        return Response::error("Reflow is only supported with --emulator xterm".to_string());
+    }
 
     // Send TIOCSWINSZ to update terminal size using rustix's safe wrapper
     use rustix::termios::{tcsetwinsize, Winsize as RustixWinsize};
@@ -999,25 +3195,263 @@ fn handle_resize(data: serde_json::Value, state: &Arc<Mutex<DaemonState>>) -> Re
         ws_ypixel: 0,
     };
 
-    if let Err(_) = tcsetwinsize(&state.master_fd, winsize) {
+    if tcsetwinsize(&state.master_fd, winsize).is_err() {
         return Response::error("Failed to resize terminal".to_string());
     }
 
+    // Rewrap at the old height first, so rows pushed out or pulled back by
+    // the height change aren't counted as moved by the reflow
+    let history_moved = if reflow {
+        let old_history = state.terminal.scrollback_lines();
+        state.terminal.resize(old_rows, cols as usize);
+        let new_history = state.terminal.scrollback_lines();
+        // A full scrollback drops its oldest rows, so the count would be wrong
+        (new_history < state.terminal.scrollback_capacity())
+            .then(|| new_history as i64 - old_history as i64)
+    } else {
+        None
+    };
+
     // Update terminal emulator dimensions
     state.terminal.resize(rows as usize, cols as usize);
 
-    Response::ok(serde_json::json!({
-        "cols": cols,
-        "rows": rows
-    }))
+    // TIOCSWINSZ normally raises SIGWINCH in the foreground process group,
+    // but signal the child directly too so it reliably redraws
+    state.check_child_status();
+    if state.exit_code.is_none() {
+        let _ = kill(state.child_pid, Signal::SIGWINCH);
+    }
+
+    Response::ok(protocol::ResizeResponse { cols, rows, old_cols, old_rows, history_moved })
+}
+
+fn handle_clear(state: &Arc<Mutex<DaemonState>>) -> Response {
+    let mut state = lock_state(state);
+    // Process pending output first so it doesn't reappear after the clear
+    state.read_pty_output();
+    state.terminal.clear();
+
+    Response::ok(protocol::EmptyResponse {})
+}
+
+/// The view SNAPSHOT captures and RESTORE redraws; the program is not
+/// part of it. Clients get it as base64 JSON and treat it as opaque
+#[derive(Serialize, Deserialize)]
+struct SavedScreen {
+    rows: usize,
+    cols: usize,
+    /// Oldest first, with ANSI colors, one entry per line
+    scrollback: Vec<String>,
+    /// Exactly `rows` lines, with ANSI colors
+    screen: Vec<String>,
+    cursor: protocol::Cursor,
+    cursor_style: terminal::CursorStyle,
+    modes: protocol::Modes,
+    /// DECAWM
+    #[serde(default = "default_autowrap")]
+    autowrap: bool,
+    /// DECOM
+    #[serde(default)]
+    origin_mode: bool,
+    /// Taken on the alternate screen, whose hidden main screen isn't saved
+    #[serde(default)]
+    alt_screen: bool,
+}
+
+fn default_autowrap() -> bool {
+    true
+}
+
+impl SavedScreen {
+    /// Bytes that draw this view on a fresh terminal of the same size: the
+    /// lines (the first ones scroll into scrollback), then cursor and modes
+    fn redraw_bytes(&self) -> Vec<u8> {
+        let lines: Vec<&str> = self.scrollback.iter().chain(&self.screen).map(String::as_str).collect();
+        let mut out = lines.join("\r\n");
+        out.push_str("\x1b[0m");
+        // DECOM homes the cursor, so it goes before the cursor is placed; with
+        // no scroll region the position means the same either way
+        if self.origin_mode {
+            out.push_str("\x1b[?6h");
+        }
+        out.push_str(&format!("\x1b[{};{}H", self.cursor.row + 1, self.cursor.col + 1));
+        let modes = &self.modes;
+        let private_modes = [
+            (modes.application_cursor_keys, 1),
+            (modes.mouse_tracking == MouseTracking::Click, 1000),
+            (modes.mouse_tracking == MouseTracking::Drag, 1002),
+            (modes.mouse_tracking == MouseTracking::Motion, 1003),
+            (modes.focus_reporting, 1004),
+            (modes.sgr_mouse, 1006),
+            (modes.bracketed_paste, 2004),
+        ];
+        for (_, mode) in private_modes.iter().filter(|(on, _)| *on) {
+            out.push_str(&format!("\x1b[?{}h", mode));
+        }
+        if !self.autowrap {
+            out.push_str("\x1b[?7l");
+        }
+        if modes.application_keypad {
+            out.push_str("\x1b=");
+        }
+        out.push_str(&format!("\x1b[{} q", self.cursor_style.decscusr()));
+        out.into_bytes()
+    }
+}
+
+fn handle_snapshot(state: &Arc<Mutex<DaemonState>>) -> Response {
+    use base64::Engine;
+    let mut state = lock_state(state);
+    state.read_pty_output();
+
+    let terminal = &state.terminal;
+    let (rows, cols) = terminal.dimensions();
+    let (row, col) = terminal.cursor_position();
+    let mut screen: Vec<String> = terminal.get_screen_content_ansi().lines().map(str::to_string).collect();
+    screen.resize(rows, String::new());
+    let saved = SavedScreen {
+        rows,
+        cols,
+        scrollback: terminal.get_scrollback_content_ansi(terminal.scrollback_lines()).lines().map(str::to_string).collect(),
+        screen,
+        cursor: protocol::Cursor { row, col },
+        cursor_style: terminal.cursor_style(),
+        modes: protocol::Modes {
+            bracketed_paste: terminal.bracketed_paste(),
+            focus_reporting: terminal.focus_reporting(),
+            application_cursor_keys: terminal.application_cursor_keys(),
+            application_keypad: terminal.application_keypad(),
+            mouse_tracking: terminal.mouse_tracking(),
+            sgr_mouse: terminal.sgr_mouse(),
+        },
+        autowrap: terminal.autowrap(),
+        origin_mode: terminal.origin_mode(),
+        alt_screen: terminal.alt_screen(),
+    };
+    match serde_json::to_vec(&saved) {
+        Ok(json) => Response::ok(protocol::SnapshotResponse {
+            snapshot: base64::engine::general_purpose::STANDARD.encode(json),
+        }),
+        Err(e) => Response::error(format!("Failed to serialize snapshot: {}", e)),
+    }
+}
+
+fn handle_restore(data: serde_json::Value, state: &Arc<Mutex<DaemonState>>) -> Response {
+    use base64::Engine;
+    let Some(encoded) = data.get("snapshot").and_then(|v| v.as_str()) else {
+        return Response::error("Missing 'snapshot' field".to_string());
+    };
+    let saved: SavedScreen = match base64::engine::general_purpose::STANDARD.decode(encoded.trim())
+        .map_err(|e| e.to_string())
+        .and_then(|json| serde_json::from_slice(&json).map_err(|e| e.to_string()))
+    {
+        Ok(saved) => saved,
+        Err(e) => return Response::error(format!("Invalid snapshot: {}", e)),
+    };
+    // The main screen the program returns to isn't in the snapshot, so a
+    // restored alternate screen would leave it blank
+    if saved.alt_screen {
+        return Response::error("Snapshot was taken on the alternate screen, which can't be restored".to_string());
+    }
+
+    let mut state = lock_state(state);
+    // Output that arrived before the restore belongs to the view it replaces
+    state.read_pty_output();
+    let (rows, cols) = state.terminal.dimensions();
+    if (rows, cols) != (saved.rows, saved.cols) {
+        return Response::error(format!(
+            "Snapshot is {}x{} but the terminal is now {}x{}; resize it first",
+            saved.cols, saved.rows, cols, rows));
+    }
+
+    let mut terminal = create_terminal(rows, cols, state.info.emulator, state.scrollback.capacity());
+    if state.debug_raw {
+        terminal.enable_raw_capture();
+    }
+    terminal.process_bytes(&saved.redraw_bytes());
+    // Replies to the redraw would reach the program, which never asked
+    terminal.take_pending_responses();
+    state.terminal = terminal;
+
+    Response::ok(protocol::EmptyResponse {})
+}
+
+fn handle_info(state: &Arc<Mutex<DaemonState>>) -> Response {
+    let state = lock_state(state);
+    let info = &state.info;
+    let emulator = match info.emulator {
+        Emulator::Xterm => "xterm",
+        Emulator::Custom => "custom",
+    };
+
+    Response::ok(protocol::InfoResponse {
+        command: info.command.clone(),
+        started_at: info.started_at,
+        emulator: emulator.to_string(),
+        size: protocol::Size { rows: info.rows as usize, cols: info.cols as usize },
+        term: info.term.clone(),
+        scrollback: state.scrollback,
+        socket: state.socket_path.clone(),
+        pty: info.pty.clone(),
+    })
+}
+
+fn handle_children(state: &Arc<Mutex<DaemonState>>) -> Response {
+    // The child called setsid(), so its PID is the session ID
+    let session = lock_state(state).child_pid.as_raw();
+    match session_processes(session) {
+        Ok(processes) => Response::ok(protocol::ChildrenResponse { processes }),
+        Err(e) => Response::error(format!("Failed to list processes: {}", e)),
+    }
+}
+
+/// Processes in session `session`, from /proc (Linux only)
+fn session_processes(session: i32) -> std::io::Result<Vec<protocol::ProcessInfo>> {
+    let mut processes = Vec::new();
+    for entry in std::fs::read_dir("/proc")? {
+        let Ok(pid) = entry?.file_name().to_string_lossy().parse::<i32>() else { continue };
+        // The process may exit while we look; skip it then
+        let Ok(stat) = std::fs::read_to_string(format!("/proc/{}/stat", pid)) else { continue };
+        // pid (comm) state ppid pgrp session tty_nr tpgid ...; comm may
+        // contain spaces and parentheses, so split at the last ')'
+        let Some((head, tail)) = stat.rsplit_once(')') else { continue };
+        let name = head.split_once('(').map(|(_, name)| name).unwrap_or("");
+        let fields: Vec<i32> = tail.split_whitespace().skip(1).take(5).map(|f| f.parse().unwrap_or(0)).collect();
+        let [ppid, pgrp, sid, _tty, tpgid] = fields[..] else { continue };
+        if sid != session {
+            continue;
+        }
+        let cmdline = std::fs::read(format!("/proc/{}/cmdline", pid)).unwrap_or_default();
+        let mut command: Vec<String> = cmdline.split(|&b| b == 0)
+            .filter(|arg| !arg.is_empty())
+            .map(|arg| String::from_utf8_lossy(arg).into_owned())
+            .collect();
+        if command.is_empty() {
+            command.push(format!("[{}]", name));
+        }
+        processes.push(protocol::ProcessInfo { pid, ppid, foreground: pgrp == tpgid, command });
+    }
+    processes.sort_by_key(|process| (process.pid != session, process.pid));
+    Ok(processes)
 }
 
 fn handle_debug(data: serde_json::Value, state: &Arc<Mutex<DaemonState>>) -> Response {
     let clear = data.get("clear").and_then(|v| v.as_bool()).unwrap_or(false);
+    let raw = data.get("raw").and_then(|v| v.as_bool()).unwrap_or(false);
+    let sgr = data.get("sgr").and_then(|v| v.as_bool()).unwrap_or(false);
 
-    let mut state = state.lock().unwrap();
+    let mut state = lock_state(state);
+    if raw && !state.debug_raw {
+        return Response::error("Raw capture is off; start the session with --debug-raw".to_string());
+    }
+    state.read_pty_output();
 
-    let entries = state.terminal.get_debug_entries();
+    let mut entries = state.terminal.get_debug_entries();
+    if !raw {
+        for entry in &mut entries {
+            entry.original_hex = None;
+        }
+    }
     let dropped = state.terminal.get_debug_dropped();
 
     if clear {
@@ -1037,17 +3471,17 @@ fn handle_debug(data: serde_json::Value, state: &Arc<Mutex<DaemonState>>) -> Res
             let mode = if is_canonical { "cooked" } else { "raw" };
 
             // Collect active flags
-            let mut flags = Vec::new();
-            if lflags.contains(LocalFlags::ECHO) { flags.push("ECHO"); }
-            if lflags.contains(LocalFlags::ISIG) { flags.push("ISIG"); }
-            if lflags.contains(LocalFlags::IEXTEN) { flags.push("IEXTEN"); }
-            if iflags.contains(InputFlags::ICRNL) { flags.push("ICRNL"); }
-            if iflags.contains(InputFlags::INLCR) { flags.push("INLCR"); }
-            if iflags.contains(InputFlags::IGNCR) { flags.push("IGNCR"); }
-            if iflags.contains(InputFlags::IXON) { flags.push("IXON"); }
-            if iflags.contains(InputFlags::IXOFF) { flags.push("IXOFF"); }
-            if oflags.contains(OutputFlags::OPOST) { flags.push("OPOST"); }
-            if oflags.contains(OutputFlags::ONLCR) { flags.push("ONLCR"); }
+            let mut flags: Vec<String> = Vec::new();
+            if lflags.contains(LocalFlags::ECHO) { flags.push("ECHO".to_string()); }
+            if lflags.contains(LocalFlags::ISIG) { flags.push("ISIG".to_string()); }
+            if lflags.contains(LocalFlags::IEXTEN) { flags.push("IEXTEN".to_string()); }
+            if iflags.contains(InputFlags::ICRNL) { flags.push("ICRNL".to_string()); }
+            if iflags.contains(InputFlags::INLCR) { flags.push("INLCR".to_string()); }
+            if iflags.contains(InputFlags::IGNCR) { flags.push("IGNCR".to_string()); }
+            if iflags.contains(InputFlags::IXON) { flags.push("IXON".to_string()); }
+            if iflags.contains(InputFlags::IXOFF) { flags.push("IXOFF".to_string()); }
+            if oflags.contains(OutputFlags::OPOST) { flags.push("OPOST".to_string()); }
+            if oflags.contains(OutputFlags::ONLCR) { flags.push("ONLCR".to_string()); }
 
             // Raw hex values
             let iflag_raw = iflags.bits();
@@ -1079,198 +3513,175 @@ fn handle_debug(data: serde_json::Value, state: &Arc<Mutex<DaemonState>>) -> Res
             let vsusp = decode_cc(c_cc[SpecialCharacterIndices::VSUSP as usize]);
             let vquit = decode_cc(c_cc[SpecialCharacterIndices::VQUIT as usize]);
 
-            serde_json::json!({
-                "mode": mode,
-                "flags": flags,
-                "hex": {
-                    "iflag": format!("0x{:04x}", iflag_raw),
-                    "oflag": format!("0x{:04x}", oflag_raw),
-                    "lflag": format!("0x{:04x}", lflag_raw),
-                    "cflag": format!("0x{:04x}", cflag_raw)
+            protocol::TermiosInfo::Settings(Box::new(protocol::TermiosSettings {
+                mode: mode.to_string(),
+                flags,
+                hex: protocol::TermiosHex {
+                    iflag: format!("0x{:04x}", iflag_raw),
+                    oflag: format!("0x{:04x}", oflag_raw),
+                    lflag: format!("0x{:04x}", lflag_raw),
+                    cflag: format!("0x{:04x}", cflag_raw),
                 },
-                "c_cc": {
-                    "VINTR": vintr,
-                    "VEOF": veof,
-                    "VERASE": verase,
-                    "VKILL": vkill,
-                    "VSUSP": vsusp,
-                    "VQUIT": vquit
-                }
-            })
-        }
-        Err(e) => {
-            serde_json::json!({
-                "error": format!("Failed to get termios: {}", e)
-            })
-        }
-    };
-
-    Response::ok(serde_json::json!({
-        "unhandled": entries,
-        "dropped": dropped,
-        "termios": termios_info
-    }))
-}
-
-fn apply_cursor_inverse(screen: &str, cursor_row: usize, cursor_col: usize) -> String {
-    let lines: Vec<&str> = screen.lines().collect();
-
-    // Check if cursor_row is valid
-    if cursor_row >= lines.len() {
-        return screen.to_string();
-    }
-
-    let mut result = String::new();
-
-    for (row_idx, line) in lines.iter().enumerate() {
-        if row_idx == cursor_row {
-            let chars: Vec<char> = line.chars().collect();
-
-            // Check if cursor_col is valid
-            if cursor_col >= chars.len() {
-                result.push_str(line);
-            } else {
-                // Build the line with inverse video at cursor position
-                for (col_idx, ch) in chars.iter().enumerate() {
-                    if col_idx == cursor_col {
-                        result.push_str("\x1b[7m"); // Start inverse video
-                        result.push(*ch);
-                        result.push_str("\x1b[27m"); // End inverse video
-                    } else {
-                        result.push(*ch);
-                    }
-                }
-            }
-        } else {
-            result.push_str(line);
-        }
-
-        if row_idx < lines.len() - 1 {
-            result.push('\n');
+                c_cc: protocol::ControlChars { vintr, veof, verase, vkill, vsusp, vquit },
+            }))
         }
-    }
-
-    result
-}
-
-fn send_request(socket_path: &str, request: serde_json::Value) -> Result<Response> {
-    let mut stream = UnixStream::connect(socket_path)
-        .context("Failed to connect to daemon socket")?;
-
-    let json = serde_json::to_string(&request)?;
-    stream.write_all(json.as_bytes())?;
-    stream.write_all(b"\n")?;
-    stream.flush()?;
-
-    let mut reader = BufReader::new(stream);
-    let mut line = String::new();
-    reader.read_line(&mut line)?;
-
-    let response: Response = serde_json::from_str(&line)?;
-    Ok(response)
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-
-    #[test]
-    fn test_apply_cursor_inverse_basic() {
-        let screen = "Hello World\nSecond Line";
-        let result = apply_cursor_inverse(screen, 0, 6);
-
-        // Should have inverse codes around character at position 6 (the 'W')
-        assert!(result.contains("\x1b[7mW\x1b[27m"), "Should wrap 'W' with inverse codes");
-        assert!(result.contains("Hello"));
-        assert!(result.contains("orld")); // After the wrapped W
-        assert!(result.contains("Second Line"));
-    }
-
-    #[test]
-    fn test_apply_cursor_inverse_first_char() {
-        let screen = "Test";
-        let result = apply_cursor_inverse(screen, 0, 0);
-
-        // Should start with inverse code
-        assert!(result.starts_with("\x1b[7m"));
-        assert!(result.contains("\x1b[27m"));
-    }
+        Err(e) => protocol::TermiosInfo::Error {
+            error: format!("Failed to get termios: {}", e),
+        },
+    };
 
-    #[test]
-    fn test_apply_cursor_inverse_multiline() {
-        let screen = "Line 1\nLine 2\nLine 3";
-        let result = apply_cursor_inverse(screen, 1, 5);
+    Response::ok(protocol::DebugResponse {
+        unhandled: entries,
+        dropped,
+        bytes_read: state.output_counters.bytes_read,
+        csi_count: state.output_counters.csi_count,
+        esc_count: state.output_counters.esc_count,
+        print_count: state.output_counters.print_count,
+        recent_sgr: if sgr { state.output_counters.recent_sgr.iter().cloned().collect() } else { Vec::new() },
+        termios: termios_info,
+    })
+}
 
-        // Should have all lines
-        assert!(result.contains("Line 1"));
-        assert!(result.contains("Line ")); // Before wrapped character
-        assert!(result.contains("Line 3"));
+fn apply_cursor_inverse(screen: &str, cursor_row: usize, cursor_col: usize) -> String {
+    let lines: Vec<&str> = screen.lines().collect();
 
-        // Should have inverse codes wrapping character at position 5 of line 1 (the '2')
-        assert!(result.contains("\x1b[7m2\x1b[27m"));
+    // Check if cursor_row is valid
+    if cursor_row >= lines.len() {
+        return screen.to_string();
     }
 
-    #[test]
-    fn test_apply_cursor_inverse_invalid_row() {
-        let screen = "Only one line";
-        let result = apply_cursor_inverse(screen, 5, 0);
+    let mut result = String::new();
 
-        // Should return original screen unchanged
-        assert_eq!(result, screen);
-    }
+    for (row_idx, line) in lines.iter().enumerate() {
+        if row_idx == cursor_row {
+            let chars: Vec<char> = line.chars().collect();
 
-    #[test]
-    fn test_apply_cursor_inverse_invalid_col() {
-        let screen = "Short";
-        let result = apply_cursor_inverse(screen, 0, 100);
+            // Check if cursor_col is valid
+            if cursor_col >= chars.len() {
+                result.push_str(line);
+            } else {
+                // Build the line with inverse video at cursor position
+                for (col_idx, ch) in chars.iter().enumerate() {
+                    if col_idx == cursor_col {
+                        result.push_str("\x1b[7m"); // Start inverse video
+                        result.push(*ch);
+                        result.push_str("\x1b[27m"); // End inverse video
+                    } else {
+                        result.push(*ch);
+                    }
+                }
+            }
+        } else {
+            result.push_str(line);
+        }
 
-        // Should return original line (no inverse codes)
-        assert!(!result.contains("\x1b[7m"));
-        assert!(result.contains("Short"));
+        if row_idx < lines.len() - 1 {
+            result.push('\n');
+        }
     }
 
-    #[test]
-    fn test_apply_cursor_inverse_empty_screen() {
-        let screen = "";
-        let result = apply_cursor_inverse(screen, 0, 0);
+    result
+}
 
-        // Should handle gracefully
-        assert_eq!(result, screen);
+/// Drop trailing all-blank lines, keeping blank lines between content
+fn trim_trailing_blank_lines(screen: &str) -> String {
+    let lines: Vec<&str> = screen.lines().collect();
+    let keep = lines.iter().rposition(|line| !line.trim().is_empty()).map_or(0, |i| i + 1);
+    let mut result = lines[..keep].join("\n");
+    if keep > 0 {
+        result.push('\n');
     }
+    result
+}
 
-    #[test]
-    fn test_apply_cursor_inverse_preserves_all_chars() {
-        let screen = "ABCDEFGHIJKLMNOP";
-        let result = apply_cursor_inverse(screen, 0, 7);
+/// Split `line` into pieces of at most `max_width` characters. Escape
+/// sequences (cursor inverse, colors) take no columns and are never split.
+fn wrap_line(line: &str, max_width: Option<usize>) -> Vec<String> {
+    let Some(max_width) = max_width else {
+        return vec![line.to_string()];
+    };
+    let mut pieces = Vec::new();
+    let mut piece = String::new();
+    let mut columns = 0;
+    let mut chars = line.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if ch == '\x1b' {
+            piece.push(ch);
+            if chars.peek() == Some(&'[') {
+                // CSI: parameters up to and including the final byte
+                piece.push(chars.next().unwrap());
+                for c in chars.by_ref() {
+                    piece.push(c);
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            } else if let Some(c) = chars.next() {
+                piece.push(c);
+            }
+            continue;
+        }
+        if columns == max_width {
+            pieces.push(std::mem::take(&mut piece));
+            columns = 0;
+        }
+        piece.push(ch);
+        columns += 1;
+    }
+    pieces.push(piece);
+    pieces
+}
 
-        // Strip ANSI codes
-        let stripped = result
-            .replace("\x1b[7m", "")
-            .replace("\x1b[27m", "");
+/// How long the client waits for a reply, so a wedged daemon can't hang it.
+/// Longer than PTY_WRITE_STALL_TIMEOUT so a slow INPUT still gets its answer;
+/// an INPUT with its own `timeout` gets that much longer.
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(10);
 
-        // All characters should be preserved
-        assert_eq!(stripped, screen);
+/// Fail if something is listening on `socket_path`. A socket file nobody
+/// listens on is stale and is replaced when the daemon binds.
+fn ensure_socket_unused(socket_path: &str) -> Result<()> {
+    if UnixStream::connect(socket_path).is_err() {
+        return Ok(());
     }
+    match send_request(socket_path, serde_json::json!({ "type": "PING" })) {
+        Ok(response) if response.status == "ok" => {
+            bail!("Socket {} is already in use by a running daemon (stop it, or use --force to replace it)", socket_path)
+        }
+        _ => bail!("Socket {} is already in use by another program (use --force to replace it)", socket_path),
+    }
+}
 
-    #[test]
-    fn test_apply_cursor_inverse_last_char() {
-        let screen = "Test";
-        let result = apply_cursor_inverse(screen, 0, 3);
+fn send_request(socket_path: &str, request: serde_json::Value) -> Result<Response> {
+    let mut stream = UnixStream::connect(socket_path)
+        .context("Failed to connect to daemon socket")?;
 
-        // Should end with inverse codes and then the 't'
-        assert!(result.contains("\x1b[7mt\x1b[27m"));
+    let request_type = request.get("type").and_then(|v| v.as_str());
+    let read_timeout = match request.get("timeout").and_then(|v| v.as_f64()) {
+        Some(secs) if request_type == Some("INPUT") => positive_seconds(secs)
+            .map_or(RESPONSE_TIMEOUT, |timeout| RESPONSE_TIMEOUT.saturating_add(timeout)),
+        _ => RESPONSE_TIMEOUT,
+    };
+    // WAIT blocks until the awaited event by design
+    if request_type != Some("WAIT") {
+        stream.set_read_timeout(Some(read_timeout))?;
     }
 
-    #[test]
-    fn test_apply_cursor_inverse_special_chars() {
-        let screen = "Hello\tWorld\nNext";
-        let result = apply_cursor_inverse(screen, 0, 5);
+    let json = serde_json::to_string(&request)?;
+    stream.write_all(json.as_bytes())?;
+    stream.write_all(b"\n")?;
+    stream.flush()?;
 
-        // Should handle tab character
-        assert!(result.contains("\x1b[7m\t\x1b[27m"));
-        assert!(result.contains("Hello"));
-        assert!(result.contains("World"));
+    let mut reader = BufReader::new(stream);
+    let mut line = String::new();
+    match reader.read_line(&mut line) {
+        Err(e) if matches!(e.kind(), std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut) => {
+            bail!("No response from daemon within {}s", read_timeout.as_secs());
+        }
+        result => { result?; }
     }
+
+    let response: Response = serde_json::from_str(&line)?;
+    Ok(response)
 }
 
 fn main() -> Result<()> {
@@ -1280,14 +3691,134 @@ fn main() -> Result<()> {
     }
 
     let cli = Cli::parse();
+    let verbosity = Verbosity::from_flags(cli.quiet, cli.verbose);
+    let quiet = verbosity == Verbosity::Quiet;
 
     match cli.command {
-        Commands::Start { socket, size, emulator, no_daemon, pty_dump, scrollback, command } => {
-            cmd_start(socket, size, emulator, !no_daemon, pty_dump, scrollback, command)?;
+        Commands::Start { socket, force, size, rows, cols, allow_large, emulator, no_daemon, print_socket, pty_dump, log, log_input, trace_input, log_merged, debug_raw, scrollback, max_output_bytes, read_chunk_size, read_limit, term, on_exit, keepalive, max_restarts, pass_fds, initial_input, initial_input_delay_ms, prompt_regex, max_runtime, command } => {
+            validate_pass_fds(&pass_fds)?;
+            let max_runtime = max_runtime.map(positive_seconds).transpose()
+                .map_err(|e| anyhow::anyhow!("--max-runtime {}", e))?;
+            // Unescape here so a bad escape fails start rather than the daemon
+            let initial_input = initial_input.map(|text| unescape(&text)).transpose()
+                .context("Invalid --initial-input")?;
+            let initial_input_delay = Duration::from_millis(initial_input_delay_ms.unwrap_or(0));
+            let options = SessionOptions { emulator, pty_dump, log, log_input, trace_input, log_merged, debug_raw, scrollback, max_output_bytes, read_chunk_size, read_limit, term, on_exit, keepalive, max_restarts, pass_fds, initial_input, initial_input_delay, prompt_regex, max_runtime, command };
+            // The quiet banner is exactly the socket path
+            let verbosity = if print_socket { Verbosity::Quiet } else { verbosity };
+            if let (Some(socket), false) = (&socket, force) {
+                ensure_socket_unused(socket)?;
+            }
+            let size = match size {
+                Some(size) => parse_terminal_size(&size, allow_large)?,
+                None => validate_terminal_size(cols.unwrap_or(80), rows.unwrap_or(24), allow_large)?,
+            };
+            cmd_start(socket, size, !no_daemon, options, verbosity)?;
+        }
+        Commands::Exec { size, allow_large, emulator, term, timeout, command } => {
+            let size = parse_terminal_size(&size, allow_large)?;
+            let timeout = timeout.map(positive_seconds).transpose()
+                .map_err(|e| anyhow::anyhow!("--timeout {}", e))?;
+            let exit_code = cmd_exec(size, emulator, term, timeout, command)?;
+            std::process::exit(exit_code);
+        }
+        Commands::Render { file, size, allow_large, emulator, color, cursor } => {
+            let (cols, rows) = parse_terminal_size(&size, allow_large)?;
+            let bytes = match file {
+                Some(path) => fs::read(&path).with_context(|| format!("Failed to read {}", path))?,
+                None => {
+                    let mut buf = Vec::new();
+                    std::io::stdin().read_to_end(&mut buf)?;
+                    buf
+                }
+            };
+            let mut screen = render::new_screen(emulator, rows as usize, cols as usize);
+            render::feed(screen.as_mut(), &bytes);
+
+            let (cursor_row, cursor_col) = screen.cursor_position();
+            if cursor == "print" || cursor == "both" {
+                println!("Cursor: row {}, col {}", cursor_row + 1, cursor_col + 1);
+            }
+            let text = if color { render::render_ansi(screen.as_ref()) } else { render::render(screen.as_ref()) };
+            if cursor == "inverse" || cursor == "both" {
+                print!("{}", apply_cursor_inverse(&text, cursor_row, cursor_col));
+            } else {
+                print!("{}", text);
+            }
+        }
+        Commands::Version => {
+            println!("interminai {}", env!("CARGO_PKG_VERSION"));
+            println!("Commit: {}", option_env!("INTERMINAI_GIT_COMMIT").unwrap_or("unknown"));
+            println!("Protocol: {}", protocol::PROTOCOL_VERSION);
+            let name = |emulator: &Emulator| emulator.to_possible_value().map(|value| value.get_name().to_string());
+            let default = name(&Emulator::default());
+            let backends: Vec<String> = Emulator::value_variants().iter()
+                .filter_map(name)
+                .map(|backend| if Some(&backend) == default.as_ref() { format!("{} (default)", backend) } else { backend })
+                .collect();
+            println!("Backends: {}", backends.join(", "));
         }
-        Commands::Input { socket, text, password } => {
-            // Priority: --password, --text, stdin
-            let input = if password {
+        Commands::Input { socket, text, password, focus, eof, ctrl, key, mouse, send_break, drain_first, from_json, echo, enter, timeout } => {
+            if let Some(secs) = timeout {
+                positive_seconds(secs).map_err(|e| anyhow::anyhow!("--timeout {}", e))?;
+            }
+            if let Some(path) = from_json {
+                let program = if path == "-" {
+                    let mut program = String::new();
+                    std::io::stdin().read_to_string(&mut program)?;
+                    program
+                } else {
+                    std::fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path))?
+                };
+                return run_input_program(&socket, &program, timeout, drain_first);
+            }
+            // VEOF, key and mouse encodings depend on terminal state the daemon owns
+            let daemon_input = if eof {
+                Some(serde_json::json!({
+                    "type": "INPUT",
+                    "eof": true
+                }))
+            } else if send_break {
+                Some(serde_json::json!({
+                    "type": "INPUT",
+                    "break": true
+                }))
+            } else if let Some(mouse) = mouse {
+                let parts: Vec<&str> = mouse.split(',').map(str::trim).collect();
+                let (button, row, col, action) = match parts.as_slice() {
+                    [button, row, col] => (button, row, col, &"press"),
+                    [button, row, col, action] => (button, row, col, action),
+                    _ => bail!("Invalid --mouse '{}', expected BUTTON,ROW,COL[,ACTION]", mouse),
+                };
+                let row: u32 = row.parse().with_context(|| format!("Invalid mouse row '{}'", row))?;
+                let col: u32 = col.parse().with_context(|| format!("Invalid mouse column '{}'", col))?;
+                Some(serde_json::json!({
+                    "type": "INPUT",
+                    "mouse": { "button": button, "row": row, "col": col, "action": action }
+                }))
+            } else {
+                key.map(|key| serde_json::json!({
+                    "type": "INPUT",
+                    "key": key
+                }))
+            };
+            if let Some(mut request) = daemon_input {
+                if let Some(secs) = timeout {
+                    request["timeout"] = serde_json::json!(secs);
+                }
+                if drain_first {
+                    request["drain_first"] = serde_json::json!(true);
+                }
+                let response = send_request(&socket, request)?;
+                if response.status == "error" {
+                    eprintln!("Error: {}", response.error.unwrap_or_default());
+                    std::process::exit(1);
+                }
+                return Ok(());
+            }
+
+            // Priority: --password, --focus, --ctrl, --text, stdin
+            let mut input = if password {
                 // Fetch current screen to show the password prompt from the application
                 let output_request = serde_json::json!({
                     "type": "OUTPUT",
@@ -1325,19 +3856,35 @@ fn main() -> Result<()> {
                 // Read password with echo disabled, append \r for Enter
                 let password = rpassword::read_password()
                     .context("Failed to read password (is stdin a terminal?)")?;
-                format!("{}\r", password)
+                format!("{}\r", password).into_bytes()
+            } else if let Some(focus) = focus {
+                // FocusIn is CSI I, FocusOut is CSI O
+                if focus == "in" { b"\x1b[I".to_vec() } else { b"\x1b[O".to_vec() }
+            } else if let Some(key) = ctrl {
+                vec![parse_ctrl_key(&key)?]
             } else if let Some(text_arg) = text {
                 unescape(&text_arg)?
             } else {
-                let mut buf = String::new();
-                std::io::stdin().read_to_string(&mut buf)?;
+                let mut buf = Vec::new();
+                std::io::stdin().read_to_end(&mut buf)?;
                 buf
             };
+            match enter.as_deref() {
+                Some("lf") => input.push(b'\n'),
+                Some(_) => input.push(b'\r'),
+                None => {}
+            }
 
-            let request = serde_json::json!({
-                "type": "INPUT",
-                "data": input
-            });
+            let mut request = input_request(input);
+            if echo {
+                request["echo"] = serde_json::json!(true);
+            }
+            if let Some(secs) = timeout {
+                request["timeout"] = serde_json::json!(secs);
+            }
+            if drain_first {
+                request["drain_first"] = serde_json::json!(true);
+            }
 
             let response = send_request(&socket, request)?;
 
@@ -1346,8 +3893,37 @@ fn main() -> Result<()> {
                 std::process::exit(1);
             }
         }
-        Commands::Output { socket, color, no_color, number, cursor, from, to } => {
-            let format = if no_color { "ascii" } else { "ansi" };
+        Commands::Output { socket, color, no_color, number, cursor, cursor_base, from, to, trim, no_trailing_newline, max_width, format: output_format, strip_ansi, since, normalize_newlines: normalize, compress, links, cursor_style } => {
+            let jsonl = output_format == "jsonl";
+            let raw = output_format == "raw";
+            if output_format != "text" && (from.is_some() || to.is_some()) {
+                bail!("--format {} does not support --from/--to", output_format);
+            }
+            if output_format != "text" && max_width.is_some() {
+                bail!("--format {} does not support --max-width", output_format);
+            }
+            if strip_ansi && output_format != "raw" {
+                bail!("--strip-ansi requires --format raw");
+            }
+            if since.is_some() && output_format != "raw" {
+                bail!("--since requires --format raw");
+            }
+            if normalize && output_format != "raw" {
+                bail!("--normalize-newlines requires --format raw");
+            }
+            if compress && output_format != "raw" {
+                bail!("--compress requires --format raw");
+            }
+            if no_trailing_newline && output_format != "text" {
+                bail!("--no-trailing-newline requires --format text");
+            }
+            if links && output_format != "text" {
+                bail!("--links requires --format text");
+            }
+            if cursor_style && output_format != "text" {
+                bail!("--cursor-style requires --format text");
+            }
+            let format = if jsonl || raw { output_format.as_str() } else if no_color { "ascii" } else { "ansi" };
             let _ = color;
 
             let from_json = match from.as_deref() {
@@ -1355,12 +3931,18 @@ fn main() -> Result<()> {
                 Some("-") => serde_json::json!("-"),
                 Some(s) => serde_json::json!(s.parse::<i64>().unwrap_or(0)),
             };
-            let request = serde_json::json!({
+            let mut request = serde_json::json!({
                 "type": "OUTPUT",
                 "format": format,
                 "from": from_json,
                 "to": to
             });
+            if let Some(offset) = since {
+                request["since"] = serde_json::json!(offset);
+            }
+            if compress && daemon_supports_gzip(&socket)? {
+                request["compression"] = serde_json::json!(protocol::COMPRESSION_GZIP);
+            }
 
             let response = send_request(&socket, request)?;
 
@@ -1370,14 +3952,75 @@ fn main() -> Result<()> {
             }
 
             if let Some(data) = response.data {
+                if raw {
+                    use base64::Engine;
+                    let bytes = if let Some(encoded) = data.get("data_gz").and_then(|v| v.as_str()) {
+                        let compressed = base64::engine::general_purpose::STANDARD.decode(encoded)
+                            .context("Invalid base64 in compressed raw output")?;
+                        gunzip(&compressed).context("Invalid gzip data in raw output")?
+                    } else {
+                        let encoded = data.get("data_b64").and_then(|v| v.as_str()).unwrap_or("");
+                        base64::engine::general_purpose::STANDARD.decode(encoded)
+                            .context("Invalid base64 in raw output")?
+                    };
+                    let dropped = data.get("dropped").and_then(|v| v.as_u64()).unwrap_or(0);
+                    if dropped > 0 {
+                        eprintln!("Warning: {} bytes dropped (raw buffer overflow)", dropped);
+                    }
+                    if since.is_some() {
+                        let offset = data.get("offset").and_then(|v| v.as_u64()).unwrap_or(0);
+                        eprintln!("Offset: {}", offset);
+                    }
+                    if strip_ansi {
+                        // Already ends lines with LF
+                        print!("{}", plain_text(&bytes));
+                    } else if normalize {
+                        std::io::stdout().write_all(&normalize_newlines(&bytes))?;
+                    } else {
+                        std::io::stdout().write_all(&bytes)?;
+                    }
+                    return Ok(());
+                }
+
+                if data.get("parser_in_escape").and_then(|v| v.as_bool()).unwrap_or(false) {
+                    eprintln!("Warning: output ends mid escape sequence; the screen may still change");
+                }
+                if data.get("truncated").and_then(|v| v.as_bool()).unwrap_or(false) {
+                    eprintln!("Warning: text was cut off at the right margin (autowrap is off); a wider terminal would show it");
+                }
+                let wrapped_lines = data.get("wrapped_lines").and_then(|v| v.as_u64()).unwrap_or(0);
+                if wrapped_lines > 0 && verbosity == Verbosity::Verbose {
+                    eprintln!("Note: {} lines wrapped at the right margin since the last read", wrapped_lines);
+                }
+
                 let cursor_mode = cursor.as_str();
+                // Protocol cursor is 0-based; shift to the requested base for display
+                let cursor_pos = match (
+                    data.get("cursor").and_then(|c| c.get("row")).and_then(|v| v.as_u64()),
+                    data.get("cursor").and_then(|c| c.get("col")).and_then(|v| v.as_u64())
+                ) {
+                    (Some(row), Some(col)) => Some((row + cursor_base, col + cursor_base)),
+                    _ => None,
+                };
+
+                if cursor_mode == "json" {
+                    if let Some((row, col)) = cursor_pos {
+                        println!("{}", serde_json::json!({ "cursor": { "row": row, "col": col } }));
+                    }
+                }
+
+                if jsonl {
+                    // One object per row, untrimmed so column positions stay meaningful
+                    let rows = data.get("rows").and_then(|v| v.as_array()).cloned().unwrap_or_default();
+                    for (i, text) in rows.iter().enumerate() {
+                        println!("{}", serde_json::json!({ "row": i + 1, "text": text }));
+                    }
+                    return Ok(());
+                }
 
                 if cursor_mode == "print" || cursor_mode == "both" {
-                    if let (Some(cursor_row), Some(cursor_col)) = (
-                        data.get("cursor").and_then(|c| c.get("row")).and_then(|v| v.as_u64()),
-                        data.get("cursor").and_then(|c| c.get("col")).and_then(|v| v.as_u64())
-                    ) {
-                        println!("Cursor: row {}, col {}", cursor_row + 1, cursor_col + 1);
+                    if let Some((row, col)) = cursor_pos {
+                        println!("Cursor: row {}, col {}", row, col);
                     }
                 }
 
@@ -1399,7 +4042,15 @@ fn main() -> Result<()> {
                         screen.to_string()
                     };
 
-                    if number {
+                    let screen = if trim {
+                        trim_trailing_blank_lines(&screen)
+                    } else {
+                        screen
+                    };
+
+                    let max_width = max_width.map(|w| w as usize);
+                    let mut text = if number {
+                        let mut text = String::new();
                         let lines: Vec<&str> = screen.lines().collect();
                         // Line numbers from effective from value
                         // eff_from < 0: starts at eff_from, increments, skips 0
@@ -1411,19 +4062,62 @@ fn main() -> Result<()> {
                         let max_abs = nums.iter().map(|n| n.unsigned_abs()).max().unwrap_or(1);
                         let width = max_abs.to_string().len();
                         for (line, num) in lines.iter().zip(nums.iter()) {
+                            let mut pieces = wrap_line(line, max_width).into_iter();
+                            let first = pieces.next().unwrap_or_default();
                             if *num < 0 {
-                                println!("-{:0>width$}\t{}", num.unsigned_abs(), line, width = width);
+                                text.push_str(&format!("-{:0>width$}\t{}\n", num.unsigned_abs(), first, width = width));
                             } else {
-                                println!(" {:0>width$}\t{}", num, line, width = width);
+                                text.push_str(&format!(" {:0>width$}\t{}\n", num, first, width = width));
+                            }
+                            // Continuation lines keep the number column blank
+                            for piece in pieces {
+                                text.push_str(&format!(" {:width$}\t{}\n", "", piece, width = width));
                             }
                         }
+                        text
+                    } else if max_width.is_some() {
+                        let wrapped: Vec<String> = screen.lines().flat_map(|line| wrap_line(line, max_width)).collect();
+                        let mut text = wrapped.join("\n");
+                        if screen.ends_with('\n') {
+                            text.push('\n');
+                        }
+                        text
+                    } else {
+                        screen
+                    };
+                    if no_trailing_newline && text.ends_with('\n') {
+                        text.pop();
+                    }
+                    print!("{}", text);
+                }
+
+                if links {
+                    let found: Vec<terminal::Hyperlink> = data.get("links")
+                        .and_then(|v| serde_json::from_value(v.clone()).ok())
+                        .unwrap_or_default();
+                    for link in found {
+                        println!("Link: row {}, col {}: {} -> {}", link.row + 1, link.col + 1, link.text, link.url);
+                    }
+                }
+
+                if cursor_style {
+                    let style: terminal::CursorStyle = data.get("cursor_style")
+                        .and_then(|v| serde_json::from_value(v.clone()).ok())
+                        .unwrap_or_default();
+                    if no_color {
+                        println!("Cursor style: {}", style);
                     } else {
-                        print!("{}", screen);
+                        print!("\x1b[{} q", style.decscusr());
                     }
                 }
             }
         }
-        Commands::Status { socket, quiet } => {
+        Commands::Status { socket, wait_ready, timeout } => {
+            if wait_ready {
+                let timeout = positive_seconds(timeout).map_err(|e| anyhow::anyhow!("--timeout {}", e))?;
+                wait_until_ready(&socket, timeout)?;
+            }
+
             let request = serde_json::json!({
                 "type": "STATUS",
                 "activity": !quiet
@@ -1452,26 +4146,149 @@ fn main() -> Result<()> {
                 } else {
                     // Default mode: print all status info
                     println!("Running: {}", running);
+                    let stopped = data.get("stopped").and_then(|v| v.as_bool()).unwrap_or(false);
+                    println!("Stopped: {}", stopped);
+                    let alt_screen = data.get("alt_screen").and_then(|v| v.as_bool()).unwrap_or(false);
+                    println!("Alternate screen: {}", alt_screen);
+                    let in_escape = data.get("parser_in_escape").and_then(|v| v.as_bool()).unwrap_or(false);
+                    println!("Mid escape sequence: {}", in_escape);
                     let has_activity = data.get("activity").and_then(|v| v.as_bool()).unwrap_or(false);
                     println!("Activity: {}", has_activity);
+                    let bell_count = data.get("bell_count").and_then(|v| v.as_u64()).unwrap_or(0);
+                    println!("Bells: {}", bell_count);
                     if let Some(size) = data.get("size") {
                         let rows = size.get("rows").and_then(|v| v.as_u64()).unwrap_or(0);
                         let cols = size.get("cols").and_then(|v| v.as_u64()).unwrap_or(0);
                         println!("Size: {}x{}", cols, rows);
                     }
+                    let cursor_style: terminal::CursorStyle = data.get("cursor_style")
+                        .and_then(|v| serde_json::from_value(v.clone()).ok())
+                        .unwrap_or_default();
+                    println!("Cursor style: {}", cursor_style);
                     let sb_avail = data.get("scrollback_available").and_then(|v| v.as_u64()).unwrap_or(0);
                     let sb_cap = data.get("scrollback_capacity").and_then(|v| v.as_u64()).unwrap_or(0);
-                    println!("Scrollback: {}/{}", sb_avail, sb_cap);
+                    let sb_dropped = data.get("scrollback_dropped").and_then(|v| v.as_u64()).unwrap_or(0);
+                    if sb_dropped > 0 {
+                        println!("Scrollback: {}/{} ({} lines dropped by --max-output-bytes)", sb_avail, sb_cap, sb_dropped);
+                    } else {
+                        println!("Scrollback: {}/{}", sb_avail, sb_cap);
+                    }
+                    if let Some(modes) = data.get("modes") {
+                        let bracketed_paste = modes.get("bracketed_paste").and_then(|v| v.as_bool()).unwrap_or(false);
+                        let focus_reporting = modes.get("focus_reporting").and_then(|v| v.as_bool()).unwrap_or(false);
+                        println!("Bracketed paste: {}", bracketed_paste);
+                        println!("Focus reporting: {}", focus_reporting);
+                        let application_cursor_keys = modes.get("application_cursor_keys").and_then(|v| v.as_bool()).unwrap_or(false);
+                        let application_keypad = modes.get("application_keypad").and_then(|v| v.as_bool()).unwrap_or(false);
+                        println!("Application cursor keys: {}", application_cursor_keys);
+                        println!("Application keypad: {}", application_keypad);
+                        let mouse_tracking = modes.get("mouse_tracking").and_then(|v| v.as_str()).unwrap_or("off");
+                        let sgr_mouse = modes.get("sgr_mouse").and_then(|v| v.as_bool()).unwrap_or(false);
+                        println!("Mouse tracking: {}{}", mouse_tracking, if sgr_mouse { " (SGR)" } else { "" });
+                    }
+                    if let Some(restarts) = data.get("restarts").and_then(|v| v.as_u64()) {
+                        println!("Restarts: {}", restarts);
+                    }
                     if !running {
                         if let Some(exit_code) = data.get("exit_code") {
                             println!("Exit code: {}", exit_code);
                         }
                     }
+                    if let Some(exec_error) = data.get("exec_error").and_then(|v| v.as_str()) {
+                        println!("Exec failed: {}", exec_error);
+                    }
+                    if let Some(killed_by) = data.get("killed_by").and_then(|v| v.as_str()) {
+                        println!("Killed by: {}", killed_by);
+                    }
+                    if let Some(degraded) = data.get("degraded").and_then(|v| v.as_str()) {
+                        println!("Degraded: {}", degraded);
+                    }
+                    if let Some(error) = data.get("initial_input_error").and_then(|v| v.as_str()) {
+                        println!("Initial input failed: {}", error);
+                    }
                 }
             }
         }
-        Commands::Wait { socket, quiet, line, not_contains, contains } => {
-            if let Some(line_num) = line {
+        Commands::Wait { socket, line, not_contains, contains, propagate_exit, any, exit_code: expected_exit, ready } => {
+            if ready {
+                let request = serde_json::json!({
+                    "type": "WAIT",
+                    "ready": true
+                });
+
+                let response = send_request(&socket, request)?;
+
+                if response.status == "error" {
+                    eprintln!("Error: {}", response.error.unwrap_or_default());
+                    std::process::exit(1);
+                }
+
+                let data = response.data.unwrap_or_default();
+                let Some(cursor) = data.get("cursor") else {
+                    let exit_code = data.get("exit_code").and_then(|v| v.as_i64()).unwrap_or(-1);
+                    eprintln!("Error: application exited with code {} before the prompt appeared", exit_code);
+                    std::process::exit(1);
+                };
+                let row = cursor.get("row").and_then(|v| v.as_u64()).unwrap_or(0) + 1;
+                let col = cursor.get("col").and_then(|v| v.as_u64()).unwrap_or(0) + 1;
+                if quiet {
+                    println!("{} {}", row, col);
+                } else {
+                    println!("Ready, cursor: row {}, col {}", row, col);
+                }
+            } else if let Some(expected) = expected_exit {
+                let request = serde_json::json!({
+                    "type": "WAIT",
+                    "exit_code": expected
+                });
+
+                let response = send_request(&socket, request)?;
+
+                if response.status == "error" {
+                    eprintln!("Error: {}", response.error.unwrap_or_default());
+                    std::process::exit(1);
+                }
+
+                let data = response.data.unwrap_or_default();
+                let exit_code = data.get("exit_code").and_then(|v| v.as_i64()).unwrap_or(-1);
+                let matched = data.get("matched").and_then(|v| v.as_bool()).unwrap_or(false);
+                if quiet {
+                    println!("{}", exit_code);
+                } else if matched {
+                    println!("Exited with code {}", exit_code);
+                } else {
+                    println!("Exited with code {}, not {}", exit_code, expected);
+                }
+                if !matched {
+                    std::process::exit(1);
+                }
+            } else if any {
+                let request = serde_json::json!({
+                    "type": "WAIT",
+                    "activity": true
+                });
+
+                let response = send_request(&socket, request)?;
+
+                if response.status == "error" {
+                    eprintln!("Error: {}", response.error.unwrap_or_default());
+                    std::process::exit(1);
+                }
+
+                // Exit wins when both happened: the program is gone either way
+                let data = response.data.unwrap_or_default();
+                let exited = data.get("exited").and_then(|v| v.as_bool()).unwrap_or(false);
+                let result = if exited {
+                    serde_json::json!({
+                        "reason": "exit",
+                        "exit_code": data.get("exit_code").cloned().unwrap_or_default(),
+                        "activity": data.get("activity").and_then(|v| v.as_bool()).unwrap_or(false)
+                    })
+                } else {
+                    serde_json::json!({ "reason": "activity" })
+                };
+                println!("{}", result);
+            } else if let Some(line_num) = line {
                 // --line mode: wait until specified line matches condition
                 if line_num == 0 {
                     eprintln!("Error: line number must be 1 or greater (1-based)");
@@ -1526,7 +4343,7 @@ fn main() -> Result<()> {
                     match wait_mode.0 {
                         "not_contains" => !line.contains(&wait_mode.1),
                         "contains" => line.contains(&wait_mode.1),
-                        "change" => line != &initial_line,
+                        "change" => line != initial_line,
                         _ => false,
                     }
                 };
@@ -1570,7 +4387,7 @@ fn main() -> Result<()> {
                 // Original behavior: single wait
                 let request = serde_json::json!({
                     "type": "WAIT",
-                    "activity": !quiet
+                    "activity": !quiet && !propagate_exit
                 });
 
                 let response = send_request(&socket, request)?;
@@ -1581,10 +4398,16 @@ fn main() -> Result<()> {
                 }
 
                 if let Some(data) = response.data {
-                    if quiet {
+                    if quiet || propagate_exit {
                         // Quiet mode: just print exit code
-                        if let Some(exit_code) = data.get("exit_code") {
-                            println!("{}", exit_code);
+                        let exit_code = data.get("exit_code").and_then(|v| v.as_i64());
+                        if quiet {
+                            if let Some(exit_code) = exit_code {
+                                println!("{}", exit_code);
+                            }
+                        }
+                        if propagate_exit {
+                            std::process::exit(exit_code.unwrap_or(1) as i32);
                         }
                     } else {
                         // Default mode: report both terminal activity and exit status
@@ -1596,7 +4419,17 @@ fn main() -> Result<()> {
                 }
             }
         }
-        Commands::Kill { socket, signal } => {
+        Commands::Kill { socket, signal, list } => {
+            if list {
+                for sig in Signal::iterator() {
+                    println!("{:>2} {}", sig as i32, sig.as_str());
+                }
+                return Ok(());
+            }
+            let Some(socket) = socket else {
+                bail!("--socket is required");
+            };
+
             let request = serde_json::json!({
                 "type": "KILL",
                 "signal": signal
@@ -1609,10 +4442,13 @@ fn main() -> Result<()> {
                 std::process::exit(1);
             }
         }
-        Commands::Stop { socket } => {
-            let request = serde_json::json!({
+        Commands::Stop { socket, keep_socket, remove_socket } => {
+            let mut request = serde_json::json!({
                 "type": "STOP"
             });
+            if keep_socket || remove_socket {
+                request["remove_socket"] = serde_json::json!(remove_socket);
+            }
 
             let response = send_request(&socket, request)?;
 
@@ -1622,14 +4458,160 @@ fn main() -> Result<()> {
             }
         }
 
-        Commands::Resize { socket, size } => {
-            // Parse and validate size
-            let (cols, rows) = parse_terminal_size(&size)?;
+        Commands::Resize { socket, size, auto, rows, cols, allow_large, reflow } => {
+            // Parse and validate size, take it from the caller's terminal,
+            // or resolve --rows/--cols against the current size
+            let (cols, rows) = match size {
+                Some(size) => parse_terminal_size(&size, allow_large)?,
+                None if auto => caller_terminal_size()?,
+                None if rows.is_some() || cols.is_some() => {
+                    let (current_cols, current_rows) = current_terminal_size(&socket)?;
+                    let cols = match cols {
+                        Some(spec) => resolve_dimension(&spec, current_cols, MIN_RESIZE_COLS).context("Invalid --cols")?,
+                        None => current_cols,
+                    };
+                    let rows = match rows {
+                        Some(spec) => resolve_dimension(&spec, current_rows, MIN_RESIZE_ROWS).context("Invalid --rows")?,
+                        None => current_rows,
+                    };
+                    (cols, rows)
+                }
+                None => bail!("One of --size, --auto, --rows or --cols is required"),
+            };
 
             let request = serde_json::json!({
                 "type": "RESIZE",
                 "cols": cols,
-                "rows": rows
+                "rows": rows,
+                "allow_large": allow_large,
+                "reflow": reflow
+            });
+
+            let response = send_request(&socket, request)?;
+
+            if response.status == "error" {
+                eprintln!("Error: {}", response.error.unwrap_or_default());
+                std::process::exit(1);
+            }
+
+            let data = response.data.unwrap_or_default();
+            if verbosity == Verbosity::Verbose {
+                let old_cols = data.get("old_cols").and_then(|v| v.as_u64()).unwrap_or(0);
+                let old_rows = data.get("old_rows").and_then(|v| v.as_u64()).unwrap_or(0);
+                println!("Terminal resized from {}x{} to {}x{}", old_cols, old_rows, cols, rows);
+            } else {
+                verbosity.info(format_args!("Terminal resized to {}x{}", cols, rows));
+            }
+            match data.get("history_moved").and_then(|v| v.as_i64()) {
+                Some(moved) if moved >= 0 => {
+                    verbosity.info(format_args!("Reflow: {} lines moved into scrollback", moved));
+                }
+                Some(moved) => {
+                    verbosity.info(format_args!("Reflow: {} lines moved back from scrollback", -moved));
+                }
+                None if reflow => verbosity.info(format_args!("Reflow: scrollback is full, moved lines not counted")),
+                None => {}
+            }
+        }
+
+        Commands::Clear { socket } => {
+            let request = serde_json::json!({
+                "type": "CLEAR"
+            });
+
+            let response = send_request(&socket, request)?;
+
+            if response.status == "error" {
+                eprintln!("Error: {}", response.error.unwrap_or_default());
+                std::process::exit(1);
+            }
+        }
+
+        Commands::Snapshot { socket } => {
+            let response = send_request(&socket, serde_json::json!({ "type": "SNAPSHOT" }))?;
+
+            if response.status == "error" {
+                eprintln!("Error: {}", response.error.unwrap_or_default());
+                std::process::exit(1);
+            }
+
+            let data = response.data.unwrap_or_default();
+            println!("{}", data.get("snapshot").and_then(|v| v.as_str()).unwrap_or(""));
+        }
+
+        Commands::Restore { socket, file } => {
+            let snapshot = match file {
+                Some(path) => fs::read_to_string(&path).with_context(|| format!("Failed to read {}", path))?,
+                None => {
+                    let mut snapshot = String::new();
+                    std::io::stdin().read_to_string(&mut snapshot)?;
+                    snapshot
+                }
+            };
+            let request = serde_json::json!({
+                "type": "RESTORE",
+                "snapshot": snapshot.trim()
+            });
+
+            let response = send_request(&socket, request)?;
+
+            if response.status == "error" {
+                eprintln!("Error: {}", response.error.unwrap_or_default());
+                std::process::exit(1);
+            }
+        }
+
+        Commands::Info { socket } => {
+            let request = serde_json::json!({
+                "type": "INFO"
+            });
+
+            let response = send_request(&socket, request)?;
+
+            if response.status == "error" {
+                eprintln!("Error: {}", response.error.unwrap_or_default());
+                std::process::exit(1);
+            }
+
+            if let Some(data) = response.data {
+                if let Some(command) = data.get("command") {
+                    println!("Command: {}", command);
+                }
+                if let Some(started_at) = data.get("started_at").and_then(|v| v.as_u64()) {
+                    let now = std::time::SystemTime::now()
+                        .duration_since(std::time::UNIX_EPOCH)
+                        .map(|d| d.as_secs())
+                        .unwrap_or(started_at);
+                    println!("Started: {} ({}s ago)", started_at, now.saturating_sub(started_at));
+                }
+                if let Some(emulator) = data.get("emulator").and_then(|v| v.as_str()) {
+                    println!("Emulator: {}", emulator);
+                }
+                if let Some(size) = data.get("size") {
+                    let rows = size.get("rows").and_then(|v| v.as_u64()).unwrap_or(0);
+                    let cols = size.get("cols").and_then(|v| v.as_u64()).unwrap_or(0);
+                    println!("Size: {}x{}", cols, rows);
+                }
+                if let Some(term) = data.get("term").and_then(|v| v.as_str()) {
+                    println!("TERM: {}", term);
+                }
+                match data.get("scrollback") {
+                    Some(serde_json::Value::String(policy)) => println!("Scrollback: {}", policy),
+                    Some(lines) => println!("Scrollback: {} lines", lines),
+                    None => {}
+                }
+                if let Some(socket) = data.get("socket").and_then(|v| v.as_str()) {
+                    println!("Socket: {}", socket);
+                }
+                if let Some(pty) = data.get("pty").and_then(|v| v.as_str()) {
+                    println!("PTY: {}", pty);
+                }
+            }
+        }
+
+        Commands::Children { socket } => {
+            let request = serde_json::json!({
+                "type": "CHILDREN"
             });
 
             let response = send_request(&socket, request)?;
@@ -1639,13 +4621,24 @@ fn main() -> Result<()> {
                 std::process::exit(1);
             }
 
-            println!("Terminal resized to {}x{}", cols, rows);
+            let processes: Vec<protocol::ProcessInfo> = response.data
+                .and_then(|data| data.get("processes").cloned())
+                .and_then(|v| serde_json::from_value(v).ok())
+                .unwrap_or_default();
+            // "+" marks the foreground process group, as in ps
+            println!("{:>7} {:>7}   COMMAND", "PID", "PPID");
+            for process in processes {
+                let foreground = if process.foreground { "+" } else { " " };
+                println!("{:>7} {:>7} {} {}", process.pid, process.ppid, foreground, process.command.join(" "));
+            }
         }
 
-        Commands::Debug { socket, clear } => {
+        Commands::Debug { socket, clear, raw, decode_sgr } => {
             let request = serde_json::json!({
                 "type": "DEBUG",
-                "clear": clear
+                "clear": clear,
+                "raw": raw,
+                "sgr": decode_sgr
             });
 
             let response = send_request(&socket, request)?;
@@ -1667,7 +4660,10 @@ fn main() -> Result<()> {
                         for entry in entries {
                             let seq = entry.get("sequence").and_then(|v| v.as_str()).unwrap_or("?");
                             let hex = entry.get("raw_hex").and_then(|v| v.as_str()).unwrap_or("?");
-                            println!("  {} ({})", seq, hex);
+                            match entry.get("original_hex").and_then(|v| v.as_str()) {
+                                Some(original) => println!("  {} ({}) raw: {}", seq, hex, original),
+                                None => println!("  {} ({})", seq, hex),
+                            }
                         }
                     }
                 }
@@ -1676,6 +4672,24 @@ fn main() -> Result<()> {
                     println!("Dropped: {} (buffer overflow)", dropped);
                 }
 
+                let counter = |name: &str| data.get(name).and_then(|v| v.as_u64()).unwrap_or(0);
+                println!("Output: {} bytes read, {} printed, {} CSI, {} ESC",
+                    counter("bytes_read"), counter("print_count"), counter("csi_count"), counter("esc_count"));
+
+                if decode_sgr {
+                    let recent: Vec<String> = data.get("recent_sgr")
+                        .and_then(|v| serde_json::from_value(v.clone()).ok())
+                        .unwrap_or_default();
+                    if recent.is_empty() {
+                        println!("No SGR sequences");
+                    } else {
+                        println!("SGR sequences (oldest first):");
+                        for params in recent {
+                            println!("  \\e[{}m  SGR: {}", params, describe_sgr(&params));
+                        }
+                    }
+                }
+
                 // Display termios info
                 if let Some(termios) = data.get("termios") {
                     if let Some(error) = termios.get("error").and_then(|v| v.as_str()) {
@@ -1725,3 +4739,313 @@ fn main() -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_apply_cursor_inverse_basic() {
+        let screen = "Hello World\nSecond Line";
+        let result = apply_cursor_inverse(screen, 0, 6);
+
+        // Should have inverse codes around character at position 6 (the 'W')
+        assert!(result.contains("\x1b[7mW\x1b[27m"), "Should wrap 'W' with inverse codes");
+        assert!(result.contains("Hello"));
+        assert!(result.contains("orld")); // After the wrapped W
+        assert!(result.contains("Second Line"));
+    }
+
+    #[test]
+    fn test_apply_cursor_inverse_first_char() {
+        let screen = "Test";
+        let result = apply_cursor_inverse(screen, 0, 0);
+
+        // Should start with inverse code
+        assert!(result.starts_with("\x1b[7m"));
+        assert!(result.contains("\x1b[27m"));
+    }
+
+    #[test]
+    fn test_apply_cursor_inverse_multiline() {
+        let screen = "Line 1\nLine 2\nLine 3";
+        let result = apply_cursor_inverse(screen, 1, 5);
+
+        // Should have all lines
+        assert!(result.contains("Line 1"));
+        assert!(result.contains("Line ")); // Before wrapped character
+        assert!(result.contains("Line 3"));
+
+        // Should have inverse codes wrapping character at position 5 of line 1 (the '2')
+        assert!(result.contains("\x1b[7m2\x1b[27m"));
+    }
+
+    #[test]
+    fn test_apply_cursor_inverse_invalid_row() {
+        let screen = "Only one line";
+        let result = apply_cursor_inverse(screen, 5, 0);
+
+        // Should return original screen unchanged
+        assert_eq!(result, screen);
+    }
+
+    #[test]
+    fn test_apply_cursor_inverse_invalid_col() {
+        let screen = "Short";
+        let result = apply_cursor_inverse(screen, 0, 100);
+
+        // Should return original line (no inverse codes)
+        assert!(!result.contains("\x1b[7m"));
+        assert!(result.contains("Short"));
+    }
+
+    #[test]
+    fn test_apply_cursor_inverse_empty_screen() {
+        let screen = "";
+        let result = apply_cursor_inverse(screen, 0, 0);
+
+        // Should handle gracefully
+        assert_eq!(result, screen);
+    }
+
+    #[test]
+    fn test_apply_cursor_inverse_preserves_all_chars() {
+        let screen = "ABCDEFGHIJKLMNOP";
+        let result = apply_cursor_inverse(screen, 0, 7);
+
+        // Strip ANSI codes
+        let stripped = result
+            .replace("\x1b[7m", "")
+            .replace("\x1b[27m", "");
+
+        // All characters should be preserved
+        assert_eq!(stripped, screen);
+    }
+
+    #[test]
+    fn test_apply_cursor_inverse_last_char() {
+        let screen = "Test";
+        let result = apply_cursor_inverse(screen, 0, 3);
+
+        // Should end with inverse codes and then the 't'
+        assert!(result.contains("\x1b[7mt\x1b[27m"));
+    }
+
+    #[test]
+    fn test_echo_text() {
+        assert_eq!(echo_text(b"hello\r"), b"hello\r\n");
+        assert_eq!(echo_text(b"a\nb"), b"a\r\nb");
+        assert_eq!(echo_text(b"x\x1b[Ay\x07\t"), b"xy");
+        assert_eq!(echo_text("héllo".as_bytes()), "héllo".as_bytes());
+    }
+
+    #[test]
+    fn test_plain_text() {
+        assert_eq!(plain_text(b"\x1b[1;31mred\x1b[0m text\r\n"), "red text\n");
+        assert_eq!(plain_text(b"\x1b]0;title\x07a\tb\x1b[5Gc\x1bP$q m\x1b\\"), "a\tbc");
+        assert_eq!(plain_text(b"10%\r50%\r100%\r\n"), "10%\n50%\n100%\n");
+        assert_eq!(plain_text("héllo\x07".as_bytes()), "héllo");
+    }
+
+    #[test]
+    fn test_normalize_newlines() {
+        assert_eq!(normalize_newlines(b"a\r\nb\nc\rd"), b"a\nb\nc\nd");
+        assert_eq!(normalize_newlines(b"\r\r\n\n"), b"\n\n\n");
+        assert_eq!(normalize_newlines(b"\x1b[31mred\x1b[0m\r\n"), b"\x1b[31mred\x1b[0m\n");
+    }
+
+    #[test]
+    fn test_wrap_line() {
+        assert_eq!(wrap_line("abcdef", Some(4)), vec!["abcd", "ef"]);
+        assert_eq!(wrap_line("abcd", Some(4)), vec!["abcd"]);
+        assert_eq!(wrap_line("", Some(4)), vec![""]);
+        assert_eq!(wrap_line("abcdef", None), vec!["abcdef"]);
+        assert_eq!(wrap_line("ab\x1b[7mc\x1b[27mdef", Some(3)), vec!["ab\x1b[7mc\x1b[27m", "def"]);
+    }
+
+    #[test]
+    fn test_mouse_sequence() {
+        let event = |button: &str, row, col, action: &str| MouseEvent {
+            button: button.to_string(), row, col, action: action.to_string(),
+        };
+        let click = event("left", 5, 10, "press");
+        assert_eq!(mouse_sequence(&click, MouseTracking::Click, true).unwrap(), b"\x1b[<0;10;5M");
+        assert_eq!(mouse_sequence(&event("right", 5, 10, "release"), MouseTracking::Click, true).unwrap(), b"\x1b[<2;10;5m");
+        assert_eq!(mouse_sequence(&click, MouseTracking::Click, false).unwrap(), b"\x1b[M *%");
+        assert_eq!(mouse_sequence(&event("right", 5, 10, "release"), MouseTracking::Click, false).unwrap(), b"\x1b[M#*%");
+        assert_eq!(mouse_sequence(&event("wheel-up", 1, 1, "press"), MouseTracking::Click, true).unwrap(), b"\x1b[<64;1;1M");
+        assert_eq!(mouse_sequence(&event("left", 1, 1, "move"), MouseTracking::Drag, true).unwrap(), b"\x1b[<32;1;1M");
+        assert_eq!(mouse_sequence(&event("none", 1, 1, "move"), MouseTracking::Motion, true).unwrap(), b"\x1b[<35;1;1M");
+
+        assert!(mouse_sequence(&click, MouseTracking::Off, true).is_err());
+        assert!(mouse_sequence(&event("left", 1, 1, "move"), MouseTracking::Click, true).is_err());
+        assert!(mouse_sequence(&event("none", 1, 1, "move"), MouseTracking::Drag, true).is_err());
+        assert!(mouse_sequence(&event("left", 1, 300, "press"), MouseTracking::Click, false).is_err());
+        assert_eq!(mouse_sequence(&event("left", 1, 300, "press"), MouseTracking::Click, true).unwrap(), b"\x1b[<0;300;1M");
+        assert!(mouse_sequence(&event("left", 0, 1, "press"), MouseTracking::Click, true).is_err());
+        assert!(mouse_sequence(&event("wheel-down", 1, 1, "release"), MouseTracking::Click, true).is_err());
+        assert!(mouse_sequence(&event("none", 1, 1, "press"), MouseTracking::Motion, true).is_err());
+        assert!(mouse_sequence(&event("thumb", 1, 1, "press"), MouseTracking::Click, true).is_err());
+    }
+
+    #[test]
+    fn test_key_sequence() {
+        assert_eq!(key_sequence("up", false, false).unwrap(), b"\x1b[A");
+        assert_eq!(key_sequence("up", true, false).unwrap(), b"\x1bOA");
+        assert_eq!(key_sequence("up", false, true).unwrap(), b"\x1bOA");
+        assert_eq!(key_sequence("home", false, false).unwrap(), b"\x1b[H");
+        assert_eq!(key_sequence("pagedown", true, true).unwrap(), b"\x1b[6~");
+        assert_eq!(key_sequence("kp5", false, false).unwrap(), b"5");
+        assert_eq!(key_sequence("kp5", false, true).unwrap(), b"\x1bOu");
+        assert_eq!(key_sequence("kp-enter", false, true).unwrap(), b"\x1bOM");
+        assert_eq!(key_sequence("kp-enter", true, false).unwrap(), b"\r");
+        assert!(key_sequence("kp10", false, false).is_none());
+        assert!(key_sequence("sideways", false, false).is_none());
+    }
+
+    #[test]
+    fn test_parse_ctrl_key() {
+        assert_eq!(parse_ctrl_key("C-c").unwrap(), 0x03);
+        assert_eq!(parse_ctrl_key("C-d").unwrap(), 0x04);
+        assert_eq!(parse_ctrl_key("C-Z").unwrap(), 0x1a);
+        assert_eq!(parse_ctrl_key("C-[").unwrap(), 0x1b);
+        assert_eq!(parse_ctrl_key("C-@").unwrap(), 0x00);
+        assert_eq!(parse_ctrl_key("C-?").unwrap(), 0x7f);
+        assert!(parse_ctrl_key("d").is_err());
+        assert!(parse_ctrl_key("C-").is_err());
+        assert!(parse_ctrl_key("C-dd").is_err());
+        assert!(parse_ctrl_key("C-1").is_err());
+    }
+
+    #[test]
+    fn test_unescape_utf8_text() {
+        assert_eq!(unescape("héllo ✓").unwrap(), "héllo ✓".as_bytes());
+    }
+
+    #[test]
+    fn test_unescape_hex_is_raw_byte() {
+        // \xHH is a single byte, not a Latin-1 character re-encoded as UTF-8
+        assert_eq!(unescape("\\xff\\x00").unwrap(), vec![0xff, 0x00]);
+        assert_eq!(unescape("\\xc3\\xa9").unwrap(), "é".as_bytes());
+    }
+
+    #[test]
+    fn test_unescape_unknown_escape_keeps_utf8() {
+        assert_eq!(unescape("\\é").unwrap(), "\\é".as_bytes());
+    }
+
+    #[test]
+    fn test_resolve_dimension() {
+        assert_eq!(resolve_dimension("40", 24, MIN_RESIZE_ROWS).unwrap(), 40);
+        assert_eq!(resolve_dimension("+10", 24, MIN_RESIZE_ROWS).unwrap(), 34);
+        assert_eq!(resolve_dimension("-20", 80, MIN_RESIZE_COLS).unwrap(), 60);
+        assert_eq!(resolve_dimension("-100", 80, MIN_RESIZE_COLS).unwrap(), MIN_RESIZE_COLS);
+        assert!(resolve_dimension("+x", 80, MIN_RESIZE_COLS).is_err());
+    }
+
+    #[test]
+    fn test_format_log_entry() {
+        let entry = format_log_entry(Duration::from_millis(1500), "output", b"\x1b[Hhello\r\n");
+        assert_eq!(entry,
+            "[    1.500000] output 10 bytes\n    \
+             1b 5b 48 68 65 6c 6c 6f 0d 0a                    |.[Hhello..|\n");
+    }
+
+    #[test]
+    fn test_format_trace_entry() {
+        let entry = format_trace_entry(Duration::from_millis(250), b":q\x1b[A\r", 6);
+        assert_eq!(entry, "[    0.250000] trace 6 bytes, 6 written: 3a 71 1b 5b 41 0d \":q\\x1b[A\\r\"\n");
+    }
+
+    #[test]
+    fn test_describe_sgr() {
+        assert_eq!(describe_sgr("1;31"), "bold, fg=red");
+        assert_eq!(describe_sgr(""), "reset");
+        assert_eq!(describe_sgr("22;39;49"), "normal intensity, fg=default, bg=default");
+        assert_eq!(describe_sgr("97;104"), "fg=bright white, bg=bright blue");
+        assert_eq!(describe_sgr("38;5;196;1"), "fg=color 196, bold");
+        assert_eq!(describe_sgr("38:2::255:0:10"), "fg=#ff000a");
+        assert_eq!(describe_sgr("38:2:255:0:10"), "fg=#ff000a");
+        assert_eq!(describe_sgr("48:5:17;1"), "bg=color 17, bold");
+        assert_eq!(describe_sgr("48;2;0;128;255"), "bg=#0080ff");
+        assert_eq!(describe_sgr("4:3;58:5:1"), "curly underline, underline color=color 1");
+        assert_eq!(describe_sgr("73"), "unknown 73");
+    }
+
+    #[test]
+    fn test_format_merged_entry() {
+        assert_eq!(format_merged_entry(Duration::from_millis(1500), "IN", b"hi\r"),
+            "[    1.500000] IN  3 \"hi\\r\"\n");
+        assert_eq!(format_merged_entry(Duration::ZERO, "OUT", b"\x1b[Hhi\r\n"),
+            "[    0.000000] OUT 7 \"\\x1b[Hhi\\r\\n\"\n");
+    }
+
+    #[test]
+    fn test_format_log_entry_wraps_at_16_bytes() {
+        let entry = format_log_entry(Duration::ZERO, "input", &[b'a'; 20]);
+        let lines: Vec<&str> = entry.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[1].ends_with("|aaaaaaaaaaaaaaaa|"));
+        assert!(lines[2].ends_with("|aaaa|"));
+    }
+
+    #[test]
+    fn test_raw_output_buffer_take_drains() {
+        let mut buf = RawOutputBuffer::new(16);
+        buf.push(b"hello");
+        buf.push(b" world");
+        assert_eq!(buf.take(), (b"hello world".to_vec(), 0));
+        assert_eq!(buf.take(), (Vec::new(), 0));
+    }
+
+    #[test]
+    fn test_raw_output_buffer_drops_oldest() {
+        let mut buf = RawOutputBuffer::new(4);
+        buf.push(b"abc");
+        buf.push(b"def");
+        assert_eq!(buf.take(), (b"cdef".to_vec(), 2));
+        // Dropped count resets after reading
+        buf.push(b"x");
+        assert_eq!(buf.take(), (b"x".to_vec(), 0));
+    }
+
+    #[test]
+    fn test_raw_output_buffer_read_since() {
+        let mut buf = RawOutputBuffer::new(4);
+        buf.push(b"ab");
+        assert_eq!(buf.read_since(0), Some((b"ab".to_vec(), 0)));
+        assert_eq!(buf.read_since(1), Some((b"b".to_vec(), 0)));
+        assert_eq!(buf.read_since(2), Some((Vec::new(), 0)));
+        assert_eq!(buf.read_since(3), None);
+        // "ab" evicted: reading from 0 reports a 2-byte gap
+        buf.push(b"cdef");
+        assert_eq!(buf.end(), 6);
+        assert_eq!(buf.read_since(0), Some((b"cdef".to_vec(), 2)));
+        // Reading by offset doesn't drain
+        assert_eq!(buf.take(), (b"cdef".to_vec(), 2));
+        assert_eq!(buf.read_since(4), Some((b"ef".to_vec(), 0)));
+    }
+
+    #[test]
+    fn test_trim_trailing_blank_lines() {
+        let screen = "first\n\nthird\n\n   \n\n";
+        assert_eq!(trim_trailing_blank_lines(screen), "first\n\nthird\n");
+    }
+
+    #[test]
+    fn test_trim_trailing_blank_lines_all_blank() {
+        assert_eq!(trim_trailing_blank_lines("\n\n\n"), "");
+        assert_eq!(trim_trailing_blank_lines(""), "");
+    }
+
+    #[test]
+    fn test_apply_cursor_inverse_special_chars() {
+        let screen = "Hello\tWorld\nNext";
+        let result = apply_cursor_inverse(screen, 0, 5);
+
+        // Should handle tab character
+        assert!(result.contains("\x1b[7m\t\x1b[27m"));
+        assert!(result.contains("Hello"));
+        assert!(result.contains("World"));
+    }
+}
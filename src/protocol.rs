@@ -0,0 +1,667 @@
+// Response payloads of the socket protocol
+//
+// Each request type answers with one of these as the `data` of an "ok"
+// Response; PROTOCOL.md documents the fields. Optional fields are left out
+// of the JSON when unset.
+
+use serde::{Deserialize, Serialize};
+
+use crate::terminal::{CursorStyle, Hyperlink, MouseTracking, UnhandledSequence};
+
+/// Version of this protocol, shown by `interminai version`; bumped when a
+/// change would break existing clients
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// Compression a daemon can apply to raw OUTPUT, as listed by PING and
+/// requested with OUTPUT's `compression`
+pub const COMPRESSION_GZIP: &str = "gzip";
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Size {
+    pub rows: usize,
+    pub cols: usize,
+}
+
+/// 0-based cursor position
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Cursor {
+    pub row: usize,
+    pub col: usize,
+}
+
+/// Answer with no fields: INPUT, CLEAR, PING
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct EmptyResponse {}
+
+/// PING; daemons from before compression send no fields
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct PingResponse {
+    /// Compression OUTPUT can apply to raw data, e.g. ["gzip"]
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub compression: Vec<String>,
+}
+
+/// OUTPUT with format "raw": the bytes as `data_b64`, or gzipped as
+/// `data_gz` if the request asked for compression
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RawOutputResponse {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_b64: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_gz: Option<String>,
+    pub dropped: usize,
+    /// Set when `dropped` bytes are missing before the data
+    pub gap: bool,
+    /// Stream offset just past the returned bytes, for the next `since`
+    pub offset: u64,
+}
+
+/// SNAPSHOT: the view, opaque to clients, to pass back to RESTORE
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotResponse {
+    pub snapshot: String,
+}
+
+/// OUTPUT with format "ascii", "ansi" or "jsonl"
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct OutputResponse {
+    pub screen: String,
+    pub cursor: Cursor,
+    /// Set by the application with DECSCUSR
+    #[serde(default)]
+    pub cursor_style: CursorStyle,
+    pub size: Size,
+    pub from: i64,
+    pub to: i64,
+    pub scrollback_available: usize,
+    pub scrollback_capacity: usize,
+    /// Changes whenever the screen may have changed; equal values mean the same screen
+    pub generation: u64,
+    /// The application has switched to the alternate screen (full-screen programs)
+    pub alt_screen: bool,
+    /// The output read so far ends partway through an escape sequence; read
+    /// again before trusting the screen
+    #[serde(default)]
+    pub parser_in_escape: bool,
+    /// Times text reached the right margin and continued on the next row
+    /// since the previous OUTPUT
+    #[serde(default)]
+    pub wrapped_lines: usize,
+    /// Text was overwritten at the right margin (autowrap off) since the
+    /// previous OUTPUT
+    #[serde(default)]
+    pub truncated: bool,
+    /// OSC 8 hyperlinks on the visible screen
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub links: Vec<Hyperlink>,
+    /// Untrimmed screen rows, for format "jsonl"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rows: Option<Vec<String>>,
+}
+
+/// Terminal modes enabled by the application
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Modes {
+    pub bracketed_paste: bool,
+    pub focus_reporting: bool,
+    pub application_cursor_keys: bool,
+    pub application_keypad: bool,
+    pub mouse_tracking: MouseTracking,
+    pub sgr_mouse: bool,
+}
+
+/// How many lines that scroll off the top are kept, from `start --scrollback`.
+/// On the wire a number of lines, or "none" / "unlimited".
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(into = "serde_json::Value", try_from = "serde_json::Value")]
+pub enum ScrollbackPolicy {
+    Lines(usize),
+    None,
+    /// Bounded only by --max-output-bytes
+    Unlimited,
+}
+
+impl ScrollbackPolicy {
+    pub fn capacity(self) -> usize {
+        match self {
+            ScrollbackPolicy::Lines(n) => n,
+            ScrollbackPolicy::None => 0,
+            ScrollbackPolicy::Unlimited => usize::MAX,
+        }
+    }
+}
+
+impl std::str::FromStr for ScrollbackPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(ScrollbackPolicy::None),
+            "unlimited" => Ok(ScrollbackPolicy::Unlimited),
+            _ => match s.parse::<usize>() {
+                Ok(0) => Ok(ScrollbackPolicy::None),
+                Ok(n) => Ok(ScrollbackPolicy::Lines(n)),
+                Err(_) => Err(format!("expected a number of lines, none or unlimited, got '{}'", s)),
+            },
+        }
+    }
+}
+
+impl From<ScrollbackPolicy> for serde_json::Value {
+    fn from(policy: ScrollbackPolicy) -> Self {
+        match policy {
+            ScrollbackPolicy::Lines(n) => serde_json::json!(n),
+            ScrollbackPolicy::None => serde_json::json!("none"),
+            ScrollbackPolicy::Unlimited => serde_json::json!("unlimited"),
+        }
+    }
+}
+
+impl TryFrom<serde_json::Value> for ScrollbackPolicy {
+    type Error = String;
+
+    fn try_from(value: serde_json::Value) -> Result<Self, Self::Error> {
+        match value {
+            serde_json::Value::Number(n) => n.to_string().parse(),
+            serde_json::Value::String(s) => s.parse(),
+            other => Err(format!("invalid scrollback policy: {}", other)),
+        }
+    }
+}
+
+/// STATUS
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StatusResponse {
+    pub running: bool,
+    pub stopped: bool,
+    pub ready: bool,
+    /// The application has switched to the alternate screen (full-screen programs)
+    pub alt_screen: bool,
+    /// As in OUTPUT
+    #[serde(default)]
+    pub parser_in_escape: bool,
+    /// As in OUTPUT
+    #[serde(default)]
+    pub cursor_style: CursorStyle,
+    pub size: Size,
+    pub scrollback_available: usize,
+    pub scrollback_capacity: usize,
+    pub scrollback_dropped: u64,
+    pub scrollback_policy: ScrollbackPolicy,
+    pub modes: Modes,
+    /// Activity mode only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub activity: Option<bool>,
+    /// Activity mode only
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bell_count: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    /// Only with `start --keepalive`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub restarts: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exec_failed: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exec_error: Option<String>,
+    /// Set once the daemon killed the command itself, e.g. "max_runtime"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub killed_by: Option<String>,
+    /// Set once the screen stopped being updated, e.g. the PTY reader panicked
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub degraded: Option<String>,
+    /// Set when `start --initial-input` could not be written
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub initial_input_error: Option<String>,
+}
+
+/// WAIT: just `exit_code` normally; `activity` and `exited` in activity mode,
+/// with `exit_code` once the process has exited
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WaitResponse {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub activity: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exited: Option<bool>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exit_code: Option<i32>,
+    /// With `exit_code` in the request: whether the exit had that code
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub matched: Option<bool>,
+    /// With `ready` in the request: where the prompt left the cursor
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<Cursor>,
+}
+
+/// KILL
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct KillResponse {
+    pub signal_sent: String,
+}
+
+/// STOP
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct StopResponse {
+    pub message: String,
+}
+
+/// RESIZE
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ResizeResponse {
+    pub cols: u16,
+    pub rows: u16,
+    pub old_cols: usize,
+    pub old_rows: usize,
+    /// With reflow: rows moved into scrollback (negative: pulled back onto the screen);
+    /// left out when the scrollback filled up and dropped rows
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub history_moved: Option<i64>,
+}
+
+/// INFO
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct InfoResponse {
+    pub command: Vec<String>,
+    /// Seconds since the Unix epoch
+    pub started_at: u64,
+    /// "xterm" or "custom"
+    pub emulator: String,
+    pub size: Size,
+    pub term: String,
+    pub scrollback: ScrollbackPolicy,
+    pub socket: String,
+    /// PTY device the command runs on, e.g. "/dev/pts/7"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pty: Option<String>,
+}
+
+/// A process in the session, read from /proc
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ProcessInfo {
+    pub pid: i32,
+    pub ppid: i32,
+    /// In the terminal's foreground process group, i.e. what input goes to
+    pub foreground: bool,
+    /// Command line; `[name]` for processes without one, e.g. zombies
+    pub command: Vec<String>,
+}
+
+/// CHILDREN
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct ChildrenResponse {
+    /// Session leader first, then by PID
+    pub processes: Vec<ProcessInfo>,
+}
+
+/// Raw termios flag words, as 0x-prefixed hex
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TermiosHex {
+    pub iflag: String,
+    pub oflag: String,
+    pub lflag: String,
+    pub cflag: String,
+}
+
+/// Control characters in ^X notation
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub struct ControlChars {
+    pub vintr: String,
+    pub veof: String,
+    pub verase: String,
+    pub vkill: String,
+    pub vsusp: String,
+    pub vquit: String,
+}
+
+/// PTY settings
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TermiosSettings {
+    /// "cooked" or "raw"
+    pub mode: String,
+    pub flags: Vec<String>,
+    pub hex: TermiosHex,
+    pub c_cc: ControlChars,
+}
+
+/// PTY settings in DEBUG, or why they couldn't be read
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum TermiosInfo {
+    Settings(Box<TermiosSettings>),
+    Error {
+        error: String,
+    },
+}
+
+/// DEBUG
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct DebugResponse {
+    pub unhandled: Vec<UnhandledSequence>,
+    pub dropped: usize,
+    pub bytes_read: u64,
+    pub csi_count: u64,
+    pub esc_count: u64,
+    pub print_count: u64,
+    /// Parameters of the latest SGR sequences, oldest first, e.g. "1;31";
+    /// only when asked for with `sgr`
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub recent_sgr: Vec<String>,
+    pub termios: TermiosInfo,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::terminal::CursorShape;
+    use serde::de::DeserializeOwned;
+
+    /// Serialize, check against the expected JSON, and parse back
+    fn round_trip<T: Serialize + DeserializeOwned + PartialEq + std::fmt::Debug>(value: T, expected: serde_json::Value) {
+        let json = serde_json::to_value(&value).unwrap();
+        assert_eq!(json, expected);
+        let parsed: T = serde_json::from_value(json).unwrap();
+        assert_eq!(parsed, value);
+    }
+
+    fn modes() -> Modes {
+        Modes {
+            bracketed_paste: true,
+            focus_reporting: false,
+            application_cursor_keys: false,
+            application_keypad: true,
+            mouse_tracking: MouseTracking::Drag,
+            sgr_mouse: true,
+        }
+    }
+
+    #[test]
+    fn test_empty_response() {
+        round_trip(EmptyResponse {}, serde_json::json!({}));
+    }
+
+    #[test]
+    fn test_ping_response() {
+        round_trip(PingResponse::default(), serde_json::json!({}));
+        round_trip(
+            PingResponse { compression: vec![COMPRESSION_GZIP.to_string()] },
+            serde_json::json!({ "compression": ["gzip"] }),
+        );
+    }
+
+    #[test]
+    fn test_raw_output_response() {
+        round_trip(
+            RawOutputResponse { data_b64: Some("aGk=".to_string()), data_gz: None, dropped: 3, gap: true, offset: 12 },
+            serde_json::json!({ "data_b64": "aGk=", "dropped": 3, "gap": true, "offset": 12 }),
+        );
+        round_trip(
+            RawOutputResponse { data_b64: None, data_gz: Some("H4s=".to_string()), dropped: 0, gap: false, offset: 2 },
+            serde_json::json!({ "data_gz": "H4s=", "dropped": 0, "gap": false, "offset": 2 }),
+        );
+    }
+
+    #[test]
+    fn test_output_response() {
+        let mut output = OutputResponse {
+            screen: "hi\n".to_string(),
+            cursor: Cursor { row: 1, col: 0 },
+            cursor_style: CursorStyle { shape: CursorShape::Underline, blinking: false },
+            size: Size { rows: 24, cols: 80 },
+            from: -2,
+            to: 24,
+            scrollback_available: 2,
+            scrollback_capacity: 10000,
+            generation: 7,
+            alt_screen: false,
+            parser_in_escape: true,
+            wrapped_lines: 3,
+            truncated: false,
+            links: Vec::new(),
+            rows: None,
+        };
+        let mut expected = serde_json::json!({
+            "screen": "hi\n",
+            "cursor": { "row": 1, "col": 0 },
+            "cursor_style": { "shape": "underline", "blinking": false },
+            "size": { "rows": 24, "cols": 80 },
+            "from": -2,
+            "to": 24,
+            "scrollback_available": 2,
+            "scrollback_capacity": 10000,
+            "generation": 7,
+            "alt_screen": false,
+            "parser_in_escape": true,
+            "wrapped_lines": 3,
+            "truncated": false
+        });
+        round_trip(output.clone(), expected.clone());
+
+        output.rows = Some(vec!["hi  ".to_string()]);
+        expected["rows"] = serde_json::json!(["hi  "]);
+        round_trip(output.clone(), expected.clone());
+
+        output.links = vec![Hyperlink { url: "https://example.com".to_string(), text: "hi".to_string(), row: 0, col: 0 }];
+        expected["links"] = serde_json::json!([{ "url": "https://example.com", "text": "hi", "row": 0, "col": 0 }]);
+        round_trip(output, expected);
+    }
+
+    #[test]
+    fn test_scrollback_policy() {
+        round_trip(ScrollbackPolicy::Lines(500), serde_json::json!(500));
+        round_trip(ScrollbackPolicy::None, serde_json::json!("none"));
+        round_trip(ScrollbackPolicy::Unlimited, serde_json::json!("unlimited"));
+        assert!(serde_json::from_value::<ScrollbackPolicy>(serde_json::json!("lots")).is_err());
+        assert!(serde_json::from_value::<ScrollbackPolicy>(serde_json::json!(-1)).is_err());
+    }
+
+    #[test]
+    fn test_status_response() {
+        let mut status = StatusResponse {
+            running: true,
+            stopped: false,
+            ready: true,
+            alt_screen: true,
+            parser_in_escape: false,
+            cursor_style: CursorStyle { shape: CursorShape::Bar, blinking: true },
+            size: Size { rows: 24, cols: 80 },
+            scrollback_available: 0,
+            scrollback_capacity: 10000,
+            scrollback_dropped: 0,
+            scrollback_policy: ScrollbackPolicy::Lines(10000),
+            modes: modes(),
+            activity: None,
+            bell_count: None,
+            exit_code: None,
+            restarts: None,
+            exec_failed: None,
+            exec_error: None,
+            killed_by: None,
+            degraded: None,
+            initial_input_error: None,
+        };
+        let mut expected = serde_json::json!({
+            "running": true,
+            "stopped": false,
+            "ready": true,
+            "alt_screen": true,
+            "parser_in_escape": false,
+            "cursor_style": { "shape": "bar", "blinking": true },
+            "size": { "rows": 24, "cols": 80 },
+            "scrollback_available": 0,
+            "scrollback_capacity": 10000,
+            "scrollback_dropped": 0,
+            "scrollback_policy": 10000,
+            "modes": {
+                "bracketed_paste": true,
+                "focus_reporting": false,
+                "application_cursor_keys": false,
+                "application_keypad": true,
+                "mouse_tracking": "drag",
+                "sgr_mouse": true
+            }
+        });
+        round_trip(status.clone(), expected.clone());
+
+        status.running = false;
+        status.activity = Some(true);
+        status.bell_count = Some(2);
+        status.exit_code = Some(127);
+        status.restarts = Some(1);
+        status.exec_failed = Some(true);
+        status.exec_error = Some("Failed to execute 'foo'".to_string());
+        status.killed_by = Some("max_runtime".to_string());
+        status.degraded = Some("PTY reader thread panicked: boom".to_string());
+        status.initial_input_error = Some("Failed to write input: timed out".to_string());
+        expected["running"] = serde_json::json!(false);
+        expected["activity"] = serde_json::json!(true);
+        expected["bell_count"] = serde_json::json!(2);
+        expected["exit_code"] = serde_json::json!(127);
+        expected["restarts"] = serde_json::json!(1);
+        expected["exec_failed"] = serde_json::json!(true);
+        expected["exec_error"] = serde_json::json!("Failed to execute 'foo'");
+        expected["killed_by"] = serde_json::json!("max_runtime");
+        expected["degraded"] = serde_json::json!("PTY reader thread panicked: boom");
+        expected["initial_input_error"] = serde_json::json!("Failed to write input: timed out");
+        round_trip(status, expected);
+    }
+
+    #[test]
+    fn test_wait_response() {
+        round_trip(
+            WaitResponse { activity: None, exited: None, exit_code: Some(0), matched: None, cursor: None },
+            serde_json::json!({ "exit_code": 0 }),
+        );
+        round_trip(
+            WaitResponse { activity: Some(true), exited: Some(false), exit_code: None, matched: None, cursor: None },
+            serde_json::json!({ "activity": true, "exited": false }),
+        );
+        round_trip(
+            WaitResponse { activity: None, exited: None, exit_code: Some(2), matched: Some(false), cursor: None },
+            serde_json::json!({ "exit_code": 2, "matched": false }),
+        );
+        round_trip(
+            WaitResponse { activity: None, exited: None, exit_code: None, matched: Some(true), cursor: Some(Cursor { row: 3, col: 2 }) },
+            serde_json::json!({ "matched": true, "cursor": { "row": 3, "col": 2 } }),
+        );
+    }
+
+    #[test]
+    fn test_kill_stop_resize_responses() {
+        round_trip(
+            KillResponse { signal_sent: "SIGINT".to_string() },
+            serde_json::json!({ "signal_sent": "SIGINT" }),
+        );
+        round_trip(
+            StopResponse { message: "Shutting down".to_string() },
+            serde_json::json!({ "message": "Shutting down" }),
+        );
+        round_trip(
+            ResizeResponse { cols: 100, rows: 30, old_cols: 80, old_rows: 24, history_moved: None },
+            serde_json::json!({ "cols": 100, "rows": 30, "old_cols": 80, "old_rows": 24 }),
+        );
+        round_trip(
+            ResizeResponse { cols: 20, rows: 5, old_cols: 80, old_rows: 5, history_moved: Some(2) },
+            serde_json::json!({ "cols": 20, "rows": 5, "old_cols": 80, "old_rows": 5, "history_moved": 2 }),
+        );
+    }
+
+    #[test]
+    fn test_info_response() {
+        round_trip(
+            InfoResponse {
+                command: vec!["vim".to_string(), "notes.txt".to_string()],
+                started_at: 1760000000,
+                emulator: "xterm".to_string(),
+                size: Size { rows: 24, cols: 80 },
+                term: "xterm-256color".to_string(),
+                scrollback: ScrollbackPolicy::Unlimited,
+                socket: "/tmp/s.sock".to_string(),
+                pty: Some("/dev/pts/7".to_string()),
+            },
+            serde_json::json!({
+                "command": ["vim", "notes.txt"],
+                "started_at": 1760000000,
+                "emulator": "xterm",
+                "size": { "rows": 24, "cols": 80 },
+                "term": "xterm-256color",
+                "scrollback": "unlimited",
+                "socket": "/tmp/s.sock",
+                "pty": "/dev/pts/7"
+            }),
+        );
+    }
+
+    #[test]
+    fn test_children_response() {
+        round_trip(
+            ChildrenResponse {
+                processes: vec![
+                    ProcessInfo { pid: 100, ppid: 99, foreground: false, command: vec!["bash".to_string()] },
+                    ProcessInfo { pid: 101, ppid: 100, foreground: true, command: vec!["sleep".to_string(), "100".to_string()] },
+                ],
+            },
+            serde_json::json!({
+                "processes": [
+                    { "pid": 100, "ppid": 99, "foreground": false, "command": ["bash"] },
+                    { "pid": 101, "ppid": 100, "foreground": true, "command": ["sleep", "100"] }
+                ]
+            }),
+        );
+    }
+
+    #[test]
+    fn test_debug_response() {
+        let mut debug = DebugResponse {
+            unhandled: vec![UnhandledSequence {
+                sequence: "CSI ? 9999 h".to_string(),
+                raw_hex: "1b 5b 3f 39 39 39 39 68".to_string(),
+                original_hex: None,
+            }],
+            dropped: 0,
+            bytes_read: 10,
+            csi_count: 1,
+            esc_count: 0,
+            print_count: 2,
+            recent_sgr: vec!["1;31".to_string(), String::new()],
+            termios: TermiosInfo::Settings(Box::new(TermiosSettings {
+                mode: "cooked".to_string(),
+                flags: vec!["ECHO".to_string(), "ICANON".to_string()],
+                hex: TermiosHex {
+                    iflag: "0x0500".to_string(),
+                    oflag: "0x0005".to_string(),
+                    lflag: "0x8a3b".to_string(),
+                    cflag: "0x00bf".to_string(),
+                },
+                c_cc: ControlChars {
+                    vintr: "^C".to_string(),
+                    veof: "^D".to_string(),
+                    verase: "^?".to_string(),
+                    vkill: "^U".to_string(),
+                    vsusp: "^Z".to_string(),
+                    vquit: "^\\".to_string(),
+                },
+            })),
+        };
+        let mut expected = serde_json::json!({
+            "unhandled": [{ "sequence": "CSI ? 9999 h", "raw_hex": "1b 5b 3f 39 39 39 39 68" }],
+            "dropped": 0,
+            "bytes_read": 10,
+            "csi_count": 1,
+            "esc_count": 0,
+            "print_count": 2,
+            "recent_sgr": ["1;31", ""],
+            "termios": {
+                "mode": "cooked",
+                "flags": ["ECHO", "ICANON"],
+                "hex": { "iflag": "0x0500", "oflag": "0x0005", "lflag": "0x8a3b", "cflag": "0x00bf" },
+                "c_cc": { "VINTR": "^C", "VEOF": "^D", "VERASE": "^?", "VKILL": "^U", "VSUSP": "^Z", "VQUIT": "^\\" }
+            }
+        });
+        round_trip(debug.clone(), expected.clone());
+
+        debug.termios = TermiosInfo::Error { error: "Failed to get termios: EBADF".to_string() };
+        expected["termios"] = serde_json::json!({ "error": "Failed to get termios: EBADF" });
+        round_trip(debug, expected);
+    }
+}
@@ -0,0 +1,50 @@
+// Pure emulator feeding and rendering
+//
+// The daemon feeds PTY output to the emulator and renders the screen for
+// OUTPUT; these functions do the same with no PTY, process or socket, so
+// emulator behavior can be checked in a plain #[test].
+
+use crate::alacritty_backend::AlacrittyTerminal;
+use crate::custom_screen::CustomScreen;
+use crate::terminal::TerminalEmulator;
+
+/// Terminal emulator backend
+#[derive(Clone, Copy, Debug, Default, clap::ValueEnum)]
+pub enum Emulator {
+    /// Full xterm-256color terminal emulation (alacritty backend)
+    #[default]
+    Xterm,
+    /// Basic ANSI terminal emulation (custom backend)
+    Custom,
+}
+
+/// Any terminal emulator backend
+pub type Screen = dyn TerminalEmulator;
+
+/// A blank `rows` x `cols` screen for `emulator`
+pub fn new_screen(emulator: Emulator, rows: usize, cols: usize) -> Box<Screen> {
+    match emulator {
+        Emulator::Xterm => Box::new(AlacrittyTerminal::new(rows, cols, 0)),
+        Emulator::Custom => Box::new(CustomScreen::new(rows, cols)),
+    }
+}
+
+/// Feed bytes to the screen as if the program had written them.
+///
+/// There is no PTY line discipline in between: write `\r\n` where a
+/// program's `\n` would reach the terminal after the PTY's newline translation.
+/// Replies to queries stay queued in `take_pending_responses`.
+pub fn feed(screen: &mut Screen, bytes: &[u8]) {
+    screen.process_bytes(bytes);
+}
+
+/// The screen as plain text, as OUTPUT returns it: one line per row, trailing
+/// whitespace trimmed
+pub fn render(screen: &Screen) -> String {
+    screen.get_screen_content()
+}
+
+/// The screen with ANSI color codes, as OUTPUT returns it in "ansi" format
+pub fn render_ansi(screen: &Screen) -> String {
+    screen.get_screen_content_ansi()
+}
@@ -4,10 +4,232 @@
 // emulation implementations, allowing different backends (custom, alacritty).
 
 /// Entry in the unhandled escape sequence debug buffer
-#[derive(Clone, serde::Serialize)]
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
 pub struct UnhandledSequence {
     pub sequence: String,
     pub raw_hex: String,
+    /// The bytes exactly as received, when raw capture is enabled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub original_hex: Option<String>,
+}
+
+/// Text the application marked as a hyperlink with OSC 8, one entry per run
+/// of linked text on a screen row
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct Hyperlink {
+    pub url: String,
+    pub text: String,
+    /// Screen position of the first character, 0-indexed
+    pub row: usize,
+    pub col: usize,
+}
+
+/// Which mouse events the application asked to have reported, least to most
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MouseTracking {
+    Off,
+    /// Presses and releases (CSI ?1000h)
+    Click,
+    /// Also movement while a button is held (CSI ?1002h)
+    Drag,
+    /// Also movement with no button held (CSI ?1003h)
+    Motion,
+}
+
+/// Cursor shape set by the application with DECSCUSR (`CSI Ps SP q`)
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CursorShape {
+    #[default]
+    Block,
+    Underline,
+    Bar,
+}
+
+/// Cursor appearance; the default is a steady block
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+pub struct CursorStyle {
+    pub shape: CursorShape,
+    pub blinking: bool,
+}
+
+impl CursorStyle {
+    /// Style for DECSCUSR parameter `code`; 0 restores the default.
+    /// None for codes DECSCUSR doesn't define.
+    pub fn from_decscusr(code: u16) -> Option<Self> {
+        let shape = match code {
+            0 => return Some(Self::default()),
+            1 | 2 => CursorShape::Block,
+            3 | 4 => CursorShape::Underline,
+            5 | 6 => CursorShape::Bar,
+            _ => return None,
+        };
+        Some(CursorStyle { shape, blinking: code % 2 == 1 })
+    }
+
+    /// The DECSCUSR parameter that selects this style
+    pub fn decscusr(&self) -> u16 {
+        let steady = if self.blinking { 0 } else { 1 };
+        match self.shape {
+            CursorShape::Block => 1 + steady,
+            CursorShape::Underline => 3 + steady,
+            CursorShape::Bar => 5 + steady,
+        }
+    }
+}
+
+impl std::fmt::Display for CursorStyle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let shape = match self.shape {
+            CursorShape::Block => "block",
+            CursorShape::Underline => "underline",
+            CursorShape::Bar => "bar",
+        };
+        write!(f, "{} {}", if self.blinking { "blinking" } else { "steady" }, shape)
+    }
+}
+
+/// Ring buffer for tracking unhandled escape sequences
+pub struct DebugBuffer {
+    entries: Vec<UnhandledSequence>,
+    capacity: usize,
+    dropped: usize,
+}
+
+impl DebugBuffer {
+    pub fn new(capacity: usize) -> Self {
+        DebugBuffer {
+            entries: Vec::with_capacity(capacity),
+            capacity,
+            dropped: 0,
+        }
+    }
+
+    pub fn push(&mut self, sequence: String, raw_bytes: &[u8], original: Option<&[u8]>) {
+        let hex = |bytes: &[u8]| bytes.iter().map(|b| format!("{:02x}", b)).collect();
+        let entry = UnhandledSequence {
+            sequence,
+            raw_hex: hex(raw_bytes),
+            original_hex: original.map(hex),
+        };
+
+        if self.entries.len() >= self.capacity {
+            self.entries.remove(0);
+            self.dropped += 1;
+        }
+        self.entries.push(entry);
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.dropped = 0;
+    }
+
+    pub fn get_entries(&self) -> &[UnhandledSequence] {
+        &self.entries
+    }
+
+    pub fn get_dropped(&self) -> usize {
+        self.dropped
+    }
+}
+
+/// Screen state read in one go, so text, cursor, size and scrollback
+/// always agree
+pub struct ScreenSnapshot {
+    pub screen: String,
+    /// Each screen row untrimmed, as get_screen_rows
+    pub rows: Vec<String>,
+    /// The last lines of scrollback asked for, oldest first
+    pub scrollback: String,
+    /// Scrollback lines there are in all
+    pub scrollback_available: usize,
+    /// (row, col), 0-indexed
+    pub cursor: (usize, usize),
+    pub cursor_style: CursorStyle,
+    /// (rows, cols)
+    pub size: (usize, usize),
+    pub alt_screen: bool,
+    pub parser_in_escape: bool,
+    pub generation: u64,
+}
+
+/// Reply to an XTVERSION query (`CSI > q`): DCS > | name(version) ST
+pub fn xtversion_response() -> Vec<u8> {
+    format!("\x1bP>|interminai({})\x1b\\", env!("CARGO_PKG_VERSION")).into_bytes()
+}
+
+/// Reply to a DECRQSS query (`DCS $ q Pt ST`): DCS 1 $ r setting ST when the
+/// setting is known, DCS 0 $ r ST when it isn't
+pub fn decrqss_response(setting: Option<&str>) -> Vec<u8> {
+    match setting {
+        Some(setting) => format!("\x1bP1$r{}\x1b\\", setting).into_bytes(),
+        None => b"\x1bP0$r\x1b\\".to_vec(),
+    }
+}
+
+/// Character cell size assumed for pixel reports; there is no real window
+pub const CELL_WIDTH_PIXELS: usize = 8;
+pub const CELL_HEIGHT_PIXELS: usize = 16;
+
+/// Reply to an XTWINOPS size report (`CSI Ps t`) on a `rows` x `cols`
+/// screen: 14 is the text area in pixels, 18 the text area and 19 the whole
+/// screen in characters (the same here). None for other operations.
+pub fn window_size_report(op: u16, rows: usize, cols: usize) -> Option<Vec<u8>> {
+    let reply = match op {
+        14 => format!("\x1b[4;{};{}t", rows * CELL_HEIGHT_PIXELS, cols * CELL_WIDTH_PIXELS),
+        18 => format!("\x1b[8;{};{}t", rows, cols),
+        19 => format!("\x1b[9;{};{}t", rows, cols),
+        _ => return None,
+    };
+    Some(reply.into_bytes())
+}
+
+/// Follows the escape sequence structure of the byte stream, so callers can
+/// tell when a read stopped partway through a sequence the parser is still
+/// collecting. Mirrors the states of the vte parser both backends are built on.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct EscapeTracker {
+    state: EscapeState,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+enum EscapeState {
+    #[default]
+    Ground,
+    /// After ESC, possibly with intermediates
+    Escape,
+    /// After ESC [, before the final byte
+    Csi,
+    /// OSC body, ended by BEL or ST
+    Osc,
+    /// DCS, SOS, PM or APC body, ended by ST
+    String,
+}
+
+impl EscapeTracker {
+    pub fn advance(&mut self, byte: u8) {
+        use EscapeState::*;
+        self.state = match (self.state, byte) {
+            // CAN and SUB abort any sequence, ESC restarts one (ST is ESC \)
+            (_, 0x18 | 0x1a) => Ground,
+            (_, 0x1b) => Escape,
+            (Ground, _) => Ground,
+            (Escape, b'[') => Csi,
+            (Escape, b']') => Osc,
+            (Escape, b'P' | b'X' | b'^' | b'_') => String,
+            (Escape, 0x30..=0x7e) => Ground,
+            (Csi, 0x40..=0x7e) => Ground,
+            (Osc, 0x07) => Ground,
+            (state, _) => state,
+        };
+    }
+
+    /// The last byte seen left a sequence unfinished
+    pub fn in_escape(&self) -> bool {
+        self.state != EscapeState::Ground
+    }
 }
 
 /// Trait abstracting terminal emulator implementations
@@ -23,6 +245,9 @@ pub trait TerminalEmulator: Send {
     /// Trailing whitespace on each line is trimmed.
     fn get_screen_content(&self) -> String;
 
+    /// Get each screen row as plain text, untrimmed (every row is `cols` cells wide).
+    fn get_screen_rows(&self) -> Vec<String>;
+
     /// Get the screen content with ANSI color codes embedded.
     /// Default implementation returns plain text (same as get_screen_content).
     fn get_screen_content_ansi(&self) -> String {
@@ -35,9 +260,36 @@ pub trait TerminalEmulator: Send {
     /// Get terminal dimensions (rows, cols)
     fn dimensions(&self) -> (usize, usize);
 
+    /// Counter bumped whenever the screen may have changed (output, resize, clear)
+    fn generation(&self) -> u64;
+
+    /// Screen text (with ANSI colors if `ansi`), the last `scrollback` lines
+    /// of scrollback, cursor, size and generation together
+    fn snapshot(&self, ansi: bool, scrollback: usize) -> ScreenSnapshot {
+        ScreenSnapshot {
+            screen: if ansi { self.get_screen_content_ansi() } else { self.get_screen_content() },
+            rows: self.get_screen_rows(),
+            scrollback: match (scrollback, ansi) {
+                (0, _) => String::new(),
+                (_, true) => self.get_scrollback_content_ansi(scrollback),
+                (_, false) => self.get_scrollback_content(scrollback),
+            },
+            scrollback_available: self.scrollback_lines(),
+            cursor: self.cursor_position(),
+            cursor_style: self.cursor_style(),
+            size: self.dimensions(),
+            alt_screen: self.alt_screen(),
+            parser_in_escape: self.parser_in_escape(),
+            generation: self.generation(),
+        }
+    }
+
     /// Resize the terminal to new dimensions
     fn resize(&mut self, rows: usize, cols: usize);
 
+    /// Blank the screen, home the cursor and drop the scrollback
+    fn clear(&mut self);
+
     /// Get pending responses to send back to PTY (e.g., cursor position reports, device attributes)
     fn take_pending_responses(&mut self) -> Vec<Vec<u8>>;
 
@@ -50,12 +302,20 @@ pub trait TerminalEmulator: Send {
     /// Get count of dropped debug entries (due to buffer overflow)
     fn get_debug_dropped(&self) -> usize;
 
+    /// Also record the original bytes of each unhandled sequence, which
+    /// `sequence`/`raw_hex` only reconstruct from the parsed form
+    fn enable_raw_capture(&mut self) {}
+
     /// Get number of lines available in scrollback history
     fn scrollback_lines(&self) -> usize { 0 }
 
     /// Get scrollback buffer capacity (max lines)
     fn scrollback_capacity(&self) -> usize { 0 }
 
+    /// Drop the oldest scrollback lines so at most `max_lines` remain;
+    /// returns how many were dropped. The visible screen is untouched.
+    fn truncate_scrollback(&mut self, _max_lines: usize) -> usize { 0 }
+
     /// Get scrollback content as plain text (most recent `lines` lines before visible screen)
     fn get_scrollback_content(&self, _lines: usize) -> String { String::new() }
 
@@ -63,4 +323,56 @@ pub trait TerminalEmulator: Send {
     fn get_scrollback_content_ansi(&self, lines: usize) -> String {
         self.get_scrollback_content(lines)
     }
+
+    /// Whether the application enabled bracketed paste (CSI ?2004h)
+    fn bracketed_paste(&self) -> bool { false }
+
+    /// Whether the application enabled focus reporting (CSI ?1004h)
+    fn focus_reporting(&self) -> bool { false }
+
+    /// Whether the application enabled application cursor keys (DECCKM, CSI ?1h)
+    fn application_cursor_keys(&self) -> bool { false }
+
+    /// Whether the application enabled application keypad mode (DECKPAM, ESC =)
+    fn application_keypad(&self) -> bool { false }
+
+    /// Mouse events the application enabled reporting for
+    fn mouse_tracking(&self) -> MouseTracking { MouseTracking::Off }
+
+    /// Whether mouse events are to be reported in SGR encoding (CSI ?1006h)
+    fn sgr_mouse(&self) -> bool { false }
+
+    /// Whether resize rewraps lines to the new width, moving rows between the
+    /// screen and scrollback
+    fn reflows_on_resize(&self) -> bool { false }
+
+    /// Whether the application switched to the alternate screen (CSI ?1049h, ?1047h or ?47h)
+    fn alt_screen(&self) -> bool { false }
+
+    /// Whether text wraps at the right margin (DECAWM, CSI ?7h; on by default)
+    fn autowrap(&self) -> bool { true }
+
+    /// Whether cursor positions are relative to the scroll region (DECOM, CSI ?6h)
+    fn origin_mode(&self) -> bool { false }
+
+    /// Cursor style last set with DECSCUSR
+    fn cursor_style(&self) -> CursorStyle { CursorStyle::default() }
+
+    /// Hyperlinks (OSC 8) currently on the visible screen, top to bottom
+    fn hyperlinks(&self) -> Vec<Hyperlink> { Vec::new() }
+
+    /// The bytes processed so far end inside an incomplete escape sequence,
+    /// so the screen may change as soon as the rest arrives
+    fn parser_in_escape(&self) -> bool { false }
+
+    /// Get the number of BEL characters received since the last call, and reset it
+    fn take_bell_count(&mut self) -> usize { 0 }
+
+    /// Get the number of times text reached the right margin and continued
+    /// on the next row since the last call, and reset it
+    fn take_wrapped_lines(&mut self) -> usize { 0 }
+
+    /// Whether text was overwritten at the right margin because autowrap
+    /// (DECAWM) was off, since the last call; resets it
+    fn take_margin_truncated(&mut self) -> bool { false }
 }
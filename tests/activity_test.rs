@@ -250,3 +250,119 @@ fn test_status_activity_clears_after_read() {
     let stdout = String::from_utf8_lossy(&output.stdout).trim().to_string();
     assert!(stdout.contains("Activity: true"), "Third status should report true after new output: got '{}'", stdout);
 }
+
+// Test status --wait-ready blocks until the program has printed something
+#[test]
+fn test_status_wait_ready_blocks_until_output() {
+    let env = TestEnv::new();
+
+    // The helper already waits 300ms, so delay output well past that
+    let daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["bash", "-c", "sleep 1; echo ready; sleep 10"]);
+
+    let output = Command::new(interminai_bin())
+        .arg("status")
+        .arg("--socket")
+        .arg(&daemon.socket_path)
+        .arg("--wait-ready")
+        .timeout(Duration::from_secs(5))
+        .output()
+        .expect("Failed to get status");
+    assert!(output.status.success(), "status --wait-ready failed: {}", String::from_utf8_lossy(&output.stderr));
+
+    // Output must be on screen as soon as --wait-ready returns
+    let output = Command::new(interminai_bin())
+        .arg("output")
+        .arg("--socket")
+        .arg(&daemon.socket_path)
+        .output()
+        .expect("Failed to get output");
+    let screen = String::from_utf8_lossy(&output.stdout);
+    assert!(screen.contains("ready"), "Output should have appeared: got '{}'", screen);
+}
+
+// Test status --wait-ready gives up after --timeout
+#[test]
+fn test_status_wait_ready_times_out() {
+    let env = TestEnv::new();
+
+    let daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["sleep", "10"]);
+
+    let output = Command::new(interminai_bin())
+        .arg("status")
+        .arg("--socket")
+        .arg(&daemon.socket_path)
+        .arg("--wait-ready")
+        .arg("--timeout")
+        .arg("0.5")
+        .timeout(Duration::from_secs(5))
+        .output()
+        .expect("Failed to get status");
+    assert!(!output.status.success(), "status --wait-ready should fail without output");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Timed out"), "Should report the timeout: got '{}'", stderr);
+}
+
+// Test status --wait-ready rejects a --timeout that isn't a usable number of seconds
+#[test]
+fn test_status_wait_ready_rejects_bad_timeout() {
+    let env = TestEnv::new();
+
+    for timeout in ["1e30", "nan", "-1", "0"] {
+        let output = Command::new(interminai_bin())
+            .arg("status")
+            .arg("--socket")
+            .arg(env.socket())
+            .arg("--wait-ready")
+            .arg(format!("--timeout={}", timeout))
+            .timeout(Duration::from_secs(5))
+            .output()
+            .expect("Failed to get status");
+        assert_eq!(output.status.code(), Some(1), "--timeout {} should fail cleanly", timeout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains("--timeout"), "Should name the flag for {}: got '{}'", timeout, stderr);
+    }
+}
+
+fn wait_any(socket: &str) -> serde_json::Value {
+    let output = Command::new(interminai_bin())
+        .arg("wait")
+        .arg("--socket")
+        .arg(socket)
+        .arg("--any")
+        .timeout(Duration::from_secs(5))
+        .output()
+        .expect("Failed to wait");
+    assert!(output.status.success(), "wait --any failed: {}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    serde_json::from_str(stdout.trim()).expect("wait --any should print JSON")
+}
+
+// Test wait --any reports exit when the program exits before printing anything
+#[test]
+fn test_wait_any_exit_first() {
+    let env = TestEnv::new();
+    let daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["bash", "-c", "sleep 0.5; exit 3"]);
+
+    let result = wait_any(&daemon.socket_path);
+    assert_eq!(result["reason"], "exit", "Got: {}", result);
+    assert_eq!(result["exit_code"], 3, "Got: {}", result);
+}
+
+// Test wait --any reports activity when the program prints while still running
+#[test]
+fn test_wait_any_activity_first() {
+    let env = TestEnv::new();
+    let daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["bash", "-c", "sleep 0.5; echo hi; sleep 10"]);
+
+    let result = wait_any(&daemon.socket_path);
+    assert_eq!(result["reason"], "activity", "Got: {}", result);
+
+    // The session is still running
+    Command::new(interminai_bin())
+        .arg("status")
+        .arg("--socket")
+        .arg(&daemon.socket_path)
+        .arg("--quiet")
+        .assert()
+        .success();
+}
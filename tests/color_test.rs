@@ -211,6 +211,37 @@ fn test_color_rgb_color() {
     daemon.stop();
 }
 
+/// Truecolor (and 256-color) in the ITU-T colon form, with or without the
+/// color space slot, must come out the same as the legacy semicolon form
+#[test]
+fn test_color_colon_subparams_match_semicolon_form() {
+    if emulator() == "custom" {
+        return;
+    }
+
+    let env = TestEnv::new();
+    let encodings = [
+        "38;2;255;128;0;48;2;0;64;128",
+        "38:2::255:128:0;48:2::0:64:128",
+        "38:2:255:128:0;48:2:0:64:128",
+    ];
+    let printf_arg: String = encodings.iter()
+        .map(|sgr| format!("\\033[{}mTRUE\\033[0m\\n", sgr))
+        .chain(["\\033[38;5;202mIDX\\033[0m\\n".to_string(), "\\033[38:5:202mIDX\\033[0m\\n".to_string()])
+        .collect();
+    let daemon = DaemonHandle::spawn_printf(&env.socket(), "80x24", &printf_arg);
+
+    let output = daemon.get_output_color();
+    let lines: Vec<&str> = output.lines().collect();
+    assert!(lines[0].contains("TRUE") && lines[0].contains("\x1b["), "Expected a colored line: {:?}", lines[0]);
+    assert_eq!(lines[1], lines[0], "38:2::r:g:b should match 38;2;r;g;b");
+    assert_eq!(lines[2], lines[0], "38:2:r:g:b should match 38;2;r;g;b");
+    assert!(lines[3].contains("IDX") && lines[3].contains("\x1b["), "Expected a colored line: {:?}", lines[3]);
+    assert_eq!(lines[4], lines[3], "38:5:n should match 38;5;n");
+
+    daemon.stop();
+}
+
 /// Test that --color includes background colors
 #[test]
 fn test_color_background_color() {
@@ -93,7 +93,7 @@ fn test_cursor_flag_none() {
     let output = Command::new(interminai_bin())
         .arg("output")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .arg("--cursor")
         .arg("none")
         .timeout(Duration::from_secs(2))
@@ -125,7 +125,7 @@ fn test_cursor_flag_default_is_none() {
     let output = Command::new(interminai_bin())
         .arg("output")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .timeout(Duration::from_secs(2))
         .output()
         .expect("Failed to get output");
@@ -155,7 +155,7 @@ fn test_cursor_flag_print() {
     let output = Command::new(interminai_bin())
         .arg("output")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .arg("--cursor")
         .arg("print")
         .timeout(Duration::from_secs(2))
@@ -194,7 +194,7 @@ fn test_cursor_flag_inverse() {
     Command::new(interminai_bin())
         .arg("input")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .write_stdin("lll") // Move right 3 times
         .timeout(Duration::from_secs(2))
         .assert()
@@ -206,7 +206,7 @@ fn test_cursor_flag_inverse() {
     let check_output = Command::new(interminai_bin())
         .arg("output")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .arg("--cursor")
         .arg("print")
         .timeout(Duration::from_secs(2))
@@ -220,7 +220,7 @@ fn test_cursor_flag_inverse() {
     let output = Command::new(interminai_bin())
         .arg("output")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .arg("--cursor")
         .arg("inverse")
         .timeout(Duration::from_secs(2))
@@ -243,7 +243,7 @@ fn test_cursor_flag_inverse() {
     Command::new(interminai_bin())
         .arg("input")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .write_stdin(":q!\n")
         .timeout(Duration::from_secs(2))
         .output()
@@ -268,7 +268,7 @@ fn test_cursor_flag_both() {
     Command::new(interminai_bin())
         .arg("input")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .write_stdin("lllll") // Move right 5 times
         .timeout(Duration::from_secs(2))
         .assert()
@@ -280,7 +280,7 @@ fn test_cursor_flag_both() {
     let output = Command::new(interminai_bin())
         .arg("output")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .arg("--cursor")
         .arg("both")
         .timeout(Duration::from_secs(2))
@@ -306,7 +306,7 @@ fn test_cursor_flag_both() {
     Command::new(interminai_bin())
         .arg("input")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .write_stdin(":q!\n")
         .timeout(Duration::from_secs(2))
         .output()
@@ -326,7 +326,7 @@ fn test_cursor_position_reported() {
     let output = Command::new(interminai_bin())
         .arg("output")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .arg("--cursor")
         .arg("print")
         .timeout(Duration::from_secs(2))
@@ -355,7 +355,7 @@ fn test_cursor_with_interactive_session() {
     Command::new(interminai_bin())
         .arg("input")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .write_stdin("Hello")
         .timeout(Duration::from_secs(2))
         .assert()
@@ -367,7 +367,7 @@ fn test_cursor_with_interactive_session() {
     let output = Command::new(interminai_bin())
         .arg("output")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .arg("--cursor")
         .arg("print")
         .timeout(Duration::from_secs(2))
@@ -396,7 +396,7 @@ fn test_cursor_inverse_does_not_obscure_text() {
     let output = Command::new(interminai_bin())
         .arg("output")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .arg("--cursor")
         .arg("inverse")
         .timeout(Duration::from_secs(2))
@@ -430,7 +430,7 @@ fn test_cursor_print_appears_before_screen() {
     let output = Command::new(interminai_bin())
         .arg("output")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .arg("--cursor")
         .arg("print")
         .timeout(Duration::from_secs(2))
@@ -467,7 +467,7 @@ fn test_cursor_with_multiline_output() {
     Command::new(interminai_bin())
         .arg("input")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .write_stdin("jlllll") // j=down, then 5x right
         .timeout(Duration::from_secs(2))
         .assert()
@@ -479,7 +479,7 @@ fn test_cursor_with_multiline_output() {
     let check_output = Command::new(interminai_bin())
         .arg("output")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .arg("--cursor")
         .arg("print")
         .timeout(Duration::from_secs(2))
@@ -493,7 +493,7 @@ fn test_cursor_with_multiline_output() {
     let output = Command::new(interminai_bin())
         .arg("output")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .arg("--cursor")
         .arg("both")
         .timeout(Duration::from_secs(2))
@@ -519,7 +519,7 @@ fn test_cursor_with_multiline_output() {
     Command::new(interminai_bin())
         .arg("input")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .write_stdin(":q!\n")
         .timeout(Duration::from_secs(2))
         .output()
@@ -562,7 +562,7 @@ fn test_dsr_cursor_position_query_responds() {
     let output = Command::new(interminai_bin())
         .arg("output")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .timeout(Duration::from_secs(2))
         .output()
         .expect("Failed to get output");
@@ -606,7 +606,7 @@ fn test_dsr_device_status_query_responds() {
     let output = Command::new(interminai_bin())
         .arg("output")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .timeout(Duration::from_secs(2))
         .output()
         .expect("Failed to get output");
@@ -647,7 +647,7 @@ fn test_primary_device_attributes_responds() {
     let output = Command::new(interminai_bin())
         .arg("output")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .timeout(Duration::from_secs(2))
         .output()
         .expect("Failed to get output");
@@ -661,3 +661,120 @@ fn test_primary_device_attributes_responds() {
 
     daemon.stop();
 }
+
+#[test]
+fn test_secondary_device_attributes_responds() {
+    // Test that ESC[>c (Secondary Device Attributes) gets a proper response
+    let env = TestEnv::new();
+
+    let daemon = DaemonHandle::spawn_with_socket(
+        &env.socket(),
+        &["bash", "-c", r#"
+            # Query secondary device attributes by sending ESC[>c
+            printf '\033[>c'
+            # Read response with 1 second timeout
+            # The response is ESC[>Pp;Pv;Pcc (terminal type, version, ROM cartridge)
+            if read -r -t 1 -d 'c' response; then
+                echo "GOT_DA2:${response#*>}"
+            else
+                echo "NO_RESPONSE"
+            fi
+            sleep 5
+        "#]
+    );
+
+    thread::sleep(Duration::from_millis(1500));
+
+    let output = Command::new(interminai_bin())
+        .arg("output")
+        .arg("--socket")
+        .arg(env.socket())
+        .timeout(Duration::from_secs(2))
+        .output()
+        .expect("Failed to get output");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("GOT_DA2:0;"),
+        "Should receive secondary DA response. Got: {}", stdout);
+    assert!(!stdout.contains("NO_RESPONSE"),
+        "Secondary DA query should not timeout. Got: {}", stdout);
+
+    daemon.stop();
+}
+
+fn cursor_json_line(socket: &str, extra_args: &[&str]) -> serde_json::Value {
+    let output = Command::new(interminai_bin())
+        .arg("output")
+        .arg("--socket")
+        .arg(socket)
+        .arg("--cursor")
+        .arg("json")
+        .args(extra_args)
+        .timeout(Duration::from_secs(2))
+        .output()
+        .expect("Failed to get output");
+    assert!(output.status.success(), "output failed: {}", String::from_utf8_lossy(&output.stderr));
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let first = stdout.lines().next().expect("Expected a cursor line");
+    serde_json::from_str(first).expect("Cursor line should be JSON")
+}
+
+#[test]
+fn test_cursor_json_one_based_by_default() {
+    let env = TestEnv::new();
+    let daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["bash", "-c", "printf 'AB'; sleep 10"]);
+
+    thread::sleep(Duration::from_millis(500));
+
+    // Protocol position is row 0, col 2
+    let cursor = cursor_json_line(&env.socket(), &[]);
+    assert_eq!(cursor["cursor"]["row"], 1, "Got: {}", cursor);
+    assert_eq!(cursor["cursor"]["col"], 3, "Got: {}", cursor);
+
+    daemon.stop();
+}
+
+#[test]
+fn test_cursor_json_zero_based_matches_protocol() {
+    let env = TestEnv::new();
+    let daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["bash", "-c", "printf 'AB'; sleep 10"]);
+
+    thread::sleep(Duration::from_millis(500));
+
+    let cursor = cursor_json_line(&env.socket(), &["--cursor-base", "0"]);
+    assert_eq!(cursor["cursor"]["row"], 0, "Got: {}", cursor);
+    assert_eq!(cursor["cursor"]["col"], 2, "Got: {}", cursor);
+
+    // The cursor line leads jsonl output too
+    let cursor = cursor_json_line(&env.socket(), &["--cursor-base", "0", "--format", "jsonl"]);
+    assert_eq!(cursor["cursor"]["col"], 2, "Got: {}", cursor);
+
+    daemon.stop();
+}
+
+#[test]
+fn test_cursor_print_zero_based() {
+    let env = TestEnv::new();
+    let daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["bash", "-c", "printf 'AB'; sleep 10"]);
+
+    thread::sleep(Duration::from_millis(500));
+
+    let output = Command::new(interminai_bin())
+        .arg("output")
+        .arg("--socket")
+        .arg(env.socket())
+        .arg("--cursor")
+        .arg("print")
+        .arg("--cursor-base")
+        .arg("0")
+        .timeout(Duration::from_secs(2))
+        .output()
+        .expect("Failed to get output");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Cursor: row 0, col 2"), "Got: {}", stdout);
+
+    daemon.stop();
+}
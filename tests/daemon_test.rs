@@ -1,10 +1,11 @@
 use assert_cmd::Command;
 use std::thread;
+use std::path::Path;
 use std::time::Duration;
 use tempfile::TempDir;
 
 mod common;
-use common::{interminai_bin, interminai_server_bin, interminai_client_bin, emulator_args};
+use common::{interminai_bin, interminai_server_bin, interminai_client_bin, emulator, emulator_args};
 
 #[test]
 fn test_daemon_mode_returns_immediately() {
@@ -145,6 +146,73 @@ fn test_daemon_mode_survives_parent_exit() {
         .success();
 }
 
+/// Whether a process is gone (a zombie waiting to be reaped counts as gone)
+fn process_gone(pid: u32) -> bool {
+    match std::fs::read_to_string(format!("/proc/{}/stat", pid)) {
+        // The state follows the parenthesized command name
+        Ok(stat) => stat.rsplit(')').next().is_some_and(|rest| rest.trim_start().starts_with('Z')),
+        Err(_) => true,
+    }
+}
+
+#[test]
+fn test_rapid_start_stop_cleans_up() {
+    if !Path::new("/proc/self/stat").exists() {
+        return;
+    }
+
+    let daemons: Vec<(u32, String)> = (0..20).map(|_| {
+        let output = Command::new(interminai_server_bin())
+            .arg("start")
+            .args(emulator_args())
+            .arg("--")
+            .arg("sleep")
+            .arg("30")
+            .output()
+            .expect("Failed to execute interminai");
+        assert!(output.status.success(), "Command failed: {}", String::from_utf8_lossy(&output.stderr));
+        let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+        let field = |name: &str| stdout.lines()
+            .find_map(|line| line.strip_prefix(name))
+            .unwrap_or_else(|| panic!("Output missing '{}' line: {}", name, stdout))
+            .trim()
+            .to_string();
+        (field("PID: ").parse().expect("Invalid PID"), field("Socket: "))
+    }).collect();
+
+    for (_, socket) in &daemons {
+        // Stop as soon as the socket accepts connections
+        let mut stopped = false;
+        for _ in 0..50 {
+            let result = Command::new(interminai_client_bin())
+                .arg("stop")
+                .arg("--socket")
+                .arg(socket)
+                .output()
+                .expect("Failed to run stop");
+            if result.status.success() {
+                stopped = true;
+                break;
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+        assert!(stopped, "Could not stop daemon at {}", socket);
+    }
+
+    for (pid, socket) in &daemons {
+        let socket_dir = Path::new(socket).parent().expect("Socket should have parent directory");
+        let cleaned_up = || process_gone(*pid) && !Path::new(socket).exists() && !socket_dir.exists();
+        for _ in 0..100 {
+            if cleaned_up() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+        assert!(process_gone(*pid), "Daemon {} should have exited", pid);
+        assert!(!socket_dir.exists(), "Socket directory {} should be removed", socket_dir.display());
+    }
+}
+
 #[test]
 fn test_auto_generated_socket_from_output() {
     // Start without --socket and parse socket path from output
@@ -205,7 +273,7 @@ fn test_auto_generated_socket_from_output() {
         .success();
 
     // Verify socket and its directory were cleaned up
-    // (daemon has 200ms delay before cleanup, so wait longer)
+    // (the daemon first gives the child up to 200ms to exit, so wait longer)
     thread::sleep(Duration::from_millis(500));
     let socket = std::path::Path::new(socket_path);
     assert!(!socket.exists(),
@@ -261,3 +329,1242 @@ fn test_pty_dump_captures_output() {
     assert!(dump_content.contains("TESTMARKER123"),
         "Dump file should contain TESTMARKER123. Got: {}", dump_content);
 }
+
+#[test]
+fn test_log_records_output_and_input() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let socket_path = temp_dir.path().join("log.sock");
+    let log_path = temp_dir.path().join("session.log");
+
+    let output = Command::new(interminai_server_bin())
+        .arg("start")
+        .args(emulator_args())
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .arg("--log")
+        .arg(log_path.to_str().unwrap())
+        .arg("--log-input")
+        .arg("--")
+        .arg("cat")
+        .output()
+        .expect("Failed to execute interminai");
+
+    assert!(output.status.success(), "Command failed: {}", String::from_utf8_lossy(&output.stderr));
+
+    thread::sleep(Duration::from_millis(500));
+
+    Command::new(interminai_client_bin())
+        .arg("input")
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .arg("--text")
+        .arg("LOGMARK\\n")
+        .assert()
+        .success();
+
+    thread::sleep(Duration::from_millis(300));
+
+    Command::new(interminai_client_bin())
+        .arg("stop")
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .assert()
+        .success();
+
+    thread::sleep(Duration::from_millis(200));
+
+    let log = std::fs::read_to_string(&log_path).expect("Failed to read log file");
+    assert!(log.contains("] input 8 bytes"), "Log should record the input. Got: {}", log);
+    assert!(log.contains("4c 4f 47 4d 41 52 4b 0a"), "Log should hex dump the input. Got: {}", log);
+    assert!(log.contains("] output "), "Log should record the echoed output. Got: {}", log);
+    assert!(log.contains("|LOGMARK"), "Log should show the text column. Got: {}", log);
+}
+
+#[test]
+fn test_log_input_requires_log() {
+    Command::new(interminai_server_bin())
+        .arg("start")
+        .arg("--log-input")
+        .arg("--")
+        .arg("true")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_trace_input_logs_payloads() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let socket_path = temp_dir.path().join("trace.sock");
+    let log_path = temp_dir.path().join("session.log");
+
+    let output = Command::new(interminai_server_bin())
+        .arg("start")
+        .args(emulator_args())
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .arg("--log")
+        .arg(log_path.to_str().unwrap())
+        .arg("--trace-input")
+        .arg("--")
+        .arg("cat")
+        .output()
+        .expect("Failed to execute interminai");
+
+    assert!(output.status.success(), "Command failed: {}", String::from_utf8_lossy(&output.stderr));
+
+    thread::sleep(Duration::from_millis(500));
+
+    for args in [&["--text", "hi\\n"][..], &["--key", "up"][..], &["--text", "\\t"][..]] {
+        Command::new(interminai_client_bin())
+            .arg("input")
+            .arg("--socket")
+            .arg(socket_path.to_str().unwrap())
+            .args(args)
+            .assert()
+            .success();
+    }
+
+    Command::new(interminai_client_bin())
+        .arg("stop")
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .assert()
+        .success();
+
+    thread::sleep(Duration::from_millis(200));
+
+    let log = std::fs::read_to_string(&log_path).expect("Failed to read log file");
+    assert!(log.contains("] trace 3 bytes, 3 written: 68 69 0a \"hi\\n\""), "Log should trace the text. Got: {}", log);
+    assert!(log.contains("] trace 3 bytes, 3 written: 1b 5b 41 \"\\x1b[A\""), "Log should trace the key. Got: {}", log);
+    assert!(log.contains("] trace 1 bytes, 1 written: 09 \"\\t\""), "Log should trace the tab. Got: {}", log);
+    assert!(!log.contains("] input "), "Without --log-input, PTY writes are not logged. Got: {}", log);
+}
+
+#[test]
+fn test_log_merged_interleaves_input_and_output() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let socket_path = temp_dir.path().join("merged.sock");
+    let log_path = temp_dir.path().join("merged.log");
+
+    let output = Command::new(interminai_server_bin())
+        .arg("start")
+        .args(emulator_args())
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .arg("--log-merged")
+        .arg(log_path.to_str().unwrap())
+        .arg("--")
+        .arg("cat")
+        .output()
+        .expect("Failed to execute interminai");
+
+    assert!(output.status.success(), "Command failed: {}", String::from_utf8_lossy(&output.stderr));
+
+    thread::sleep(Duration::from_millis(500));
+
+    for text in ["first\\n", "second\\n"] {
+        Command::new(interminai_client_bin())
+            .arg("input")
+            .arg("--socket")
+            .arg(socket_path.to_str().unwrap())
+            .arg("--text")
+            .arg(text)
+            .assert()
+            .success();
+        thread::sleep(Duration::from_millis(300));
+    }
+
+    Command::new(interminai_client_bin())
+        .arg("stop")
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .assert()
+        .success();
+
+    thread::sleep(Duration::from_millis(200));
+
+    let log = std::fs::read_to_string(&log_path).expect("Failed to read log file");
+    let position = |needle: &str| log.find(needle).unwrap_or_else(|| panic!("Log should contain {:?}. Got: {}", needle, log));
+    // Each line goes in, then cat's echo and its copy of the line come out
+    let first_in = position("] IN  6 \"first\\n\"");
+    let first_out = position("first\\r\\n");
+    let second_in = position("] IN  7 \"second\\n\"");
+    let second_out = position("second\\r\\n");
+    assert!(first_in < first_out && first_out < second_in && second_in < second_out,
+        "Chunks should be logged in the order they happened. Got: {}", log);
+    assert!(log.contains("] OUT "), "Output should be tagged OUT. Got: {}", log);
+}
+
+/// Start `echo TERM=$TERM` with extra start arguments and return the screen
+fn start_and_echo_term(extra_args: &[&str]) -> String {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let socket_path = temp_dir.path().join("term.sock");
+
+    let output = Command::new(interminai_server_bin())
+        .arg("start")
+        .args(emulator_args())
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .args(extra_args)
+        .arg("--")
+        .arg("bash")
+        .arg("-c")
+        .arg("echo TERM=$TERM; sleep 5")
+        .output()
+        .expect("Failed to execute interminai");
+    assert!(output.status.success(), "Command failed: {}", String::from_utf8_lossy(&output.stderr));
+
+    thread::sleep(Duration::from_millis(500));
+
+    let output = Command::new(interminai_client_bin())
+        .arg("output")
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .arg("--no-color")
+        .output()
+        .expect("Failed to get output");
+
+    Command::new(interminai_client_bin())
+        .arg("stop")
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .output()
+        .ok();
+
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+#[test]
+fn test_term_option_sets_child_term() {
+    let screen = start_and_echo_term(&["--term", "vt100"]);
+    assert!(screen.contains("TERM=vt100"), "Child should see TERM=vt100. Got: {}", screen);
+}
+
+#[test]
+fn test_term_defaults_to_backend() {
+    let expected = if emulator() == "custom" { "TERM=ansi" } else { "TERM=xterm-256color" };
+    let screen = start_and_echo_term(&[]);
+    assert!(screen.contains(expected), "Child should see {}. Got: {}", expected, screen);
+}
+
+fn start_and_stop(socket_path: &Path, script: &str) {
+    let output = Command::new(interminai_server_bin())
+        .arg("start")
+        .args(emulator_args())
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .arg("--")
+        .arg("bash")
+        .arg("-c")
+        .arg(script)
+        .output()
+        .expect("Failed to execute interminai");
+
+    assert!(output.status.success(), "Command failed: {}", String::from_utf8_lossy(&output.stderr));
+
+    thread::sleep(Duration::from_millis(500));
+
+    Command::new(interminai_client_bin())
+        .arg("stop")
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .assert()
+        .success();
+
+    // Let the daemon finish its teardown
+    thread::sleep(Duration::from_millis(800));
+}
+
+#[test]
+fn test_stop_sends_sighup() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let socket_path = temp_dir.path().join("hup.sock");
+    let marker = temp_dir.path().join("hup.marker");
+
+    let script = format!("trap 'echo hup > {}; exit 0' HUP; while true; do sleep 0.1; done", marker.display());
+    start_and_stop(&socket_path, &script);
+
+    let content = std::fs::read_to_string(&marker).expect("SIGHUP trap should have written the marker");
+    assert_eq!(content.trim(), "hup");
+}
+
+#[test]
+fn test_stop_terminates_child_ignoring_sighup() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let socket_path = temp_dir.path().join("nohup.sock");
+    let pid_file = temp_dir.path().join("child.pid");
+
+    let script = format!("trap '' HUP; echo $$ > {}; while true; do sleep 0.1; done", pid_file.display());
+    start_and_stop(&socket_path, &script);
+
+    let pid = std::fs::read_to_string(&pid_file).expect("Child should have written its pid");
+    // Gone, or a zombie waiting to be reaped by init
+    let status = std::fs::read_to_string(format!("/proc/{}/status", pid.trim())).unwrap_or_default();
+    assert!(!status.lines().any(|line| line.starts_with("State:") && !line.contains("Z")),
+        "Child ignoring SIGHUP should still be terminated on stop. Got: {}", status);
+}
+
+#[test]
+fn test_start_quiet_prints_only_socket_path() {
+    // Global flag works before the subcommand...
+    let output = Command::new(interminai_server_bin())
+        .arg("--quiet")
+        .arg("start")
+        .args(emulator_args())
+        .arg("--")
+        .arg("sleep")
+        .arg("10")
+        .output()
+        .expect("Failed to execute interminai");
+    assert!(output.status.success(), "Command failed: {}", String::from_utf8_lossy(&output.stderr));
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 1, "Quiet start should print one line. Got: {}", stdout);
+    let socket = lines[0];
+    assert!(socket.starts_with('/') && socket.ends_with("socket"),
+        "The line should be the bare socket path. Got: {}", socket);
+
+    thread::sleep(Duration::from_millis(300));
+
+    // ...and after it: status --quiet exits 0 for a running session
+    Command::new(interminai_client_bin())
+        .arg("status")
+        .arg("--socket")
+        .arg(socket)
+        .arg("--quiet")
+        .assert()
+        .success()
+        .stdout("");
+
+    Command::new(interminai_client_bin())
+        .arg("stop")
+        .arg("--socket")
+        .arg(socket)
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_start_verbose_prints_size() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let socket_path = temp_dir.path().join("verbose.sock");
+
+    let output = Command::new(interminai_server_bin())
+        .arg("start")
+        .arg("--verbose")
+        .args(emulator_args())
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .arg("--size")
+        .arg("100x30")
+        .arg("--")
+        .arg("sleep")
+        .arg("10")
+        .output()
+        .expect("Failed to execute interminai");
+    assert!(output.status.success(), "Command failed: {}", String::from_utf8_lossy(&output.stderr));
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Socket: "), "Verbose keeps the normal lines. Got: {}", stdout);
+    assert!(stdout.contains("PID: "), "Verbose keeps the normal lines. Got: {}", stdout);
+    assert!(stdout.contains("Size: 100x30"), "Verbose should print the size. Got: {}", stdout);
+
+    thread::sleep(Duration::from_millis(300));
+
+    Command::new(interminai_client_bin())
+        .arg("stop")
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_quiet_conflicts_with_verbose() {
+    Command::new(interminai_bin())
+        .arg("--quiet")
+        .arg("--verbose")
+        .arg("status")
+        .arg("--socket")
+        .arg("/nonexistent/socket")
+        .assert()
+        .failure();
+}
+
+#[test]
+fn test_start_print_socket_outputs_exactly_the_path() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let socket_path = temp_dir.path().join("print.sock");
+
+    let output = Command::new(interminai_server_bin())
+        .arg("start")
+        .args(emulator_args())
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .arg("--print-socket")
+        .arg("--")
+        .arg("sleep")
+        .arg("10")
+        .output()
+        .expect("Failed to execute interminai");
+    assert!(output.status.success(), "Command failed: {}", String::from_utf8_lossy(&output.stderr));
+
+    assert_eq!(String::from_utf8_lossy(&output.stdout), format!("{}\n", socket_path.display()));
+
+    thread::sleep(Duration::from_millis(300));
+
+    Command::new(interminai_client_bin())
+        .arg("stop")
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_start_rows_and_cols_set_size() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let socket_path = temp_dir.path().join("size.sock");
+
+    Command::new(interminai_server_bin())
+        .arg("start")
+        .args(emulator_args())
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .arg("--rows")
+        .arg("40")
+        .arg("--cols")
+        .arg("120")
+        .arg("--")
+        .arg("sleep")
+        .arg("10")
+        .assert()
+        .success();
+
+    thread::sleep(Duration::from_millis(300));
+
+    let output = Command::new(interminai_client_bin())
+        .arg("status")
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .output()
+        .expect("Failed to get status");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Size: 120x40"), "Expected a 120x40 terminal. Got: {}", stdout);
+
+    Command::new(interminai_client_bin())
+        .arg("stop")
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_start_size_conflicts_with_rows() {
+    let output = Command::new(interminai_server_bin())
+        .arg("start")
+        .arg("--size")
+        .arg("80x24")
+        .arg("--rows")
+        .arg("40")
+        .arg("--")
+        .arg("sleep")
+        .arg("10")
+        .output()
+        .expect("Failed to execute interminai");
+
+    assert!(!output.status.success(), "--size and --rows should be mutually exclusive");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("cannot be used with"), "Expected a conflict error. Got: {}", stderr);
+}
+
+#[test]
+fn test_start_rejects_zero_rows() {
+    Command::new(interminai_server_bin())
+        .arg("start")
+        .arg("--rows")
+        .arg("0")
+        .arg("--")
+        .arg("sleep")
+        .arg("10")
+        .assert()
+        .failure();
+}
+
+/// Start with an auto-generated socket under the given environment, stop it,
+/// and return the socket path it reported
+fn auto_socket_with_env(env: &[(&str, &Path)]) -> String {
+    let mut cmd = Command::new(interminai_server_bin());
+    cmd.arg("start")
+        .arg("--print-socket")
+        .args(emulator_args())
+        .arg("--")
+        .arg("sleep")
+        .arg("10");
+    for (key, value) in env {
+        cmd.env(key, value);
+    }
+    let output = cmd.output().expect("Failed to execute interminai");
+    assert!(output.status.success(), "Command failed: {}", String::from_utf8_lossy(&output.stderr));
+
+    let socket_path = String::from_utf8_lossy(&output.stdout).trim().to_string();
+
+    thread::sleep(Duration::from_millis(300));
+
+    Command::new(interminai_client_bin())
+        .arg("stop")
+        .arg("--socket")
+        .arg(&socket_path)
+        .assert()
+        .success();
+
+    socket_path
+}
+
+#[test]
+fn test_auto_socket_prefers_xdg_runtime_dir() {
+    let runtime_dir = TempDir::new().expect("Failed to create temp dir");
+
+    let socket_path = auto_socket_with_env(&[("XDG_RUNTIME_DIR", runtime_dir.path())]);
+    assert!(Path::new(&socket_path).starts_with(runtime_dir.path()),
+        "Socket should be under $XDG_RUNTIME_DIR {}. Got: {}", runtime_dir.path().display(), socket_path);
+}
+
+#[test]
+fn test_auto_socket_falls_back_to_tmpdir() {
+    let tmp_dir = TempDir::new().expect("Failed to create temp dir");
+    let missing = tmp_dir.path().join("no-such-runtime-dir");
+
+    let socket_path = auto_socket_with_env(&[("XDG_RUNTIME_DIR", &missing), ("TMPDIR", tmp_dir.path())]);
+    assert!(Path::new(&socket_path).starts_with(tmp_dir.path()),
+        "Socket should fall back to $TMPDIR {}. Got: {}", tmp_dir.path().display(), socket_path);
+}
+
+fn status_output(socket_path: &Path) -> String {
+    let output = Command::new(interminai_client_bin())
+        .arg("status")
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .output()
+        .expect("Failed to get status");
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+#[test]
+fn test_keepalive_restarts_bounded_times() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let socket_path = temp_dir.path().join("keepalive.sock");
+    let runs = temp_dir.path().join("runs");
+
+    // Each run appends a line and exits immediately
+    let script = format!("echo run >> {}; exit 7", runs.display());
+    Command::new(interminai_server_bin())
+        .arg("start")
+        .args(emulator_args())
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .arg("--keepalive")
+        .arg("--max-restarts")
+        .arg("3")
+        .arg("--")
+        .arg("bash")
+        .arg("-c")
+        .arg(&script)
+        .assert()
+        .success();
+
+    thread::sleep(Duration::from_millis(1500));
+
+    let status = status_output(&socket_path);
+    assert!(status.contains("Running: false"), "Should give up after the limit. Got: {}", status);
+    assert!(status.contains("Restarts: 3"), "Should report the restart count. Got: {}", status);
+    assert!(status.contains("Exit code: 7"), "Should report the last exit code. Got: {}", status);
+
+    // The first run plus three restarts
+    let runs = std::fs::read_to_string(&runs).expect("Failed to read run log");
+    assert_eq!(runs.lines().count(), 4, "Got: {}", runs);
+
+    Command::new(interminai_client_bin())
+        .arg("stop")
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_keepalive_restart_resets_screen() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let socket_path = temp_dir.path().join("keepalive.sock");
+    let marker = temp_dir.path().join("ran-once");
+
+    // First run prints FIRST and exits; the restarted run prints SECOND and stays
+    let script = format!("if [ -e {0} ]; then echo SECOND; sleep 10; else touch {0}; echo FIRST; fi", marker.display());
+    Command::new(interminai_server_bin())
+        .arg("start")
+        .args(emulator_args())
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .arg("--keepalive")
+        .arg("--")
+        .arg("bash")
+        .arg("-c")
+        .arg(&script)
+        .assert()
+        .success();
+
+    thread::sleep(Duration::from_millis(1000));
+
+    let status = status_output(&socket_path);
+    assert!(status.contains("Running: true"), "Restarted command should be running. Got: {}", status);
+    assert!(status.contains("Restarts: 1"), "Got: {}", status);
+
+    let output = Command::new(interminai_client_bin())
+        .arg("output")
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .output()
+        .expect("Failed to get output");
+    let screen = String::from_utf8_lossy(&output.stdout);
+    assert!(screen.contains("SECOND"), "Screen should show the new run. Got: {}", screen);
+    assert!(!screen.contains("FIRST"), "Screen should be reset on restart. Got: {}", screen);
+
+    // Stopping must not trigger another restart
+    Command::new(interminai_client_bin())
+        .arg("stop")
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .assert()
+        .success();
+    thread::sleep(Duration::from_millis(500));
+    assert!(!socket_path.exists() || status_output(&socket_path).is_empty(),
+        "Daemon should have shut down");
+}
+
+#[test]
+fn test_on_exit_hook_gets_exit_code() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let socket_path = temp_dir.path().join("hook.sock");
+    let hook_file = temp_dir.path().join("hook.txt");
+
+    let hook = format!("echo \"$INTERMINAI_EXIT_CODE $INTERMINAI_SOCKET\" > {}", hook_file.display());
+    Command::new(interminai_server_bin())
+        .arg("start")
+        .args(emulator_args())
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .arg("--on-exit")
+        .arg(&hook)
+        .arg("--")
+        .arg("bash")
+        .arg("-c")
+        .arg("sleep 0.3; exit 3")
+        .assert()
+        .success();
+
+    let mut contents = String::new();
+    for _ in 0..30 {
+        thread::sleep(Duration::from_millis(100));
+        contents = std::fs::read_to_string(&hook_file).unwrap_or_default();
+        if contents.ends_with('\n') {
+            break;
+        }
+    }
+    assert_eq!(contents, format!("3 {}\n", socket_path.display()), "Hook should see the exit code and socket");
+
+    Command::new(interminai_client_bin())
+        .arg("stop")
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .assert()
+        .success();
+}
+
+fn start_sleeper(socket_path: &Path, extra_args: &[&str]) -> assert_cmd::assert::Assert {
+    Command::new(interminai_server_bin())
+        .arg("start")
+        .args(emulator_args())
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .args(extra_args)
+        .arg("--")
+        .arg("sleep")
+        .arg("30")
+        .assert()
+}
+
+fn start_sleeper_and_wait(socket_path: &Path, extra_args: &[&str]) {
+    start_sleeper(socket_path, extra_args).success();
+    // The daemon binds the socket after start returns
+    thread::sleep(Duration::from_millis(500));
+}
+
+#[test]
+fn test_start_refuses_socket_in_use() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let socket_path = temp_dir.path().join("shared.sock");
+
+    start_sleeper_and_wait(&socket_path, &[]);
+    start_sleeper(&socket_path, &[])
+        .failure()
+        .stderr(predicates::str::contains("already in use by a running daemon"));
+
+    // The first daemon still answers
+    Command::new(interminai_client_bin())
+        .arg("status")
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .assert()
+        .success();
+
+    // --force replaces it
+    start_sleeper_and_wait(&socket_path, &["--force"]);
+    Command::new(interminai_client_bin())
+        .arg("stop")
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_start_replaces_stale_socket() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let socket_path = temp_dir.path().join("stale.sock");
+
+    // Bound and dropped: the file stays but nobody listens
+    drop(std::os::unix::net::UnixListener::bind(&socket_path).unwrap());
+    assert!(socket_path.exists());
+
+    start_sleeper_and_wait(&socket_path, &[]);
+    Command::new(interminai_client_bin())
+        .arg("stop")
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .assert()
+        .success();
+}
+
+fn send_signal(socket_path: &Path, signal: &str) {
+    Command::new(interminai_client_bin())
+        .arg("kill")
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .arg("--signal")
+        .arg(signal)
+        .assert()
+        .success();
+    thread::sleep(Duration::from_millis(200));
+}
+
+#[test]
+fn test_status_reports_stopped_child() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let socket_path = temp_dir.path().join("stopped.sock");
+
+    start_sleeper_and_wait(&socket_path, &[]);
+    assert!(status_output(&socket_path).contains("Stopped: false"));
+
+    send_signal(&socket_path, "SIGSTOP");
+    let status = status_output(&socket_path);
+    assert!(status.contains("Running: true"), "A stopped child is still running: {}", status);
+    assert!(status.contains("Stopped: true"), "Status should show the child stopped: {}", status);
+
+    send_signal(&socket_path, "SIGCONT");
+    let status = status_output(&socket_path);
+    assert!(status.contains("Stopped: false"), "Status should show the child resumed: {}", status);
+
+    Command::new(interminai_client_bin())
+        .arg("stop")
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_pass_fd_keeps_listed_descriptors_only() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let socket_path = temp_dir.path().join("passfd.sock");
+
+    // fd 3 is a pipe's read end and is passed; fd 4 is inherited but not
+    // listed. `[ -e ]` is a shell builtin, so it opens no descriptors of its own
+    let child = "cat <&3; for fd in 3 4; do [ -e /proc/self/fd/$fd ] && echo fd$fd=open || echo fd$fd=closed; done; sleep 30";
+    let script = format!(
+        "exec 3< <(echo from-pipe) 4</dev/null; exec \"$0\" start {} --socket \"$1\" --pass-fd 3 -- sh -c '{}'",
+        emulator_args().join(" "), child);
+    let output = Command::new("bash")
+        .arg("-c")
+        .arg(script)
+        .arg(interminai_server_bin())
+        .arg(socket_path.to_str().unwrap())
+        .output()
+        .expect("Failed to execute interminai");
+    assert!(output.status.success(), "Command failed: {}", String::from_utf8_lossy(&output.stderr));
+
+    thread::sleep(Duration::from_millis(500));
+
+    let screen = Command::new(interminai_client_bin())
+        .arg("output")
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .arg("--no-color")
+        .output()
+        .expect("Failed to get output");
+    let screen = String::from_utf8_lossy(&screen.stdout);
+    assert!(screen.contains("from-pipe"), "The child should read the passed pipe: {}", screen);
+    assert!(screen.contains("fd3=open"), "Passed fd should stay open: {}", screen);
+    assert!(screen.contains("fd4=closed"), "Unlisted fd should be closed: {}", screen);
+
+    Command::new(interminai_client_bin())
+        .arg("stop")
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_pass_fd_rejects_closed_and_standard_descriptors() {
+    for fd in ["2", "97"] {
+        let output = Command::new(interminai_server_bin())
+            .arg("start")
+            .arg("--pass-fd")
+            .arg(fd)
+            .arg("--")
+            .arg("true")
+            .output()
+            .expect("Failed to execute interminai");
+        assert!(!output.status.success(), "--pass-fd {} should be rejected", fd);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains(&format!("--pass-fd {}", fd)), "Got: {}", stderr);
+    }
+}
+
+#[test]
+fn test_child_inherits_no_daemon_descriptors() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let socket_path = temp_dir.path().join("fds.sock");
+    let log_path = temp_dir.path().join("fds.log");
+
+    // ls reads the shell's descriptors from outside, so the listing itself
+    // adds none
+    let start = || Command::new(interminai_server_bin())
+        .arg("start")
+        .args(emulator_args())
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .arg("--size")
+        .arg("160x20")
+        .arg("--log")
+        .arg(log_path.to_str().unwrap())
+        .arg("--")
+        .arg("sh")
+        .arg("-c")
+        .arg("ls -l /proc/$$/fd; echo listed; sleep 5 & sleep 30")
+        .output()
+        .expect("Failed to execute interminai");
+    let output = start();
+    assert!(output.status.success(), "Command failed: {}", String::from_utf8_lossy(&output.stderr));
+
+    thread::sleep(Duration::from_millis(500));
+
+    let screen = Command::new(interminai_client_bin())
+        .arg("output")
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .arg("--no-color")
+        .output()
+        .expect("Failed to get output");
+    let screen = String::from_utf8_lossy(&screen.stdout);
+    assert!(screen.contains("listed"), "Screen: {}", screen);
+    let fds: Vec<&str> = screen.lines().filter(|line| line.contains(" -> ")).collect();
+    assert_eq!(fds.len(), 3, "Only stdin, stdout and stderr should be open: {:?}", fds);
+    assert!(fds.iter().all(|line| line.contains("/dev/pts/")), "All should be the terminal: {:?}", fds);
+
+    Command::new(interminai_client_bin())
+        .arg("stop")
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .assert()
+        .success();
+    thread::sleep(Duration::from_millis(300));
+
+    // The background sleep outlives the session but holds no listener, so
+    // start sees a stale socket it may replace rather than one in use
+    let output = start();
+    assert!(output.status.success(), "Restart failed: {}", String::from_utf8_lossy(&output.stderr));
+    thread::sleep(Duration::from_millis(300));
+    Command::new(interminai_client_bin())
+        .arg("stop")
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_start_returns_once_socket_is_listening() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let socket_path = temp_dir.path().join("ready.sock");
+
+    Command::new(interminai_server_bin())
+        .arg("start")
+        .args(emulator_args())
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .arg("--")
+        .arg("sleep")
+        .arg("30")
+        .assert()
+        .success();
+
+    // No sleep: the daemon can be reached as soon as start returns
+    Command::new(interminai_client_bin())
+        .arg("status")
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .arg("--quiet")
+        .assert()
+        .success();
+
+    Command::new(interminai_client_bin())
+        .arg("stop")
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_start_reports_daemon_failure() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let socket_path = temp_dir.path().join("missing").join("daemon.sock");
+
+    let output = Command::new(interminai_server_bin())
+        .arg("start")
+        .args(emulator_args())
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .arg("--")
+        .arg("sleep")
+        .arg("30")
+        .output()
+        .expect("Failed to execute interminai");
+    assert!(!output.status.success(), "start should fail when the socket can't be bound");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("Daemon failed to start: Failed to bind socket"), "Got: {}", stderr);
+}
+
+#[test]
+fn test_initial_input_reaches_command() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let socket_path = temp_dir.path().join("initial.sock");
+
+    let output = Command::new(interminai_server_bin())
+        .arg("start")
+        .args(emulator_args())
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .arg("--initial-input")
+        .arg("early-bird\\n")
+        .arg("--initial-input-delay-ms")
+        .arg("100")
+        .arg("--")
+        .arg("cat")
+        .output()
+        .expect("Failed to execute interminai");
+    assert!(output.status.success(), "Command failed: {}", String::from_utf8_lossy(&output.stderr));
+
+    thread::sleep(Duration::from_millis(500));
+
+    let screen = Command::new(interminai_client_bin())
+        .arg("output")
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .arg("--no-color")
+        .output()
+        .expect("Failed to get output");
+    let screen = String::from_utf8_lossy(&screen.stdout);
+    // Once echoed by the terminal, once printed back by cat
+    assert_eq!(screen.matches("early-bird").count(), 2, "Screen: {}", screen);
+
+    Command::new(interminai_client_bin())
+        .arg("stop")
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_initial_input_delay_keeps_socket_connectable() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let socket_path = temp_dir.path().join("initial-delay.sock");
+    let log_path = temp_dir.path().join("session.log");
+
+    let output = Command::new(interminai_server_bin())
+        .arg("start")
+        .args(emulator_args())
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .arg("--log")
+        .arg(log_path.to_str().unwrap())
+        .arg("--trace-input")
+        .arg("--initial-input")
+        .arg("late\\n")
+        .arg("--initial-input-delay-ms")
+        .arg("1000")
+        .arg("--")
+        .arg("cat")
+        .output()
+        .expect("Failed to execute interminai");
+    assert!(output.status.success(), "Command failed: {}", String::from_utf8_lossy(&output.stderr));
+
+    // The socket is there well before the delay is over
+    for _ in 0..20 {
+        if socket_path.exists() {
+            break;
+        }
+        thread::sleep(Duration::from_millis(25));
+    }
+    assert!(socket_path.exists(), "Socket should be bound during the delay");
+
+    // The request is answered once the initial input was written
+    let wait = Command::new(interminai_client_bin())
+        .arg("wait")
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .arg("--contains")
+        .arg("late")
+        .timeout(Duration::from_secs(5))
+        .output()
+        .expect("Failed to wait");
+    assert!(wait.status.success(), "wait failed: {}", String::from_utf8_lossy(&wait.stderr));
+
+    Command::new(interminai_client_bin())
+        .arg("stop")
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .assert()
+        .success();
+
+    thread::sleep(Duration::from_millis(200));
+
+    let log = std::fs::read_to_string(&log_path).expect("Failed to read log file");
+    assert!(log.contains("] trace 5 bytes, 5 written: 6c 61 74 65 0a \"late\\n\""), "Log should trace the initial input. Got: {}", log);
+}
+
+#[test]
+fn test_initial_input_rejects_bad_escape() {
+    let output = Command::new(interminai_server_bin())
+        .arg("start")
+        .arg("--initial-input")
+        .arg("\\x4")
+        .arg("--")
+        .arg("true")
+        .output()
+        .expect("Failed to execute interminai");
+    assert!(!output.status.success(), "A bad escape should fail start");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("--initial-input"), "Got: {}", stderr);
+}
+
+#[test]
+fn test_max_runtime_kills_command_and_stops_daemon() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let socket_path = temp_dir.path().join("runtime.sock");
+
+    // trap '' TERM: only the SIGKILL that follows can end it
+    let output = Command::new(interminai_server_bin())
+        .arg("start")
+        .args(emulator_args())
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .arg("--max-runtime")
+        .arg("1")
+        .arg("--")
+        .arg("bash")
+        .arg("-c")
+        .arg("trap '' TERM; sleep 100")
+        .output()
+        .expect("Failed to execute interminai");
+    assert!(output.status.success(), "Command failed: {}", String::from_utf8_lossy(&output.stderr));
+
+    let status = || Command::new(interminai_client_bin())
+        .arg("status")
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .output()
+        .expect("Failed to get status");
+
+    thread::sleep(Duration::from_millis(500));
+    let before = String::from_utf8_lossy(&status().stdout).to_string();
+    assert!(before.contains("Running: true"), "Status: {}", before);
+    assert!(!before.contains("Killed by"), "Status: {}", before);
+
+    // Killed at 1s; the daemon lingers briefly so the reason is visible
+    thread::sleep(Duration::from_millis(1000));
+    let after = String::from_utf8_lossy(&status().stdout).to_string();
+    assert!(after.contains("Running: false"), "Status: {}", after);
+    assert!(after.contains("Exit code: 137"), "SIGTERM is ignored, so SIGKILL: {}", after);
+    assert!(after.contains("Killed by: max_runtime"), "Status: {}", after);
+
+    thread::sleep(Duration::from_millis(1500));
+    assert!(!status().status.success(), "The daemon should have shut down");
+}
+
+#[test]
+fn test_max_runtime_rejects_non_positive() {
+    for (value, message) in [("0", "--max-runtime must be a positive number"), ("1e30", "--max-runtime is too large")] {
+        let output = Command::new(interminai_server_bin())
+            .arg("start")
+            .arg("--max-runtime")
+            .arg(value)
+            .arg("--")
+            .arg("true")
+            .output()
+            .expect("Failed to execute interminai");
+        assert_eq!(output.status.code(), Some(1), "--max-runtime {} should fail cleanly", value);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        assert!(stderr.contains(message), "Got: {}", stderr);
+    }
+}
+
+#[test]
+fn test_max_runtime_leaves_finished_command_alone() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let socket_path = temp_dir.path().join("runtime.sock");
+
+    Command::new(interminai_server_bin())
+        .arg("start")
+        .args(emulator_args())
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .arg("--max-runtime")
+        .arg("0.5")
+        .arg("--")
+        .arg("true")
+        .assert()
+        .success();
+
+    // Well past the deadline and MAX_RUNTIME_LINGER
+    thread::sleep(Duration::from_millis(2000));
+    let status = Command::new(interminai_client_bin())
+        .arg("status")
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .output()
+        .expect("Failed to get status");
+    let stdout = String::from_utf8_lossy(&status.stdout);
+    assert!(status.status.success(), "The daemon should still be up: {}", String::from_utf8_lossy(&status.stderr));
+    assert!(stdout.contains("Exit code: 0"), "Status: {}", stdout);
+    assert!(!stdout.contains("Killed by"), "Status: {}", stdout);
+
+    Command::new(interminai_client_bin())
+        .arg("stop")
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_status_sees_exit_once_output_is_final() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let mut spurious = 0;
+    for i in 0..10 {
+        let socket_path = temp_dir.path().join(format!("echo{}.sock", i));
+        Command::new(interminai_server_bin())
+            .arg("start")
+            .args(emulator_args())
+            .arg("--socket")
+            .arg(socket_path.to_str().unwrap())
+            .arg("--")
+            .arg("echo")
+            .arg("all-done")
+            .assert()
+            .success();
+
+        // Poll the screen, then ask for status as soon as the output is there
+        let mut status = String::new();
+        for _ in 0..100 {
+            let screen = Command::new(interminai_client_bin())
+                .arg("output")
+                .arg("--socket")
+                .arg(socket_path.to_str().unwrap())
+                .output()
+                .expect("Failed to get output");
+            if String::from_utf8_lossy(&screen.stdout).contains("all-done") {
+                status = status_output(&socket_path);
+                break;
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+        assert!(!status.is_empty(), "The output never appeared");
+        if status.contains("Running: true") {
+            spurious += 1;
+        }
+
+        Command::new(interminai_client_bin())
+            .arg("stop")
+            .arg("--socket")
+            .arg(socket_path.to_str().unwrap())
+            .assert()
+            .success();
+    }
+    assert_eq!(spurious, 0, "Status reported a finished echo as running");
+}
+
+// The panic hook only exists in debug builds
+#[cfg(debug_assertions)]
+#[test]
+fn test_dead_reader_is_reported_in_status() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let socket_path = temp_dir.path().join("degraded.sock");
+
+    // The test hook makes the reader panic on the first PTY output,
+    // while it holds the daemon state lock
+    let output = Command::new(interminai_server_bin())
+        .env("INTERMINAI_TEST_READER_PANIC", "1")
+        .arg("start")
+        .args(emulator_args())
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .arg("--")
+        .arg("bash")
+        .arg("-c")
+        .arg("echo hello; sleep 100")
+        .output()
+        .expect("Failed to execute interminai");
+    assert!(output.status.success(), "Command failed: {}", String::from_utf8_lossy(&output.stderr));
+
+    thread::sleep(Duration::from_millis(500));
+    let status = Command::new(interminai_client_bin())
+        .arg("status")
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .output()
+        .expect("Failed to get status");
+    let stdout = String::from_utf8_lossy(&status.stdout);
+    assert!(status.status.success(), "The daemon should still answer: {}", String::from_utf8_lossy(&status.stderr));
+    assert!(stdout.contains("Running: true"), "Status: {}", stdout);
+    assert!(stdout.contains("Degraded: PTY reader thread panicked"), "Status: {}", stdout);
+
+    // Other requests keep working
+    Command::new(interminai_client_bin())
+        .arg("output")
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .assert()
+        .success();
+
+    Command::new(interminai_client_bin())
+        .arg("stop")
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .assert()
+        .success();
+}
@@ -5,7 +5,7 @@ use tempfile::TempDir;
 use std::path::PathBuf;
 
 mod common;
-use common::{interminai_bin, emulator_args};
+use common::{interminai_bin, emulator_args, emulator};
 
 struct TestEnv {
     _temp_dir: TempDir,
@@ -33,6 +33,14 @@ struct DaemonHandle {
 
 impl DaemonHandle {
     fn spawn(socket: &str) -> Self {
+        Self::spawn_with_command(socket, "sleep 10")
+    }
+
+    fn spawn_with_command(socket: &str, command: &str) -> Self {
+        Self::spawn_with_options(socket, &[], command)
+    }
+
+    fn spawn_with_options(socket: &str, options: &[&str], command: &str) -> Self {
         let mut cmd = std::process::Command::new(interminai_bin());
         cmd.arg("start")
             .args(emulator_args())
@@ -40,10 +48,11 @@ impl DaemonHandle {
             .arg(socket)
             .arg("--size")
             .arg("40x10")
+            .args(options)
             .arg("--")
             .arg("bash")
             .arg("-c")
-            .arg("sleep 10");
+            .arg(command);
 
         let output = cmd.output().expect("Failed to start daemon");
         if !output.status.success() {
@@ -75,7 +84,7 @@ fn test_debug_returns_valid_structure() {
     let output = Command::new(interminai_bin())
         .arg("debug")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .timeout(Duration::from_secs(2))
         .output()
         .expect("Failed to run debug command");
@@ -101,7 +110,7 @@ fn test_debug_with_clear_flag() {
     let output1 = Command::new(interminai_bin())
         .arg("debug")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .timeout(Duration::from_secs(2))
         .output()
         .expect("Failed to run debug command");
@@ -112,7 +121,7 @@ fn test_debug_with_clear_flag() {
     let output2 = Command::new(interminai_bin())
         .arg("debug")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .arg("--clear")
         .timeout(Duration::from_secs(2))
         .output()
@@ -132,7 +141,7 @@ fn test_debug_basic_functionality() {
     let output = Command::new(interminai_bin())
         .arg("debug")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .timeout(Duration::from_secs(2))
         .output()
         .expect("Failed to run debug command");
@@ -176,3 +185,102 @@ fn test_debug_requires_socket() {
     assert!(stderr.contains("socket") || stderr.contains("required"),
         "Should mention missing socket. Got: {}", stderr);
 }
+
+/// Test debug reports the bytes and sequences read from the PTY
+#[test]
+fn test_debug_reports_output_counters() {
+    let env = TestEnv::new();
+    // 5 printable characters, one CSI (SGR) and one ESC (DECSC) sequence
+    let daemon = DaemonHandle::spawn_with_command(&env.socket(), "printf 'hello\\033[1m\\0337'; sleep 10");
+
+    let output = Command::new(interminai_bin())
+        .arg("debug")
+        .arg("--socket")
+        .arg(env.socket())
+        .timeout(Duration::from_secs(2))
+        .output()
+        .expect("Failed to run debug command");
+
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let line = stdout.lines().find(|l| l.starts_with("Output:"))
+        .unwrap_or_else(|| panic!("Should report output counters. Got: {}", stdout));
+    assert!(line.contains("11 bytes read"), "Got: {}", line);
+    assert!(line.contains("5 printed"), "Got: {}", line);
+    assert!(line.contains("1 CSI"), "Got: {}", line);
+    assert!(line.contains("1 ESC"), "Got: {}", line);
+
+    daemon.stop();
+}
+
+fn debug_output(socket: &str, args: &[&str]) -> std::process::Output {
+    Command::new(interminai_bin())
+        .arg("debug")
+        .arg("--socket")
+        .arg(socket)
+        .args(args)
+        .timeout(Duration::from_secs(2))
+        .output()
+        .expect("Failed to run debug command")
+}
+
+/// Test debug --raw shows the original bytes next to the reconstructed form
+#[test]
+fn test_debug_raw_shows_original_bytes() {
+    // Only the custom backend records unhandled sequences
+    if emulator() != "custom" {
+        return;
+    }
+
+    let env = TestEnv::new();
+    // DECSTBM with the first parameter omitted: vte reports it as 0
+    let daemon = DaemonHandle::spawn_with_options(&env.socket(), &["--debug-raw"], "printf '\\033[;4r'; sleep 10");
+
+    let output = debug_output(&env.socket(), &["--raw"]);
+    assert!(output.status.success(), "debug --raw should succeed: {}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("\\e[0;4r (1b5b303b3472) raw: 1b5b3b3472"),
+        "Should show reconstructed and original bytes. Got: {}", stdout);
+
+    // Without --raw the output is unchanged
+    let stdout = String::from_utf8_lossy(&debug_output(&env.socket(), &[]).stdout).to_string();
+    assert!(stdout.contains("\\e[0;4r (1b5b303b3472)\n"), "Got: {}", stdout);
+
+    daemon.stop();
+}
+
+/// Test debug --raw needs raw capture enabled at start
+#[test]
+fn test_debug_raw_requires_start_flag() {
+    let env = TestEnv::new();
+    let daemon = DaemonHandle::spawn(&env.socket());
+
+    let output = debug_output(&env.socket(), &["--raw"]);
+    assert!(!output.status.success(), "debug --raw should fail without --debug-raw");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("--debug-raw"), "Should say how to enable it. Got: {}", stderr);
+
+    daemon.stop();
+}
+
+/// Test debug --decode-sgr explains the SGR sequences the program sent
+#[test]
+fn test_debug_decode_sgr() {
+    let env = TestEnv::new();
+    let daemon = DaemonHandle::spawn_with_command(&env.socket(), "printf '\\033[1;31mX\\033[38;5;196;48;2;0;128;255mY\\033[m'; sleep 10");
+
+    let output = debug_output(&env.socket(), &["--decode-sgr"]);
+    assert!(output.status.success(), "debug --decode-sgr should succeed: {}", String::from_utf8_lossy(&output.stderr));
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("\\e[1;31m  SGR: bold, fg=red"), "Got: {}", stdout);
+    assert!(stdout.contains("SGR: fg=color 196, bg=#0080ff"), "Got: {}", stdout);
+    // vte reports the omitted parameter of ESC [ m as 0
+    assert!(stdout.contains("\\e[0m  SGR: reset"), "Got: {}", stdout);
+
+    // Without the flag the sequences are not listed
+    let stdout = String::from_utf8_lossy(&debug_output(&env.socket(), &[]).stdout).to_string();
+    assert!(!stdout.contains("SGR:"), "Got: {}", stdout);
+
+    daemon.stop();
+}
@@ -53,6 +53,47 @@ fn test_nonexistent_socket_stop() {
         .stderr(predicates::str::contains("No such file"));
 }
 
+#[test]
+fn test_stale_socket_file_fails_fast() {
+    use tempfile::TempDir;
+    use std::os::unix::net::UnixListener;
+
+    // Bind then drop the listener: the socket file remains but nobody listens
+    let temp_dir = TempDir::new().unwrap();
+    let socket_path = temp_dir.path().join("stale.sock");
+    drop(UnixListener::bind(&socket_path).unwrap());
+    assert!(socket_path.exists());
+
+    Command::new(interminai_bin())
+        .arg("status")
+        .arg("--socket")
+        .arg(&socket_path)
+        .timeout(Duration::from_secs(2))
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("Failed to connect"));
+}
+
+#[test]
+fn test_unresponsive_daemon_times_out() {
+    use tempfile::TempDir;
+    use std::os::unix::net::UnixListener;
+
+    // A listener that never accepts: connect succeeds, but no reply ever comes
+    let temp_dir = TempDir::new().unwrap();
+    let socket_path = temp_dir.path().join("wedged.sock");
+    let _listener = UnixListener::bind(&socket_path).unwrap();
+
+    Command::new(interminai_bin())
+        .arg("status")
+        .arg("--socket")
+        .arg(&socket_path)
+        .timeout(Duration::from_secs(20))
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("No response from daemon"));
+}
+
 #[test]
 fn test_invalid_terminal_size() {
     Command::new(interminai_bin())
@@ -71,6 +112,24 @@ fn test_invalid_terminal_size() {
         .stderr(predicates::str::contains("Invalid size"));
 }
 
+#[test]
+fn test_huge_terminal_size_needs_allow_large() {
+    Command::new(interminai_bin())
+        .arg("start")
+        .args(emulator_args())
+        .arg("--socket")
+        .arg("/tmp/test-huge-size.sock")
+        .arg("--size")
+        .arg("9999x9999")
+        .arg("--")
+        .arg("sleep")
+        .arg("1")
+        .timeout(Duration::from_secs(2))
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("--allow-large"));
+}
+
 #[test]
 fn test_invalid_signal_name() {
     use tempfile::TempDir;
@@ -180,6 +239,42 @@ fn test_missing_command_argument() {
     );
 }
 
+#[test]
+fn test_empty_command_after_separator() {
+    let output = Command::new(interminai_bin())
+        .arg("start")
+        .args(emulator_args())
+        .arg("--socket")
+        .arg("/tmp/test-empty-command.sock")
+        .arg("--")
+        .timeout(Duration::from_secs(2))
+        .assert()
+        .failure();
+
+    let stderr = String::from_utf8_lossy(&output.get_output().stderr);
+    assert!(!stderr.contains("panicked"), "Should not panic, got: {}", stderr);
+    assert!(
+        stderr.contains("required arguments were not provided") ||
+        stderr.contains("arguments are required"),
+        "Expected error about the missing command, got: {}", stderr
+    );
+}
+
+#[test]
+fn test_empty_program_name() {
+    Command::new(interminai_bin())
+        .arg("start")
+        .args(emulator_args())
+        .arg("--socket")
+        .arg("/tmp/test-empty-program.sock")
+        .arg("--")
+        .arg("")
+        .timeout(Duration::from_secs(2))
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("No command given"));
+}
+
 #[test]
 fn test_nonexistent_command_exits_gracefully() {
     use tempfile::TempDir;
@@ -225,3 +320,144 @@ fn test_nonexistent_command_exits_gracefully() {
 
     let _ = daemon.wait();
 }
+
+#[test]
+fn test_nonexistent_command_reports_exec_failure() {
+    use tempfile::TempDir;
+
+    let temp_dir = TempDir::new().unwrap();
+    let socket = temp_dir.path().join("test.sock");
+
+    let mut daemon = std::process::Command::new(interminai_bin())
+        .arg("start")
+        .args(emulator_args())
+        .arg("--socket")
+        .arg(socket.to_str().unwrap())
+        .arg("--")
+        .arg("/this/command/does/not/exist")
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .expect("Failed to spawn daemon");
+
+    std::thread::sleep(Duration::from_millis(500));
+
+    let output = Command::new(interminai_bin())
+        .arg("status")
+        .arg("--socket")
+        .arg(socket.to_str().unwrap())
+        .timeout(Duration::from_secs(2))
+        .output()
+        .expect("Failed to get status");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("Exec failed: Failed to execute '/this/command/does/not/exist'"),
+        "Status should report the exec failure, got: {}", stdout);
+    assert!(stdout.contains("No such file or directory"),
+        "Status should include the OS error, got: {}", stdout);
+
+    // The error is also shown on the terminal screen
+    let output = Command::new(interminai_bin())
+        .arg("output")
+        .arg("--socket")
+        .arg(socket.to_str().unwrap())
+        .arg("--no-color")
+        .timeout(Duration::from_secs(2))
+        .output()
+        .expect("Failed to get output");
+    let screen = String::from_utf8_lossy(&output.stdout);
+    assert!(screen.contains("interminai: Failed to execute"),
+        "Screen should show the exec failure, got: {}", screen);
+
+    Command::new(interminai_bin())
+        .arg("stop")
+        .arg("--socket")
+        .arg(socket.to_str().unwrap())
+        .output()
+        .ok();
+
+    let _ = daemon.wait();
+}
+
+#[test]
+fn test_malformed_sequences_leave_daemon_responsive() {
+    use tempfile::TempDir;
+
+    let temp_dir = TempDir::new().unwrap();
+    let socket = temp_dir.path().join("test.sock");
+
+    // Huge and excessive parameters, unterminated strings, stray C1 bytes and
+    // invalid UTF-8, then a marker line and a program that still takes input
+    let script = concat!(
+        r"printf '\e[99999999999999999999;;;;;;;;;;;;m\e[?4294967296h\e[0;-1;65536H';",
+        r"printf '\e[%s1m' $(printf '1;%.0s' $(seq 500));",
+        r"printf '\e]0;%s' $(head -c 5000 /dev/zero | tr '\0' x);",
+        r"printf '\ePq#0;2;0;0;0\e[1;2;3\e\e\e\x9b\x9d\x90\xff\xfe\xc3\xe2\x82\r\n';",
+        r"printf '\e[3;999999999r\e[999999999b\e[999999999L\e[999999999@\e[999999999P';",
+        r"printf '\e[H\e[2JALIVE\n'; exec cat",
+    );
+
+    let mut daemon = std::process::Command::new(interminai_bin())
+        .arg("start")
+        .args(emulator_args())
+        .arg("--socket")
+        .arg(socket.to_str().unwrap())
+        .arg("--")
+        .arg("bash")
+        .arg("-c")
+        .arg(script)
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::null())
+        .spawn()
+        .expect("Failed to spawn daemon");
+
+    std::thread::sleep(Duration::from_millis(1000));
+
+    Command::new(interminai_bin())
+        .arg("output")
+        .arg("--socket")
+        .arg(socket.to_str().unwrap())
+        .timeout(Duration::from_secs(2))
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("ALIVE"));
+
+    Command::new(interminai_bin())
+        .arg("input")
+        .arg("--socket")
+        .arg(socket.to_str().unwrap())
+        .arg("--text")
+        .arg("STILL-HERE\\n")
+        .timeout(Duration::from_secs(2))
+        .assert()
+        .success();
+
+    std::thread::sleep(Duration::from_millis(300));
+
+    Command::new(interminai_bin())
+        .arg("output")
+        .arg("--socket")
+        .arg(socket.to_str().unwrap())
+        .timeout(Duration::from_secs(2))
+        .assert()
+        .success()
+        .stdout(predicates::str::contains("STILL-HERE"));
+
+    Command::new(interminai_bin())
+        .arg("status")
+        .arg("--quiet")
+        .arg("--socket")
+        .arg(socket.to_str().unwrap())
+        .timeout(Duration::from_secs(2))
+        .assert()
+        .success();
+
+    Command::new(interminai_bin())
+        .arg("stop")
+        .arg("--socket")
+        .arg(socket.to_str().unwrap())
+        .output()
+        .ok();
+
+    let _ = daemon.wait();
+}
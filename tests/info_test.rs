@@ -0,0 +1,167 @@
+use assert_cmd::Command;
+use std::thread;
+use std::time::Duration;
+use tempfile::TempDir;
+
+mod common;
+use common::{interminai_bin, emulator_args, emulator};
+
+struct TestEnv {
+    _temp_dir: TempDir,
+}
+
+impl TestEnv {
+    fn new() -> Self {
+        Self {
+            _temp_dir: TempDir::new().expect("Failed to create temp dir"),
+        }
+    }
+
+    fn socket(&self) -> String {
+        self._temp_dir.path().join("test.sock").to_string_lossy().to_string()
+    }
+}
+
+struct DaemonHandle {
+    socket_path: String,
+}
+
+impl DaemonHandle {
+    fn spawn(socket: &str, options: &[&str], args: &[&str]) -> Self {
+        let output = std::process::Command::new(interminai_bin())
+            .arg("start")
+            .args(emulator_args())
+            .arg("--socket")
+            .arg(socket)
+            .args(options)
+            .arg("--")
+            .args(args)
+            .output()
+            .expect("Failed to start daemon");
+
+        if !output.status.success() {
+            panic!("Daemon failed to start: {}", String::from_utf8_lossy(&output.stderr));
+        }
+
+        thread::sleep(Duration::from_millis(300));
+
+        DaemonHandle {
+            socket_path: socket.to_string(),
+        }
+    }
+}
+
+impl Drop for DaemonHandle {
+    fn drop(&mut self) {
+        let _ = std::process::Command::new(interminai_bin())
+            .arg("stop")
+            .arg("--socket")
+            .arg(&self.socket_path)
+            .output();
+    }
+}
+
+fn get_info(socket: &str) -> String {
+    let output = Command::new(interminai_bin())
+        .arg("info")
+        .arg("--socket")
+        .arg(socket)
+        .timeout(Duration::from_secs(2))
+        .output()
+        .expect("Failed to run info command");
+
+    assert!(output.status.success(), "info should succeed: {}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+#[test]
+fn test_info_echoes_command() {
+    let env = TestEnv::new();
+    let daemon = DaemonHandle::spawn(&env.socket(), &[], &["bash", "-c", "echo 'two words'; sleep 10"]);
+
+    let info = get_info(&daemon.socket_path);
+    assert!(info.contains(r#"Command: ["bash","-c","echo 'two words'; sleep 10"]"#),
+        "Should echo the command vector. Got: {}", info);
+    assert!(info.contains(&format!("Socket: {}", daemon.socket_path)), "Got: {}", info);
+    assert!(info.contains(&format!("Emulator: {}", emulator())), "Got: {}", info);
+    assert!(info.contains("Started: "), "Got: {}", info);
+}
+
+#[test]
+fn test_info_reports_configured_size_and_term() {
+    let env = TestEnv::new();
+    let daemon = DaemonHandle::spawn(&env.socket(), &["--size", "100x30", "--term", "vt100"], &["sleep", "10"]);
+
+    Command::new(interminai_bin())
+        .arg("resize")
+        .arg("--socket")
+        .arg(&daemon.socket_path)
+        .arg("--size")
+        .arg("60x20")
+        .assert()
+        .success();
+
+    // Info is static: it keeps the size given to start
+    let info = get_info(&daemon.socket_path);
+    assert!(info.contains("Size: 100x30"), "Got: {}", info);
+    assert!(info.contains("TERM: vt100"), "Got: {}", info);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_info_reports_pty_device() {
+    let env = TestEnv::new();
+    let daemon = DaemonHandle::spawn(&env.socket(), &[], &["bash", "-c", "tty; sleep 10"]);
+
+    let info = get_info(&daemon.socket_path);
+    let pty = info.lines()
+        .find_map(|l| l.strip_prefix("PTY: "))
+        .unwrap_or_else(|| panic!("Should report the PTY. Got: {}", info));
+    assert!(pty.starts_with("/dev/pts/"), "Got: {}", pty);
+    assert!(std::path::Path::new(pty).exists(), "{} should exist", pty);
+
+    // It's the terminal the command sees
+    let output = Command::new(interminai_bin())
+        .arg("output")
+        .arg("--socket")
+        .arg(&daemon.socket_path)
+        .arg("--no-color")
+        .timeout(Duration::from_secs(2))
+        .output()
+        .expect("Failed to get output");
+    let screen = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(screen.lines().next().map(str::trim_end), Some(pty), "Screen: {}", screen);
+}
+
+#[cfg(target_os = "linux")]
+#[test]
+fn test_children_lists_session_processes() {
+    let env = TestEnv::new();
+    // The trailing command keeps bash from exec'ing sleep
+    let daemon = DaemonHandle::spawn(&env.socket(), &[], &["bash", "-c", "sleep 100; true"]);
+
+    let output = Command::new(interminai_bin())
+        .arg("children")
+        .arg("--socket")
+        .arg(&daemon.socket_path)
+        .timeout(Duration::from_secs(2))
+        .output()
+        .expect("Failed to run children command");
+    assert!(output.status.success(), "children should succeed: {}", String::from_utf8_lossy(&output.stderr));
+    let listing = String::from_utf8_lossy(&output.stdout).to_string();
+
+    // PID, PPID, foreground marker, command line
+    let processes: Vec<(String, String, String)> = listing.lines().skip(1)
+        .map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let command = fields[2..].strip_prefix(&["+"]).unwrap_or(&fields[2..]);
+            (fields[0].to_string(), fields[1].to_string(), command.join(" "))
+        })
+        .collect();
+    let bash = processes.iter().find(|(_, _, command)| command == "bash -c sleep 100; true")
+        .unwrap_or_else(|| panic!("Should list the session leader. Got: {}", listing));
+    let sleep = processes.iter().find(|(_, _, command)| command == "sleep 100")
+        .unwrap_or_else(|| panic!("Should list the shell's child. Got: {}", listing));
+    assert_eq!(sleep.1, bash.0, "sleep's parent should be the shell. Got: {}", listing);
+    assert_eq!(processes.first(), Some(bash), "The session leader comes first. Got: {}", listing);
+}
@@ -250,7 +250,7 @@ fn test_running_when_active() {
     let mut cmd = Command::new(interminai_bin());
     cmd.arg("status")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .timeout(Duration::from_secs(2))
         .assert()
         .success(); // Exit code 0 = running
@@ -272,7 +272,7 @@ fn test_running_when_finished() {
         .arg("status")
         .arg("--quiet")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .timeout(Duration::from_secs(2))
         .output()
         .expect("Failed to check running status");
@@ -297,7 +297,7 @@ fn test_running_after_stop() {
     Command::new(interminai_bin())
         .arg("stop")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .timeout(Duration::from_secs(2))
         .assert()
         .success();
@@ -308,7 +308,7 @@ fn test_running_after_stop() {
     let result = Command::new(interminai_bin())
         .arg("status")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .timeout(Duration::from_secs(2))
         .output();
 
@@ -326,7 +326,7 @@ fn test_running_no_daemon() {
     let result = Command::new(interminai_bin())
         .arg("status")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .timeout(Duration::from_secs(2))
         .output();
 
@@ -355,7 +355,7 @@ fn test_wait_until_exit() {
     let output = Command::new(interminai_bin())
         .arg("wait")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .timeout(Duration::from_secs(3))
         .output()
         .expect("Failed to wait");
@@ -382,7 +382,7 @@ fn test_wait_already_finished() {
         .arg("wait")
         .arg("--quiet")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .timeout(Duration::from_secs(2))
         .output()
         .expect("Failed to wait");
@@ -394,6 +394,50 @@ fn test_wait_already_finished() {
     daemon.stop();
 }
 
+#[test]
+fn test_wait_propagate_exit_code() {
+    let env = TestEnv::new();
+
+    let daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["bash", "-c", "sleep 0.5; exit 42"]);
+
+    let output = Command::new(interminai_bin())
+        .arg("wait")
+        .arg("--socket")
+        .arg(env.socket())
+        .arg("--propagate-exit")
+        .timeout(Duration::from_secs(3))
+        .output()
+        .expect("Failed to wait");
+
+    assert_eq!(output.status.code(), Some(42), "wait should exit with the child's exit code");
+
+    daemon.stop();
+}
+
+#[test]
+fn test_wait_propagate_exit_signal() {
+    let env = TestEnv::new();
+
+    let daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["bash", "-c", "kill -TERM $$"]);
+
+    let output = Command::new(interminai_bin())
+        .arg("wait")
+        .arg("--socket")
+        .arg(env.socket())
+        .arg("--propagate-exit")
+        .arg("--quiet")
+        .timeout(Duration::from_secs(3))
+        .output()
+        .expect("Failed to wait");
+
+    // Killed by SIGTERM (15) -> 128 + 15
+    assert_eq!(output.status.code(), Some(143), "wait should exit with 128+signal");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("143"), "--quiet should still print the exit code. Got: {}", stdout);
+
+    daemon.stop();
+}
+
 #[test]
 fn test_kill_requires_socket() {
     let mut cmd = Command::new(interminai_bin());
@@ -413,7 +457,7 @@ fn test_kill_default_sigterm() {
     Command::new(interminai_bin())
         .arg("status")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .timeout(Duration::from_secs(2))
         .assert()
         .success();
@@ -422,7 +466,7 @@ fn test_kill_default_sigterm() {
     Command::new(interminai_bin())
         .arg("kill")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .timeout(Duration::from_secs(2))
         .assert()
         .success();
@@ -435,7 +479,7 @@ fn test_kill_default_sigterm() {
         .arg("status")
         .arg("--quiet")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .timeout(Duration::from_secs(2))
         .output();
 
@@ -455,7 +499,7 @@ fn test_kill_sigkill() {
     Command::new(interminai_bin())
         .arg("kill")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .arg("--signal")
         .arg("SIGKILL")
         .timeout(Duration::from_secs(2))
@@ -470,7 +514,7 @@ fn test_kill_sigkill() {
         .arg("status")
         .arg("--quiet")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .timeout(Duration::from_secs(2))
         .output();
 
@@ -490,7 +534,7 @@ fn test_kill_sigint() {
     Command::new(interminai_bin())
         .arg("kill")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .arg("--signal")
         .arg("SIGINT")
         .timeout(Duration::from_secs(2))
@@ -504,7 +548,7 @@ fn test_kill_sigint() {
     Command::new(interminai_bin())
         .arg("wait")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .timeout(Duration::from_secs(3))
         .assert()
         .success();
@@ -513,7 +557,7 @@ fn test_kill_sigint() {
     let output = Command::new(interminai_bin())
         .arg("status")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .timeout(Duration::from_secs(2))
         .output()
         .expect("Failed to get running status");
@@ -534,7 +578,7 @@ fn test_kill_numeric_signal_9() {
     Command::new(interminai_bin())
         .arg("kill")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .arg("--signal")
         .arg("9")
         .timeout(Duration::from_secs(2))
@@ -549,7 +593,41 @@ fn test_kill_numeric_signal_9() {
         .arg("status")
         .arg("--quiet")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
+        .timeout(Duration::from_secs(2))
+        .output();
+
+    assert!(result.is_err() || !result.unwrap().status.success());
+
+    daemon.stop();
+}
+
+#[test]
+fn test_kill_numeric_signal_15() {
+    let env = TestEnv::new();
+
+    let daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["sleep", "100"]);
+
+    // Kill with numeric signal 15 (SIGTERM)
+    Command::new(interminai_bin())
+        .arg("kill")
+        .arg("--socket")
+        .arg(env.socket())
+        .arg("--signal")
+        .arg("15")
+        .timeout(Duration::from_secs(2))
+        .assert()
+        .success();
+
+    // Wait for process to die
+    thread::sleep(Duration::from_millis(500));
+
+    // Should no longer be running
+    let result = Command::new(interminai_bin())
+        .arg("status")
+        .arg("--quiet")
+        .arg("--socket")
+        .arg(env.socket())
         .timeout(Duration::from_secs(2))
         .output();
 
@@ -559,104 +637,726 @@ fn test_kill_numeric_signal_9() {
 }
 
 #[test]
-fn test_kill_numeric_signal_15() {
+fn test_kill_numeric_signal_2() {
+    let env = TestEnv::new();
+
+    // Use a bash script that keeps bash alive (multiple commands prevent exec optimization)
+    let daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["bash", "-c", "trap 'exit 88' INT; while true; do sleep 1; done"]);
+
+    // Send numeric signal 2 (SIGINT)
+    Command::new(interminai_bin())
+        .arg("kill")
+        .arg("--socket")
+        .arg(env.socket())
+        .arg("--signal")
+        .arg("2")
+        .timeout(Duration::from_secs(2))
+        .assert()
+        .success();
+
+    // Give bash trap time to execute
+    thread::sleep(Duration::from_millis(500));
+
+    // Wait for process to actually exit
+    Command::new(interminai_bin())
+        .arg("wait")
+        .arg("--socket")
+        .arg(env.socket())
+        .timeout(Duration::from_secs(3))
+        .assert()
+        .success();
+
+    // Now check the exit code - should be 88 from the trap
+    let output = Command::new(interminai_bin())
+        .arg("status")
+        .arg("--socket")
+        .arg(env.socket())
+        .timeout(Duration::from_secs(2))
+        .output()
+        .expect("Failed to get running status");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("88"), "Expected exit code 88 from trap, got: {}", stdout);
+
+    daemon.stop();
+}
+
+#[test]
+fn test_kill_list_signals() {
+    let output = Command::new(interminai_bin())
+        .arg("kill")
+        .arg("--list")
+        .timeout(Duration::from_secs(2))
+        .output()
+        .expect("Failed to list signals");
+
+    assert!(output.status.success(), "kill --list should not require --socket");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    for name in ["SIGHUP", "SIGTERM", "SIGCONT", "SIGSTOP", "SIGWINCH", "SIGTSTP"] {
+        assert!(stdout.contains(name), "kill --list should include {}. Got: {}", name, stdout);
+    }
+}
+
+#[test]
+fn test_kill_extended_signal_names() {
+    let env = TestEnv::new();
+
+    let daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["sleep", "100"]);
+
+    // Stop and continue the child, accepting names with and without SIG prefix
+    for signal in ["SIGSTOP", "cont", "SIGTSTP", "SIGCONT"] {
+        Command::new(interminai_bin())
+            .arg("kill")
+            .arg("--socket")
+            .arg(env.socket())
+            .arg("--signal")
+            .arg(signal)
+            .timeout(Duration::from_secs(2))
+            .assert()
+            .success();
+    }
+
+    // Stopped-then-continued child should still be running
+    Command::new(interminai_bin())
+        .arg("status")
+        .arg("--quiet")
+        .arg("--socket")
+        .arg(env.socket())
+        .timeout(Duration::from_secs(2))
+        .assert()
+        .success();
+
+    daemon.stop();
+}
+
+#[test]
+fn test_kill_sigwinch_delivered() {
+    let env = TestEnv::new();
+
+    let daemon = DaemonHandle::spawn_with_socket(&env.socket(),
+        &["bash", "-c", "trap 'echo GOT_WINCH' WINCH; while true; do sleep 0.1; done"]);
+
+    Command::new(interminai_bin())
+        .arg("kill")
+        .arg("--socket")
+        .arg(env.socket())
+        .arg("--signal")
+        .arg("SIGWINCH")
+        .timeout(Duration::from_secs(2))
+        .assert()
+        .success();
+
+    thread::sleep(Duration::from_millis(500));
+
+    let output = Command::new(interminai_bin())
+        .arg("output")
+        .arg("--socket")
+        .arg(env.socket())
+        .timeout(Duration::from_secs(2))
+        .output()
+        .expect("Failed to get output");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("GOT_WINCH"), "Child should receive SIGWINCH. Got: {}", stdout);
+
+    daemon.stop();
+}
+
+#[test]
+fn test_output_gets_screen() {
+    let env = TestEnv::new();
+
+    let daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["bash", "-c", "echo 'Hello World'; sleep 10"]);
+
+    // Get screen output
+    let mut cmd = Command::new(interminai_bin());
+    let output = cmd
+        .arg("output")
+        .arg("--socket")
+        .arg(env.socket())
+        .timeout(Duration::from_secs(2))
+        .output()
+        .expect("Failed to run output command");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    // Should contain screen output
+    assert!(!stdout.is_empty());
+
+    daemon.stop();
+}
+
+#[test]
+fn test_output_trim_drops_trailing_blank_lines() {
+    let env = TestEnv::new();
+
+    let daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["bash", "-c", "echo hi; sleep 5"]);
+    thread::sleep(Duration::from_millis(300));
+
+    let output = Command::new(interminai_bin())
+        .arg("output")
+        .arg("--socket")
+        .arg(env.socket())
+        .arg("--no-color")
+        .arg("--trim")
+        .timeout(Duration::from_secs(2))
+        .output()
+        .expect("Failed to get output");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout, "hi\n", "Trimmed output should be a single line");
+
+    daemon.stop();
+}
+
+#[test]
+fn test_output_no_trailing_newline() {
+    let env = TestEnv::new();
+
+    let daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["bash", "-c", "echo hi; echo there; sleep 5"]);
+    thread::sleep(Duration::from_millis(300));
+
+    let output = Command::new(interminai_bin())
+        .arg("output")
+        .arg("--socket")
+        .arg(env.socket())
+        .arg("--no-color")
+        .arg("--trim")
+        .arg("--no-trailing-newline")
+        .timeout(Duration::from_secs(2))
+        .output()
+        .expect("Failed to get output");
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!stdout.ends_with('\n'), "Got: {:?}", stdout);
+    assert_eq!(stdout, "hi\nthere");
+
+    daemon.stop();
+}
+
+#[test]
+fn test_output_max_width_wraps_long_lines() {
+    let env = TestEnv::new();
+    let socket = env.socket();
+
+    let daemon = DaemonHandle::spawn(&[
+        "--socket", &socket, "--size", "120x5", "--no-daemon", "--",
+        "bash", "-c", "printf '%0120d' 0; sleep 5",
+    ]);
+    thread::sleep(Duration::from_millis(300));
+
+    let output = Command::new(interminai_bin())
+        .arg("output")
+        .arg("--socket")
+        .arg(&socket)
+        .arg("--no-color")
+        .arg("--trim")
+        .arg("--max-width")
+        .arg("40")
+        .timeout(Duration::from_secs(2))
+        .output()
+        .expect("Failed to get output");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines, vec!["0".repeat(40); 3], "120 columns should wrap into 3 lines of 40: {:?}", stdout);
+
+    daemon.stop();
+}
+
+#[test]
+fn test_output_format_jsonl_one_line_per_row() {
+    let env = TestEnv::new();
+
+    let daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["bash", "-c", "echo '  hi'; sleep 5"]);
+    thread::sleep(Duration::from_millis(300));
+
+    let output = Command::new(interminai_bin())
+        .arg("output")
+        .arg("--socket")
+        .arg(env.socket())
+        .arg("--format")
+        .arg("jsonl")
+        .timeout(Duration::from_secs(2))
+        .output()
+        .expect("Failed to get output");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let lines: Vec<&str> = stdout.lines().collect();
+    assert_eq!(lines.len(), 24, "Expected one line per screen row: {}", stdout);
+
+    for (i, line) in lines.iter().enumerate() {
+        let obj: serde_json::Value = serde_json::from_str(line).expect("Each line should be JSON");
+        assert_eq!(obj["row"], i + 1);
+        let text = obj["text"].as_str().unwrap();
+        assert_eq!(text.chars().count(), 80, "Row text should be untrimmed: {:?}", text);
+    }
+
+    let first: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+    assert!(first["text"].as_str().unwrap().starts_with("  hi  "),
+        "Row text should preserve spacing: {}", lines[0]);
+
+    daemon.stop();
+}
+
+#[test]
+fn test_output_links_reports_osc8_hyperlinks() {
+    let env = TestEnv::new();
+
+    let daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["bash", "-c",
+        "printf 'see \\033]8;;https://example.com/docs\\033\\\\the docs\\033]8;;\\033\\\\ here'; sleep 5"]);
+    thread::sleep(Duration::from_millis(300));
+
+    let output = Command::new(interminai_bin())
+        .arg("output")
+        .arg("--socket")
+        .arg(env.socket())
+        .arg("--no-color")
+        .arg("--links")
+        .timeout(Duration::from_secs(2))
+        .output()
+        .expect("Failed to get output");
+    assert!(output.status.success());
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("see the docs here"), "The URL should not leak onto the screen: {}", stdout);
+    assert!(stdout.contains("Link: row 1, col 5: the docs -> https://example.com/docs"),
+        "The link should be reported: {}", stdout);
+
+    daemon.stop();
+}
+
+#[test]
+fn test_output_format_raw_returns_bytes_verbatim() {
+    let env = TestEnv::new();
+
+    let daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["bash", "-c", "printf '\\033[31mRED\\033[0m\\033[?25l'; sleep 5"]);
+    thread::sleep(Duration::from_millis(300));
+
+    let raw_output = || {
+        Command::new(interminai_bin())
+            .arg("output")
+            .arg("--socket")
+            .arg(env.socket())
+            .arg("--format")
+            .arg("raw")
+            .timeout(Duration::from_secs(2))
+            .output()
+            .expect("Failed to get output")
+    };
+
+    let output = raw_output();
+    assert!(output.status.success());
+    let needle = b"\x1b[31mRED\x1b[0m\x1b[?25l";
+    assert!(output.stdout.windows(needle.len()).any(|w| w == needle),
+        "Raw output should contain the escape sequences verbatim: {:?}",
+        String::from_utf8_lossy(&output.stdout));
+
+    // Bytes are drained on read
+    let output = raw_output();
+    assert!(output.stdout.is_empty(), "Second raw read should be empty: {:?}",
+        String::from_utf8_lossy(&output.stdout));
+
+    daemon.stop();
+}
+
+#[test]
+fn test_output_format_raw_normalize_newlines() {
+    let env = TestEnv::new();
+
+    // Without onlcr the program's line endings reach the PTY as written
+    let script = "stty -onlcr; printf 'crlf\\r\\nlf\\ncr\\r\\033[1mbold\\033[0m\\n'; sleep 5";
+    let daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["bash", "-c", script]);
+    thread::sleep(Duration::from_millis(300));
+
+    let output = Command::new(interminai_bin())
+        .arg("output")
+        .arg("--socket")
+        .arg(env.socket())
+        .arg("--format")
+        .arg("raw")
+        .arg("--normalize-newlines")
+        .timeout(Duration::from_secs(2))
+        .output()
+        .expect("Failed to get output");
+    assert!(output.status.success());
+    let needle = b"crlf\nlf\ncr\n\x1b[1mbold\x1b[0m\n";
+    assert!(output.stdout.windows(needle.len()).any(|w| w == needle),
+        "Line endings should be LF with escape sequences kept: {:?}",
+        String::from_utf8_lossy(&output.stdout));
+    assert!(!output.stdout.contains(&b'\r'), "No CR should be left: {:?}",
+        String::from_utf8_lossy(&output.stdout));
+
+    daemon.stop();
+}
+
+#[test]
+fn test_output_format_raw_strip_ansi_leaves_text() {
+    let env = TestEnv::new();
+
+    // Colors, cursor movement, a title and a line rewritten with CR
+    let script = "printf '\\033]0;title\\007\\033[1;32mgreen\\033[0m plain\\n\\033[2;1H\\033[Kmoved\\r\\n50%%\\r100%%\\n'; sleep 5";
+    let daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["bash", "-c", script]);
+    thread::sleep(Duration::from_millis(300));
+
+    let output = Command::new(interminai_bin())
+        .arg("output")
+        .arg("--socket")
+        .arg(env.socket())
+        .arg("--format")
+        .arg("raw")
+        .arg("--strip-ansi")
+        .timeout(Duration::from_secs(2))
+        .output()
+        .expect("Failed to get output");
+    assert!(output.status.success());
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(stdout, "green plain\nmoved\n50%\n100%\n", "Only the printed text should remain");
+
+    daemon.stop();
+}
+
+#[test]
+fn test_output_format_raw_since_reads_incrementally() {
+    let env = TestEnv::new();
+
+    let daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["bash", "-c", "printf ONE; sleep 1; printf TWO; sleep 5"]);
+    thread::sleep(Duration::from_millis(300));
+
+    let read_since = |offset: &str| {
+        let output = Command::new(interminai_bin())
+            .arg("output")
+            .arg("--socket")
+            .arg(env.socket())
+            .arg("--format")
+            .arg("raw")
+            .arg("--since")
+            .arg(offset)
+            .timeout(Duration::from_secs(2))
+            .output()
+            .expect("Failed to get output");
+        assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let offset = stderr.lines()
+            .find_map(|l| l.strip_prefix("Offset: "))
+            .expect("Offset should be printed to stderr")
+            .to_string();
+        (String::from_utf8_lossy(&output.stdout).to_string(), offset)
+    };
+
+    let (first, offset) = read_since("0");
+    assert_eq!(first, "ONE");
+    assert_eq!(offset, "3");
+
+    thread::sleep(Duration::from_millis(1200));
+
+    // Only the new bytes, with no repeat of the first read
+    let (second, offset) = read_since(&offset);
+    assert_eq!(second, "TWO");
+    assert_eq!(offset, "6");
+
+    // Offset reads don't drain: a plain raw read still sees everything
+    let output = Command::new(interminai_bin())
+        .arg("output")
+        .arg("--socket")
+        .arg(env.socket())
+        .arg("--format")
+        .arg("raw")
+        .timeout(Duration::from_secs(2))
+        .output()
+        .expect("Failed to get output");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "ONETWO");
+
+    Command::new(interminai_bin())
+        .arg("output")
+        .arg("--socket")
+        .arg(env.socket())
+        .arg("--format")
+        .arg("raw")
+        .arg("--since")
+        .arg("100")
+        .timeout(Duration::from_secs(2))
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("Offset 100 is past the end of the output (6)"));
+
+    daemon.stop();
+}
+
+#[test]
+fn test_output_format_raw_compressed_matches_plain() {
+    use base64::Engine;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::os::unix::net::UnixStream;
+
+    let env = TestEnv::new();
+
+    let daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["bash", "-c", "seq 1 30000; sleep 30"]);
+    thread::sleep(Duration::from_millis(1000));
+
+    let request = |request: serde_json::Value| -> serde_json::Value {
+        let mut stream = UnixStream::connect(env.socket()).expect("Failed to connect");
+        writeln!(stream, "{}", request).unwrap();
+        let mut line = String::new();
+        BufReader::new(stream).read_line(&mut line).unwrap();
+        serde_json::from_str(&line).expect("Invalid response")
+    };
+
+    let ping = request(serde_json::json!({ "type": "PING" }));
+    assert_eq!(ping["data"]["compression"], serde_json::json!(["gzip"]));
+
+    // Offset reads don't drain, so both see the same bytes
+    let plain = request(serde_json::json!({ "type": "OUTPUT", "format": "raw", "since": 0 }));
+    let plain = base64::engine::general_purpose::STANDARD
+        .decode(plain["data"]["data_b64"].as_str().expect("Plain data expected"))
+        .unwrap();
+    let gz = request(serde_json::json!({ "type": "OUTPUT", "format": "raw", "since": 0, "compression": "gzip" }));
+    assert!(gz["data"].get("data_b64").is_none(), "Compressed data replaces data_b64: {}", gz);
+    let compressed = base64::engine::general_purpose::STANDARD
+        .decode(gz["data"]["data_gz"].as_str().expect("Compressed data expected"))
+        .unwrap();
+    let mut decompressed = Vec::new();
+    flate2::read::GzDecoder::new(&compressed[..]).read_to_end(&mut decompressed).unwrap();
+
+    assert!(plain.len() > 100_000, "Capture should be large: {} bytes", plain.len());
+    assert_eq!(decompressed, plain);
+    assert!(compressed.len() * 2 < plain.len(), "gzip should shrink {} bytes, got {}", plain.len(), compressed.len());
+
+    // The client decompresses transparently
+    let output = Command::new(interminai_bin())
+        .arg("output")
+        .arg("--socket")
+        .arg(env.socket())
+        .arg("--format")
+        .arg("raw")
+        .arg("--compress")
+        .timeout(Duration::from_secs(5))
+        .output()
+        .expect("Failed to get output");
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    assert_eq!(output.stdout, plain);
+
+    let unsupported = request(serde_json::json!({ "type": "OUTPUT", "format": "raw", "compression": "zstd" }));
+    assert_eq!(unsupported["status"], "error");
+
+    daemon.stop();
+}
+
+#[test]
+fn test_strip_ansi_requires_raw_format() {
+    Command::new(interminai_bin())
+        .arg("output")
+        .arg("--socket")
+        .arg("/nonexistent.sock")
+        .arg("--strip-ansi")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("--strip-ansi requires --format raw"));
+}
+
+#[test]
+fn test_clear_blanks_screen_and_keeps_child_running() {
+    let env = TestEnv::new();
+
+    // Fill the screen and push lines into scrollback
+    let daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["bash", "-c", "seq 1 100; sleep 10"]);
+    thread::sleep(Duration::from_millis(300));
+
+    Command::new(interminai_bin())
+        .arg("clear")
+        .arg("--socket")
+        .arg(env.socket())
+        .timeout(Duration::from_secs(2))
+        .assert()
+        .success();
+
+    let output = Command::new(interminai_bin())
+        .arg("output")
+        .arg("--socket")
+        .arg(env.socket())
+        .arg("--no-color")
+        .arg("--from")
+        .arg("-")
+        .arg("--cursor")
+        .arg("print")
+        .timeout(Duration::from_secs(2))
+        .output()
+        .expect("Failed to get output");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    let mut lines = stdout.lines();
+    assert_eq!(lines.next(), Some("Cursor: row 1, col 1"), "Cursor should be home: {}", stdout);
+    assert!(lines.all(|l| l.is_empty()), "Screen and scrollback should be blank: {}", stdout);
+
+    Command::new(interminai_bin())
+        .arg("status")
+        .arg("--socket")
+        .arg(env.socket())
+        .arg("--quiet")
+        .timeout(Duration::from_secs(2))
+        .assert()
+        .success();
+
+    daemon.stop();
+}
+
+#[test]
+fn test_restore_brings_back_snapshot_view() {
     let env = TestEnv::new();
 
-    let daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["sleep", "100"]);
+    let daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["bash", "-c", "seq 1 40; exec cat"]);
+    thread::sleep(Duration::from_millis(300));
 
-    // Kill with numeric signal 15 (SIGTERM)
-    Command::new(interminai_bin())
-        .arg("kill")
+    let run = |args: &[&str]| Command::new(interminai_bin())
+        .args(args)
         .arg("--socket")
-        .arg(&env.socket())
-        .arg("--signal")
-        .arg("15")
+        .arg(env.socket())
         .timeout(Duration::from_secs(2))
-        .assert()
-        .success();
+        .output()
+        .expect("Failed to run interminai");
+    let view = || String::from_utf8_lossy(&run(&["output", "--no-color", "--from", "-", "--cursor", "print"]).stdout).to_string();
 
-    // Wait for process to die
-    thread::sleep(Duration::from_millis(500));
+    run(&["input", "--text", "first", "--enter"]);
+    thread::sleep(Duration::from_millis(200));
+    let before = view();
+    assert!(before.contains("first") && before.contains("\n1\n"), "Got: {}", before);
 
-    // Should no longer be running
-    let result = Command::new(interminai_bin())
-        .arg("status")
-        .arg("--quiet")
+    let snapshot = run(&["snapshot"]);
+    assert!(snapshot.status.success(), "stderr: {}", String::from_utf8_lossy(&snapshot.stderr));
+
+    run(&["input", "--text", "second", "--enter"]);
+    thread::sleep(Duration::from_millis(200));
+    run(&["clear"]);
+    run(&["input", "--text", "third", "--enter"]);
+    thread::sleep(Duration::from_millis(200));
+    assert!(!view().contains("first"), "The screen should have changed");
+
+    Command::new(interminai_bin())
+        .arg("restore")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
+        .write_stdin(snapshot.stdout)
         .timeout(Duration::from_secs(2))
-        .output();
+        .assert()
+        .success();
+    assert_eq!(view(), before, "Screen, scrollback and cursor should be as snapshotted");
 
-    assert!(result.is_err() || !result.unwrap().status.success());
+    // Only the view was restored: the program is still there
+    run(&["input", "--text", "fourth", "--enter"]);
+    thread::sleep(Duration::from_millis(200));
+    assert_eq!(view().matches("fourth").count(), 2, "cat should still echo");
 
     daemon.stop();
 }
 
 #[test]
-fn test_kill_numeric_signal_2() {
+fn test_restore_rejects_bad_or_mismatched_snapshot() {
     let env = TestEnv::new();
 
-    // Use a bash script that keeps bash alive (multiple commands prevent exec optimization)
-    let daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["bash", "-c", "trap 'exit 88' INT; while true; do sleep 1; done"]);
+    let daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["sleep", "10"]);
+    thread::sleep(Duration::from_millis(300));
 
-    // Send numeric signal 2 (SIGINT)
     Command::new(interminai_bin())
-        .arg("kill")
+        .arg("restore")
         .arg("--socket")
-        .arg(&env.socket())
-        .arg("--signal")
-        .arg("2")
+        .arg(env.socket())
+        .write_stdin("not a snapshot")
         .timeout(Duration::from_secs(2))
         .assert()
-        .success();
-
-    // Give bash trap time to execute
-    thread::sleep(Duration::from_millis(500));
+        .failure()
+        .stderr(predicate::str::contains("Invalid snapshot"));
 
-    // Wait for process to actually exit
+    let snapshot = Command::new(interminai_bin())
+        .arg("snapshot")
+        .arg("--socket")
+        .arg(env.socket())
+        .timeout(Duration::from_secs(2))
+        .output()
+        .expect("Failed to snapshot");
     Command::new(interminai_bin())
-        .arg("wait")
+        .arg("resize")
         .arg("--socket")
-        .arg(&env.socket())
-        .timeout(Duration::from_secs(3))
+        .arg(env.socket())
+        .arg("--size")
+        .arg("100x30")
+        .timeout(Duration::from_secs(2))
         .assert()
         .success();
+    Command::new(interminai_bin())
+        .arg("restore")
+        .arg("--socket")
+        .arg(env.socket())
+        .write_stdin(snapshot.stdout)
+        .timeout(Duration::from_secs(2))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Snapshot is 80x24 but the terminal is now 100x30"));
 
-    // Now check the exit code - should be 88 from the trap
-    let output = Command::new(interminai_bin())
-        .arg("status")
+    daemon.stop();
+}
+
+#[test]
+fn test_restore_keeps_autowrap_off() {
+    let env = TestEnv::new();
+
+    let daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["bash", "-c", "printf '\\e[?7l'; exec cat"]);
+    thread::sleep(Duration::from_millis(300));
+
+    let run = |args: &[&str]| Command::new(interminai_bin())
+        .args(args)
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .timeout(Duration::from_secs(2))
         .output()
-        .expect("Failed to get running status");
+        .expect("Failed to run interminai");
 
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    assert!(stdout.contains("88"), "Expected exit code 88 from trap, got: {}", stdout);
+    let snapshot = run(&["snapshot"]);
+    assert!(snapshot.status.success(), "stderr: {}", String::from_utf8_lossy(&snapshot.stderr));
+    Command::new(interminai_bin())
+        .arg("restore")
+        .arg("--socket")
+        .arg(env.socket())
+        .write_stdin(snapshot.stdout)
+        .timeout(Duration::from_secs(2))
+        .assert()
+        .success();
+
+    // The echo of a line longer than the screen stays on one row
+    run(&["input", "--text", &"x".repeat(100)]);
+    thread::sleep(Duration::from_millis(200));
+    let screen = String::from_utf8_lossy(&run(&["output", "--no-color"]).stdout).to_string();
+    let lines: Vec<&str> = screen.lines().collect();
+    assert_eq!(lines[0], "x".repeat(80), "Got: {}", screen);
+    assert!(lines[1].is_empty(), "Autowrap should still be off: {}", screen);
 
     daemon.stop();
 }
 
 #[test]
-fn test_output_gets_screen() {
+fn test_restore_rejects_alt_screen_snapshot() {
     let env = TestEnv::new();
 
-    let daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["bash", "-c", "echo 'Hello World'; sleep 10"]);
+    let daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["bash", "-c", "echo main; printf '\\e[?1049hon alt'; sleep 10"]);
+    thread::sleep(Duration::from_millis(300));
 
-    // Get screen output
-    let mut cmd = Command::new(interminai_bin());
-    let output = cmd
-        .arg("output")
+    let snapshot = Command::new(interminai_bin())
+        .arg("snapshot")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .timeout(Duration::from_secs(2))
         .output()
-        .expect("Failed to run output command");
-
-    let stdout = String::from_utf8_lossy(&output.stdout);
-
-    // Should contain screen output
-    assert!(stdout.len() > 0);
+        .expect("Failed to snapshot");
+    assert!(snapshot.status.success(), "stderr: {}", String::from_utf8_lossy(&snapshot.stderr));
+    Command::new(interminai_bin())
+        .arg("restore")
+        .arg("--socket")
+        .arg(env.socket())
+        .write_stdin(snapshot.stdout)
+        .timeout(Duration::from_secs(2))
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("Snapshot was taken on the alternate screen"));
 
     daemon.stop();
 }
@@ -671,7 +1371,7 @@ fn test_input_sends_keys() {
     let mut cmd = Command::new(interminai_bin());
     cmd.arg("input")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .write_stdin("Hello\n")
         .timeout(Duration::from_secs(2))
         .assert()
@@ -683,7 +1383,7 @@ fn test_input_sends_keys() {
     let output = Command::new(interminai_bin())
         .arg("output")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .timeout(Duration::from_secs(2))
         .output()
         .expect("Failed to get output");
@@ -709,7 +1409,7 @@ fn test_stop_terminates_daemon() {
     Command::new(interminai_bin())
         .arg("stop")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .timeout(Duration::from_secs(2))
         .assert()
         .success();
@@ -721,7 +1421,7 @@ fn test_stop_terminates_daemon() {
     let result = Command::new(interminai_bin())
         .arg("output")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .timeout(Duration::from_secs(2))
         .output();
 
@@ -743,7 +1443,7 @@ fn test_user_socket_not_deleted_on_stop() {
     Command::new(interminai_bin())
         .arg("stop")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .timeout(Duration::from_secs(2))
         .output()
         .ok();
@@ -761,6 +1461,75 @@ fn test_user_socket_not_deleted_on_stop() {
     // Note: daemon already stopped, don't call stop() again
 }
 
+/// Run `stop` with extra flags and give the daemon time to clean up
+fn stop_with(socket: &str, flags: &[&str]) {
+    Command::new(interminai_bin())
+        .arg("stop")
+        .arg("--socket")
+        .arg(socket)
+        .args(flags)
+        .timeout(Duration::from_secs(2))
+        .assert()
+        .success();
+
+    // The daemon first gives the child up to 200ms to exit
+    thread::sleep(Duration::from_millis(500));
+}
+
+#[test]
+fn test_stop_socket_overrides_on_user_socket() {
+    let env = TestEnv::new();
+
+    let mut daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["sleep", "100"]);
+    stop_with(daemon.socket(), &["--keep-socket"]);
+    let _ = daemon.child.wait();
+    assert!(env.socket_path.exists(), "--keep-socket should keep the user's socket");
+
+    let mut daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["sleep", "100"]);
+    stop_with(daemon.socket(), &["--remove-socket"]);
+    let _ = daemon.child.wait();
+    assert!(!env.socket_path.exists(), "--remove-socket should delete the user's socket");
+}
+
+#[test]
+fn test_stop_socket_overrides_on_auto_socket() {
+    let daemon = DaemonHandle::spawn(&["--", "sleep", "100"]);
+    let socket = std::path::PathBuf::from(daemon.socket());
+    stop_with(daemon.socket(), &["--keep-socket"]);
+    assert!(socket.exists(), "--keep-socket should keep the generated socket");
+    let _ = std::fs::remove_dir_all(socket.parent().unwrap());
+
+    let daemon = DaemonHandle::spawn(&["--", "sleep", "100"]);
+    let socket = std::path::PathBuf::from(daemon.socket());
+    stop_with(daemon.socket(), &["--remove-socket"]);
+    assert!(!socket.exists(), "--remove-socket should delete the generated socket");
+    assert!(!socket.parent().unwrap().exists(), "and its directory");
+}
+
+#[test]
+fn test_stop_socket_flags_conflict() {
+    Command::new(interminai_bin())
+        .arg("stop")
+        .arg("--socket")
+        .arg("/nonexistent/socket")
+        .arg("--keep-socket")
+        .arg("--remove-socket")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+}
+
+#[test]
+fn test_version_prints_crate_version() {
+    Command::new(interminai_bin())
+        .arg("version")
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(format!("interminai {}", env!("CARGO_PKG_VERSION"))))
+        .stdout(predicate::str::contains("Protocol: "))
+        .stdout(predicate::str::contains("Backends: xterm (default), custom"));
+}
+
 #[test]
 fn test_socket_reuse() {
     let env = TestEnv::new();
@@ -772,7 +1541,7 @@ fn test_socket_reuse() {
     Command::new(interminai_bin())
         .arg("stop")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .timeout(Duration::from_secs(2))
         .output()
         .ok();
@@ -815,7 +1584,7 @@ fn test_terminal_size_option() {
     let mut cmd = std::process::Command::new(interminai_bin());
     cmd.arg("start")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .arg("--size")
         .arg("120x40")
         .arg("--")
@@ -837,7 +1606,7 @@ fn test_terminal_size_option() {
     let output = Command::new(interminai_bin())
         .arg("output")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .timeout(Duration::from_secs(2))
         .output()
         .expect("Failed to get output");
@@ -845,13 +1614,13 @@ fn test_terminal_size_option() {
     let stdout_out = String::from_utf8_lossy(&output.stdout);
 
     // Output should indicate terminal size (this will depend on format)
-    assert!(stdout_out.len() > 0);
+    assert!(!stdout_out.is_empty());
 
     // Cleanup
     Command::new(interminai_bin())
         .arg("stop")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .output()
         .ok();
 
@@ -873,7 +1642,7 @@ fn test_client_dies_before_response() {
     let mut child = std::process::Command::new(interminai_bin())
         .arg("output")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .stdout(std::process::Stdio::piped())
         .stderr(std::process::Stdio::piped())
         .spawn()
@@ -882,6 +1651,7 @@ fn test_client_dies_before_response() {
     // Kill the client immediately (before it reads response)
     thread::sleep(Duration::from_millis(50));
     child.kill().ok();
+    child.wait().ok();
 
     // Give daemon time to notice and clean up
     thread::sleep(Duration::from_millis(300));
@@ -890,7 +1660,7 @@ fn test_client_dies_before_response() {
     let output = Command::new(interminai_bin())
         .arg("status")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .timeout(Duration::from_secs(2))
         .output()
         .expect("Daemon should still respond after client disconnect");
@@ -901,7 +1671,7 @@ fn test_client_dies_before_response() {
     let output = Command::new(interminai_bin())
         .arg("output")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .timeout(Duration::from_secs(2))
         .output()
         .expect("Should get output after client disconnect");
@@ -923,7 +1693,7 @@ fn test_incomplete_request_daemon_survives() {
     let daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["sleep", "10"]);
 
     // Send incomplete/malformed request directly to socket
-    if let Ok(mut stream) = UnixStream::connect(&env.socket()) {
+    if let Ok(mut stream) = UnixStream::connect(env.socket()) {
         // Send incomplete JSON or garbage
         stream.write_all(b"{\"incomplete\":").ok();
         stream.flush().ok();
@@ -936,7 +1706,7 @@ fn test_incomplete_request_daemon_survives() {
     let output = Command::new(interminai_bin())
         .arg("status")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .timeout(Duration::from_secs(2))
         .output()
         .expect("Daemon should survive malformed request");
@@ -947,7 +1717,7 @@ fn test_incomplete_request_daemon_survives() {
     let output = Command::new(interminai_bin())
         .arg("output")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .timeout(Duration::from_secs(2))
         .output()
         .expect("Should accept valid request after malformed one");
@@ -967,7 +1737,7 @@ fn test_invalid_request_gets_error_response() {
     let daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["sleep", "10"]);
 
     // Send invalid request directly to socket
-    if let Ok(mut stream) = UnixStream::connect(&env.socket()) {
+    if let Ok(mut stream) = UnixStream::connect(env.socket()) {
         // Send malformed request
         stream.write_all(b"INVALID_COMMAND\n").ok();
         stream.flush().ok();
@@ -989,7 +1759,7 @@ fn test_invalid_request_gets_error_response() {
     let output = Command::new(interminai_bin())
         .arg("status")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .timeout(Duration::from_secs(2))
         .output()
         .expect("Daemon should still be running after invalid request");
@@ -1049,7 +1819,7 @@ fn test_multiple_clients_simultaneous() {
     Command::new(interminai_bin())
         .arg("status")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .timeout(Duration::from_secs(2))
         .assert()
         .success();
@@ -1074,7 +1844,7 @@ fn test_vim_create_file() {
     let output = Command::new(interminai_bin())
         .arg("output")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .timeout(Duration::from_secs(2))
         .output()
         .expect("Failed to get output");
@@ -1088,7 +1858,7 @@ fn test_vim_create_file() {
     Command::new(interminai_bin())
         .arg("input")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .write_stdin("i")
         .timeout(Duration::from_secs(2))
         .assert()
@@ -1100,7 +1870,7 @@ fn test_vim_create_file() {
     Command::new(interminai_bin())
         .arg("input")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .write_stdin("Hello from vim!")
         .timeout(Duration::from_secs(2))
         .assert()
@@ -1112,7 +1882,7 @@ fn test_vim_create_file() {
     Command::new(interminai_bin())
         .arg("input")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .write_stdin("\x1b:wq\n") // ESC, :wq, Enter
         .timeout(Duration::from_secs(2))
         .assert()
@@ -1144,7 +1914,7 @@ fn test_vim_edit_existing_file() {
     let output = Command::new(interminai_bin())
         .arg("output")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .timeout(Duration::from_secs(2))
         .output()
         .expect("Failed to get output");
@@ -1156,7 +1926,7 @@ fn test_vim_edit_existing_file() {
     Command::new(interminai_bin())
         .arg("input")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .write_stdin("GA\nAdded line\x1b:wq\n") // G, A, newline, text, ESC, :wq
         .timeout(Duration::from_secs(2))
         .assert()
@@ -1185,7 +1955,7 @@ fn test_vim_insert_mode_visible() {
     Command::new(interminai_bin())
         .arg("input")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .write_stdin("i")
         .timeout(Duration::from_secs(2))
         .assert()
@@ -1197,7 +1967,7 @@ fn test_vim_insert_mode_visible() {
     let output = Command::new(interminai_bin())
         .arg("output")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .timeout(Duration::from_secs(2))
         .output()
         .expect("Failed to get output");
@@ -1212,7 +1982,7 @@ fn test_vim_insert_mode_visible() {
     Command::new(interminai_bin())
         .arg("input")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .write_stdin("\x1b:q!\n") // ESC, :q!, Enter (quit without saving)
         .timeout(Duration::from_secs(2))
         .output()
@@ -1234,7 +2004,7 @@ fn test_vim_multiline_edit() {
     Command::new(interminai_bin())
         .arg("input")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .write_stdin("i")
         .timeout(Duration::from_secs(2))
         .assert()
@@ -1245,7 +2015,7 @@ fn test_vim_multiline_edit() {
     Command::new(interminai_bin())
         .arg("input")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .write_stdin("Line 1\nLine 2\nLine 3")
         .timeout(Duration::from_secs(2))
         .assert()
@@ -1257,7 +2027,7 @@ fn test_vim_multiline_edit() {
     let output = Command::new(interminai_bin())
         .arg("output")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .timeout(Duration::from_secs(2))
         .output()
         .expect("Failed to get output");
@@ -1271,7 +2041,7 @@ fn test_vim_multiline_edit() {
     Command::new(interminai_bin())
         .arg("input")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .write_stdin("\x1b:wq\n")
         .timeout(Duration::from_secs(2))
         .assert()
@@ -1301,7 +2071,7 @@ fn test_vim_quit_without_save() {
     Command::new(interminai_bin())
         .arg("input")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .write_stdin("iShould not be saved\x1b")
         .timeout(Duration::from_secs(2))
         .assert()
@@ -1313,7 +2083,7 @@ fn test_vim_quit_without_save() {
     Command::new(interminai_bin())
         .arg("input")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .write_stdin(":q!\n")
         .timeout(Duration::from_secs(2))
         .output()
@@ -1346,7 +2116,7 @@ fn test_vim_arrow_key_navigation() {
     Command::new(interminai_bin())
         .arg("input")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .write_stdin("iLine1\nLine2\nLine3")
         .timeout(Duration::from_secs(2))
         .assert()
@@ -1358,7 +2128,7 @@ fn test_vim_arrow_key_navigation() {
     Command::new(interminai_bin())
         .arg("input")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .write_stdin("\x1b")
         .timeout(Duration::from_secs(2))
         .assert()
@@ -1370,7 +2140,7 @@ fn test_vim_arrow_key_navigation() {
     Command::new(interminai_bin())
         .arg("input")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .write_stdin("\x1b[A")  // Up arrow
         .timeout(Duration::from_secs(2))
         .assert()
@@ -1382,7 +2152,7 @@ fn test_vim_arrow_key_navigation() {
     Command::new(interminai_bin())
         .arg("input")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .write_stdin("\x1b[B")  // Down arrow
         .timeout(Duration::from_secs(2))
         .assert()
@@ -1394,7 +2164,7 @@ fn test_vim_arrow_key_navigation() {
     Command::new(interminai_bin())
         .arg("input")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .write_stdin("\x1b[C\x1b[C")  // Right arrow twice
         .timeout(Duration::from_secs(2))
         .assert()
@@ -1406,7 +2176,7 @@ fn test_vim_arrow_key_navigation() {
     Command::new(interminai_bin())
         .arg("input")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .write_stdin("\x1b[D")  // Left arrow
         .timeout(Duration::from_secs(2))
         .assert()
@@ -1418,7 +2188,7 @@ fn test_vim_arrow_key_navigation() {
     let output = Command::new(interminai_bin())
         .arg("output")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .timeout(Duration::from_secs(2))
         .output()
         .expect("Failed to get output");
@@ -1434,7 +2204,7 @@ fn test_vim_arrow_key_navigation() {
     Command::new(interminai_bin())
         .arg("input")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .write_stdin(":q!\n")
         .timeout(Duration::from_secs(2))
         .output()
@@ -1456,7 +2226,7 @@ fn test_vim_save_and_verify() {
     Command::new(interminai_bin())
         .arg("input")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .write_stdin("iFirst line\nSecond line\nThird line")
         .timeout(Duration::from_secs(2))
         .assert()
@@ -1468,7 +2238,7 @@ fn test_vim_save_and_verify() {
     Command::new(interminai_bin())
         .arg("input")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .write_stdin("\x1b:w\n")  // ESC, :w, Enter
         .timeout(Duration::from_secs(2))
         .assert()
@@ -1487,7 +2257,7 @@ fn test_vim_save_and_verify() {
     Command::new(interminai_bin())
         .arg("input")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .write_stdin("aFourth line")  // append
         .timeout(Duration::from_secs(2))
         .assert()
@@ -1499,7 +2269,7 @@ fn test_vim_save_and_verify() {
     Command::new(interminai_bin())
         .arg("input")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .write_stdin("\x1b:w\n")
         .timeout(Duration::from_secs(2))
         .assert()
@@ -1515,7 +2285,7 @@ fn test_vim_save_and_verify() {
     Command::new(interminai_bin())
         .arg("input")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .write_stdin(":q\n")
         .timeout(Duration::from_secs(2))
         .output()
@@ -1537,7 +2307,7 @@ fn test_vim_wq_exits() {
     Command::new(interminai_bin())
         .arg("status")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .timeout(Duration::from_secs(2))
         .assert()
         .success(); // Should be running
@@ -1546,7 +2316,7 @@ fn test_vim_wq_exits() {
     Command::new(interminai_bin())
         .arg("input")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .write_stdin("iTest content for exit")
         .timeout(Duration::from_secs(2))
         .assert()
@@ -1558,7 +2328,7 @@ fn test_vim_wq_exits() {
     Command::new(interminai_bin())
         .arg("input")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .write_stdin("\x1b:wq\n")  // ESC, :wq, Enter
         .timeout(Duration::from_secs(2))
         .assert()
@@ -1571,7 +2341,7 @@ fn test_vim_wq_exits() {
     let result = Command::new(interminai_bin())
         .arg("status")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .timeout(Duration::from_secs(2))
         .output()
         .expect("Failed to check running status");
@@ -1607,7 +2377,7 @@ fn test_vim_exits_eventually_after_quit() {
     Command::new(interminai_bin())
         .arg("input")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .write_stdin(":q\n")
         .timeout(Duration::from_secs(2))
         .assert()
@@ -1622,7 +2392,7 @@ fn test_vim_exits_eventually_after_quit() {
             .arg("status")
             .arg("--quiet")
             .arg("--socket")
-            .arg(&env.socket())
+            .arg(env.socket())
             .timeout(Duration::from_secs(2))
             .output();
 
@@ -1639,3 +2409,302 @@ fn test_vim_exits_eventually_after_quit() {
     // Cleanup
     daemon.stop();
 }
+
+#[test]
+fn test_exec_prints_final_screen_and_exit_code() {
+    let runtime_dir = TempDir::new().unwrap();
+
+    let output = Command::new(interminai_bin())
+        .arg("exec")
+        .args(emulator_args())
+        .arg("--size")
+        .arg("40x10")
+        .arg("--")
+        .arg("bash")
+        .arg("-c")
+        .arg("echo hi; exit 3")
+        .env("XDG_RUNTIME_DIR", runtime_dir.path())
+        .timeout(Duration::from_secs(10))
+        .output()
+        .expect("Failed to run exec");
+
+    assert_eq!(output.status.code(), Some(3), "exec should exit with the command's exit code");
+    assert_eq!(String::from_utf8_lossy(&output.stdout), "hi\n");
+    assert!(String::from_utf8_lossy(&output.stderr).contains("Exit code: 3"),
+        "stderr: {}", String::from_utf8_lossy(&output.stderr));
+
+    // The session's socket and its directory are gone
+    assert_eq!(fs::read_dir(runtime_dir.path()).unwrap().count(), 0,
+        "exec should clean up its socket");
+}
+
+#[test]
+fn test_exec_timeout_stops_command() {
+    let start = std::time::Instant::now();
+
+    Command::new(interminai_bin())
+        .arg("exec")
+        .args(emulator_args())
+        .arg("--timeout")
+        .arg("1")
+        .arg("--")
+        .arg("bash")
+        .arg("-c")
+        .arg("echo started; sleep 30")
+        .timeout(Duration::from_secs(10))
+        .assert()
+        .code(124)
+        .stdout("started\n")
+        .stderr(predicate::str::contains("Timed out after 1.0s"));
+
+    assert!(start.elapsed() < Duration::from_secs(5), "exec should not wait for the command");
+}
+
+#[test]
+fn test_exec_rejects_out_of_range_timeout() {
+    Command::new(interminai_bin())
+        .arg("exec")
+        .arg("--timeout")
+        .arg("1e30")
+        .arg("--")
+        .arg("true")
+        .timeout(Duration::from_secs(5))
+        .assert()
+        .code(1)
+        .stderr(predicate::str::contains("--timeout is too large"));
+}
+
+#[test]
+fn test_render_prints_exact_screen() {
+    Command::new(interminai_bin())
+        .arg("render")
+        .args(emulator_args())
+        .arg("--size")
+        .arg("20x4")
+        .arg("--cursor")
+        .arg("print")
+        .write_stdin("one\r\n\x1b[31mtwo\x1b[0m\x1b[4;3Hfour\x1b[1;2H\x1b[P")
+        .timeout(Duration::from_secs(5))
+        .assert()
+        .success()
+        .stdout("Cursor: row 1, col 2\noe\ntwo\n\n  four\n");
+}
+
+#[test]
+fn test_render_pty_dump_matches_live_screen() {
+    let env = TestEnv::new();
+    let socket = env.socket();
+    let dump = env._temp_dir.path().join("pty.dump");
+
+    let script = "printf 'top\\n\\033[1;32mgreen\\033[0m\\n'; seq 1 30; printf '\\033[5;10Hmoved\\033[K'; sleep 30";
+    let daemon = DaemonHandle::spawn(&[
+        "--socket", &socket, "--no-daemon", "--pty-dump", dump.to_str().unwrap(),
+        "--", "bash", "-c", script,
+    ]);
+    thread::sleep(Duration::from_millis(500));
+
+    let live = Command::new(interminai_bin())
+        .arg("output")
+        .arg("--socket")
+        .arg(daemon.socket())
+        .arg("--no-color")
+        .timeout(Duration::from_secs(2))
+        .output()
+        .expect("Failed to get output");
+    assert!(live.status.success());
+    let live = String::from_utf8_lossy(&live.stdout).to_string();
+    assert!(live.contains("moved"), "Got: {}", live);
+
+    Command::new(interminai_bin())
+        .arg("render")
+        .args(emulator_args())
+        .arg("--file")
+        .arg(&dump)
+        .timeout(Duration::from_secs(5))
+        .assert()
+        .success()
+        .stdout(live);
+
+    daemon.stop();
+}
+
+#[test]
+fn test_cursor_style_reported() {
+    let env = TestEnv::new();
+    let socket = env.socket();
+
+    // Steady underline
+    let daemon = DaemonHandle::spawn(&[
+        "--socket", &socket, "--no-daemon",
+        "--", "sh", "-c", "printf '\\033[4 qready'; sleep 30",
+    ]);
+    thread::sleep(Duration::from_millis(1000));
+
+    Command::new(interminai_bin())
+        .arg("status")
+        .arg("--socket")
+        .arg(daemon.socket())
+        .timeout(Duration::from_secs(2))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("Cursor style: steady underline"));
+
+    Command::new(interminai_bin())
+        .arg("output")
+        .arg("--socket")
+        .arg(daemon.socket())
+        .arg("--no-color")
+        .arg("--cursor-style")
+        .timeout(Duration::from_secs(2))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("ready"))
+        .stdout(predicate::str::contains("Cursor style: steady underline"));
+
+    Command::new(interminai_bin())
+        .arg("output")
+        .arg("--socket")
+        .arg(daemon.socket())
+        .arg("--cursor-style")
+        .timeout(Duration::from_secs(2))
+        .assert()
+        .success()
+        .stdout(predicate::str::ends_with("\x1b[4 q"));
+}
+
+#[test]
+fn test_status_reports_mid_escape_sequence() {
+    let env = TestEnv::new();
+    let socket = env.socket();
+
+    // The color sequence arrives in two writes a second apart
+    let daemon = DaemonHandle::spawn(&[
+        "--socket", &socket, "--no-daemon",
+        "--", "sh", "-c", "printf 'A\\033[3'; sleep 2; printf '1mB'; sleep 30",
+    ]);
+
+    let status = || {
+        let output = Command::new(interminai_bin())
+            .arg("status")
+            .arg("--socket")
+            .arg(daemon.socket())
+            .timeout(Duration::from_secs(2))
+            .output()
+            .expect("Failed to get status");
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+
+    thread::sleep(Duration::from_millis(1000));
+    let partial = status();
+    assert!(partial.contains("Mid escape sequence: true"), "Half a CSI should be reported: {}", partial);
+    Command::new(interminai_bin())
+        .arg("output")
+        .arg("--socket")
+        .arg(daemon.socket())
+        .timeout(Duration::from_secs(2))
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("mid escape sequence"));
+
+    thread::sleep(Duration::from_millis(2000));
+    let complete = status();
+    assert!(complete.contains("Mid escape sequence: false"), "The finished CSI should be reported: {}", complete);
+    Command::new(interminai_bin())
+        .arg("output")
+        .arg("--socket")
+        .arg(daemon.socket())
+        .arg("--no-color")
+        .timeout(Duration::from_secs(2))
+        .assert()
+        .success()
+        .stdout(predicate::str::contains("AB"))
+        .stderr(predicate::str::contains("mid escape sequence").not());
+}
+
+#[test]
+fn test_status_reports_alt_screen() {
+    let env = TestEnv::new();
+    let socket = env.socket();
+
+    // xterm's terminfo has the alternate screen sequences; the shell stays
+    // around after vim quits
+    let daemon = DaemonHandle::spawn(&[
+        "--socket", &socket, "--no-daemon", "--term", "xterm-256color",
+        "--", "bash", "-c", "vim -u NONE; echo SHELL-BACK; sleep 30",
+    ]);
+
+    let status = || {
+        let output = Command::new(interminai_bin())
+            .arg("status")
+            .arg("--socket")
+            .arg(daemon.socket())
+            .timeout(Duration::from_secs(2))
+            .output()
+            .expect("Failed to get status");
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+
+    thread::sleep(Duration::from_millis(1000));
+    let in_vim = status();
+    assert!(in_vim.contains("Alternate screen: true"), "vim should be on the alternate screen: {}", in_vim);
+
+    Command::new(interminai_bin())
+        .arg("input")
+        .arg("--socket")
+        .arg(daemon.socket())
+        .arg("--text")
+        .arg(":q\\r")
+        .timeout(Duration::from_secs(2))
+        .assert()
+        .success();
+
+    thread::sleep(Duration::from_millis(500));
+    let after_vim = status();
+    assert!(after_vim.contains("Running: true"), "The shell should still be running: {}", after_vim);
+    assert!(after_vim.contains("Alternate screen: false"), "Quitting vim should leave the alternate screen: {}", after_vim);
+
+    daemon.stop();
+}
+
+#[test]
+fn test_output_reports_wrapped_and_truncated_lines() {
+    let env = TestEnv::new();
+    let socket = env.socket();
+
+    // 25 characters on a 10-column screen wrap twice; with autowrap off the
+    // second line's tail overwrites the last column
+    let daemon = DaemonHandle::spawn(&[
+        "--socket", &socket, "--no-daemon", "--size", "10x5",
+        "--", "sh", "-c", "printf 'abcdefghijklmnopqrstuvwxy\\r\\n'; sleep 2; printf '\\033[?7l0123456789XYZ'; sleep 30",
+    ]);
+    thread::sleep(Duration::from_millis(700));
+
+    let output = || Command::new(interminai_bin())
+        .arg("output")
+        .arg("--socket")
+        .arg(daemon.socket())
+        .arg("--no-color")
+        .arg("--verbose")
+        .timeout(Duration::from_secs(2))
+        .output()
+        .expect("Failed to get output");
+
+    let first = output();
+    let stderr = String::from_utf8_lossy(&first.stderr);
+    assert!(stderr.contains("2 lines wrapped at the right margin"), "Wraps should be reported: {}", stderr);
+    assert!(!stderr.contains("cut off"), "Nothing was cut off yet: {}", stderr);
+
+    // Counted since the previous read
+    let again = output();
+    let stderr = String::from_utf8_lossy(&again.stderr);
+    assert!(!stderr.contains("wrapped"), "Wraps are reported once: {}", stderr);
+
+    thread::sleep(Duration::from_millis(2000));
+    let truncated = output();
+    let stderr = String::from_utf8_lossy(&truncated.stderr);
+    assert!(stderr.contains("cut off at the right margin"), "Truncation should be reported: {}", stderr);
+    let stdout = String::from_utf8_lossy(&truncated.stdout);
+    assert!(stdout.contains("012345678Z"), "Screen: {}", stdout);
+
+    daemon.stop();
+}
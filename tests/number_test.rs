@@ -110,7 +110,7 @@ fn test_number_flag_adds_line_numbers() {
     let output = Command::new(interminai_bin())
         .arg("output")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .arg("-n")
         .timeout(Duration::from_secs(2))
         .output()
@@ -145,7 +145,7 @@ fn test_number_flag_long_form() {
     let output = Command::new(interminai_bin())
         .arg("output")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .arg("--number")
         .timeout(Duration::from_secs(2))
         .output()
@@ -172,7 +172,7 @@ fn test_number_flag_zero_padding_large_terminal() {
         cmd.arg("start")
             .args(emulator_args())
             .arg("--socket")
-            .arg(&env.socket())
+            .arg(env.socket())
             .arg("--size")
             .arg("80x100")
             .arg("--no-daemon")
@@ -204,7 +204,7 @@ fn test_number_flag_zero_padding_large_terminal() {
     let output = Command::new(interminai_bin())
         .arg("output")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .arg("-n")
         .timeout(Duration::from_secs(2))
         .output()
@@ -232,7 +232,7 @@ fn test_number_flag_without_disables_numbering() {
     let output = Command::new(interminai_bin())
         .arg("output")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .timeout(Duration::from_secs(2))
         .output()
         .expect("Failed to get output");
@@ -257,7 +257,7 @@ fn test_number_flag_with_cursor_print() {
     let output = Command::new(interminai_bin())
         .arg("output")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .arg("-n")
         .arg("--cursor")
         .arg("print")
@@ -291,7 +291,7 @@ fn test_number_flag_preserves_color() {
     let output = Command::new(interminai_bin())
         .arg("output")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .arg("-n")
         .timeout(Duration::from_secs(2))
         .output()
@@ -327,7 +327,7 @@ fn test_number_flag_keeps_prefix_uncolored_when_color_carries_across_lines() {
     let output = Command::new(interminai_bin())
         .arg("output")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .arg("-n")
         .timeout(Duration::from_secs(2))
         .output()
@@ -0,0 +1,512 @@
+use assert_cmd::Command;
+use std::thread;
+use std::time::Duration;
+use tempfile::TempDir;
+use std::path::PathBuf;
+
+mod common;
+use common::{interminai_bin, emulator, emulator_args};
+
+struct TestEnv {
+    _temp_dir: TempDir,
+    socket_path: PathBuf,
+}
+
+impl TestEnv {
+    fn new() -> Self {
+        let temp_dir = TempDir::new().expect("Failed to create temp dir");
+        let socket_path = temp_dir.path().join("test.sock");
+        Self {
+            _temp_dir: temp_dir,
+            socket_path,
+        }
+    }
+
+    fn socket(&self) -> String {
+        self.socket_path.to_str().unwrap().to_string()
+    }
+}
+
+struct DaemonHandle {
+    socket_path: String,
+}
+
+impl DaemonHandle {
+    /// Spawn a bash script that sends a terminal query and prints the reply
+    fn spawn_script(socket: &str, script: &str) -> Self {
+        let output = std::process::Command::new(interminai_bin())
+            .arg("start")
+            .args(emulator_args())
+            .arg("--socket")
+            .arg(socket)
+            .arg("--")
+            .arg("bash")
+            .arg("-c")
+            .arg(script)
+            .output()
+            .expect("Failed to start daemon");
+        if !output.status.success() {
+            panic!("Daemon failed to start: {}", String::from_utf8_lossy(&output.stderr));
+        }
+
+        thread::sleep(Duration::from_millis(1500));
+
+        DaemonHandle {
+            socket_path: socket.to_string()
+        }
+    }
+
+    fn get_output(&self) -> String {
+        let output = Command::new(interminai_bin())
+            .arg("output")
+            .arg("--socket")
+            .arg(&self.socket_path)
+            .arg("--no-color")
+            .timeout(Duration::from_secs(2))
+            .output()
+            .expect("Failed to get output");
+        String::from_utf8_lossy(&output.stdout).to_string()
+    }
+
+    fn stop(self) {
+        let _ = std::process::Command::new(interminai_bin())
+            .arg("stop")
+            .arg("--socket")
+            .arg(&self.socket_path)
+            .output();
+    }
+}
+
+/// Send a DECRQM query for private mode `mode` (after `setup`) and print the reply
+fn decrqm_script(setup: &str, mode: u32) -> String {
+    format!(r#"
+        stty -echo
+        printf '{}\033[?{}$p'
+        if read -r -t 1 -d 'y' response; then
+            echo "GOT:${{response#*[}}"
+        else
+            echo "NO_RESPONSE"
+        fi
+        sleep 5
+    "#, setup, mode)
+}
+
+/// Test XTVERSION (CSI > q) gets a DCS-wrapped version string
+#[test]
+fn test_xtversion_responds() {
+    let env = TestEnv::new();
+    let daemon = DaemonHandle::spawn_script(&env.socket(), r#"
+        stty -echo
+        printf '\033[>q'
+        # Reply is ESC P > | name(version) ESC \
+        if read -r -t 1 -d '\' response; then
+            version=${response#*|}
+            echo "GOT:${version%$'\033'}"
+        else
+            echo "NO_RESPONSE"
+        fi
+        sleep 5
+    "#);
+
+    let output = daemon.get_output();
+    assert!(output.contains("GOT:interminai("),
+        "Should receive XTVERSION reply. Got: {}", output);
+
+    daemon.stop();
+}
+
+/// Send a DECRQSS query for setting `setting` (after `setup`) and print the reply
+fn decrqss_script(setup: &str, setting: &str) -> String {
+    format!(r#"
+        stty -echo
+        printf '{}\033P$q{}\033\\'
+        # Reply is ESC P 1 $ r Pt ESC \ (or ESC P 0 $ r ESC \ if unknown)
+        if read -r -t 1 -d '\' response; then
+            reply=${{response#*P}}
+            echo "GOT:${{reply%$'\033'}}"
+        else
+            echo "NO_RESPONSE"
+        fi
+        sleep 5
+    "#, setup, setting)
+}
+
+/// Test DECRQSS for SGR reports the default attributes
+#[test]
+fn test_decrqss_sgr_default() {
+    let env = TestEnv::new();
+    let daemon = DaemonHandle::spawn_script(&env.socket(), &decrqss_script("", "m"));
+
+    let output = daemon.get_output();
+    assert!(output.contains("GOT:1$r0m"),
+        "Should report default SGR. Got: {}", output);
+
+    daemon.stop();
+}
+
+/// Test DECRQSS for SGR reflects attributes set by the application
+#[test]
+fn test_decrqss_sgr_bold() {
+    if emulator() == "custom" {
+        // The custom backend doesn't track attributes
+        return;
+    }
+
+    let env = TestEnv::new();
+    let daemon = DaemonHandle::spawn_script(&env.socket(), &decrqss_script("\\033[1m", "m"));
+
+    let output = daemon.get_output();
+    assert!(output.contains("GOT:1$r0;1m"),
+        "Should report bold SGR. Got: {}", output);
+
+    daemon.stop();
+}
+
+/// Test DECRQSS for the cursor style (DECSCUSR) reports a steady block by default
+#[test]
+fn test_decrqss_cursor_style_default() {
+    let env = TestEnv::new();
+    let daemon = DaemonHandle::spawn_script(&env.socket(), &decrqss_script("", " q"));
+
+    let output = daemon.get_output();
+    assert!(output.contains("GOT:1$r2 q"),
+        "Should report a steady block cursor. Got: {}", output);
+
+    daemon.stop();
+}
+
+/// Test DECRQSS for an unsupported setting gets the "invalid" reply
+#[test]
+fn test_decrqss_unknown_setting() {
+    let env = TestEnv::new();
+    let daemon = DaemonHandle::spawn_script(&env.socket(), &decrqss_script("", "z"));
+
+    let output = daemon.get_output();
+    assert!(output.contains("GOT:0$r"),
+        "Should report an invalid request. Got: {}", output);
+
+    daemon.stop();
+}
+
+/// Test DCS payload bytes (here a sixel image) don't leak onto the screen
+#[test]
+fn test_dcs_payload_not_printed() {
+    let env = TestEnv::new();
+    let daemon = DaemonHandle::spawn_script(&env.socket(), r#"
+        printf 'BEFORE\033Pq#0;2;0;0;0#0~~@@vv\033\\AFTER\n'
+        sleep 5
+    "#);
+
+    let output = daemon.get_output();
+    assert!(output.contains("BEFOREAFTER"),
+        "Text around the DCS string should be intact. Got: {}", output);
+    assert!(!output.contains("~~"), "DCS payload should not be printed. Got: {}", output);
+
+    daemon.stop();
+}
+
+/// Test unrecognized DCS strings are recorded in the debug buffer
+#[test]
+fn test_dcs_unknown_recorded_in_debug() {
+    if emulator() != "custom" {
+        // Only the custom backend tracks unhandled sequences
+        return;
+    }
+
+    let env = TestEnv::new();
+    let daemon = DaemonHandle::spawn_script(&env.socket(), r#"
+        printf '\033P+q544e\033\\'
+        sleep 5
+    "#);
+
+    let output = Command::new(interminai_bin())
+        .arg("debug")
+        .arg("--socket")
+        .arg(env.socket())
+        .timeout(Duration::from_secs(2))
+        .output()
+        .expect("Failed to get debug output");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("dcs \\eP+q544e"),
+        "Debug buffer should list the DCS string. Got: {}", stdout);
+
+    daemon.stop();
+}
+
+/// Test DECRQM reports autowrap (?7) as set by default
+#[test]
+fn test_decrqm_autowrap_default_set() {
+    let env = TestEnv::new();
+    let daemon = DaemonHandle::spawn_script(&env.socket(), &decrqm_script("", 7));
+
+    let output = daemon.get_output();
+    assert!(output.contains("GOT:?7;1$"),
+        "Autowrap should report set. Got: {}", output);
+
+    daemon.stop();
+}
+
+/// Test DECRQM reports autowrap (?7) as reset after CSI ?7l
+#[test]
+fn test_decrqm_autowrap_reset() {
+    let env = TestEnv::new();
+    let daemon = DaemonHandle::spawn_script(&env.socket(), &decrqm_script("\\033[?7l", 7));
+
+    let output = daemon.get_output();
+    assert!(output.contains("GOT:?7;2$"),
+        "Autowrap should report reset. Got: {}", output);
+
+    daemon.stop();
+}
+
+/// Test DECRQM reports origin mode (?6) as reset by default
+#[test]
+fn test_decrqm_origin_default_reset() {
+    let env = TestEnv::new();
+    let daemon = DaemonHandle::spawn_script(&env.socket(), &decrqm_script("", 6));
+
+    let output = daemon.get_output();
+    assert!(output.contains("GOT:?6;2$"),
+        "Origin mode should report reset. Got: {}", output);
+
+    daemon.stop();
+}
+
+/// Test DECRQM reports cursor visibility (?25) after hiding the cursor
+#[test]
+fn test_decrqm_cursor_hidden() {
+    let env = TestEnv::new();
+    let daemon = DaemonHandle::spawn_script(&env.socket(), &decrqm_script("\\033[?25l", 25));
+
+    let output = daemon.get_output();
+    assert!(output.contains("GOT:?25;2$"),
+        "Cursor visibility should report reset. Got: {}", output);
+
+    daemon.stop();
+}
+
+/// Test DECRQM reports bracketed paste (?2004) after enabling it
+#[test]
+fn test_decrqm_bracketed_paste_set() {
+    let env = TestEnv::new();
+    let daemon = DaemonHandle::spawn_script(&env.socket(), &decrqm_script("\\033[?2004h", 2004));
+
+    let output = daemon.get_output();
+    assert!(output.contains("GOT:?2004;1$"),
+        "Bracketed paste should report set. Got: {}", output);
+
+    daemon.stop();
+}
+
+/// Test DECRQM reports focus reporting (?1004) after enabling it
+#[test]
+fn test_decrqm_focus_reporting_set() {
+    let env = TestEnv::new();
+    let daemon = DaemonHandle::spawn_script(&env.socket(), &decrqm_script("\\033[?1004h", 1004));
+
+    let output = daemon.get_output();
+    assert!(output.contains("GOT:?1004;1$"),
+        "Focus reporting should report set. Got: {}", output);
+
+    daemon.stop();
+}
+
+/// Test status reports bracketed paste and focus reporting modes
+#[test]
+fn test_status_reports_modes() {
+    let env = TestEnv::new();
+    let daemon = DaemonHandle::spawn_script(&env.socket(), r#"
+        printf '\033[?2004h\033[?1004h'
+        sleep 5
+    "#);
+
+    let output = Command::new(interminai_bin())
+        .arg("status")
+        .arg("--socket")
+        .arg(env.socket())
+        .timeout(Duration::from_secs(2))
+        .output()
+        .expect("Failed to get status");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("Bracketed paste: true"),
+        "Status should report bracketed paste. Got: {}", stdout);
+    assert!(stdout.contains("Focus reporting: true"),
+        "Status should report focus reporting. Got: {}", stdout);
+
+    daemon.stop();
+}
+
+/// Test status reports modes as off when the application never enabled them
+#[test]
+fn test_status_reports_modes_default_off() {
+    let env = TestEnv::new();
+    let daemon = DaemonHandle::spawn_script(&env.socket(), "sleep 5");
+
+    let output = Command::new(interminai_bin())
+        .arg("status")
+        .arg("--socket")
+        .arg(env.socket())
+        .timeout(Duration::from_secs(2))
+        .output()
+        .expect("Failed to get status");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+
+    assert!(stdout.contains("Bracketed paste: false"),
+        "Bracketed paste should be off. Got: {}", stdout);
+    assert!(stdout.contains("Focus reporting: false"),
+        "Focus reporting should be off. Got: {}", stdout);
+
+    daemon.stop();
+}
+
+/// Test status counts BEL characters and clears the count after reading
+#[test]
+fn test_status_bell_count() {
+    let env = TestEnv::new();
+    let daemon = DaemonHandle::spawn_script(&env.socket(), r#"
+        printf 'ding\a\a\a'
+        sleep 5
+    "#);
+
+    let status = || {
+        let output = Command::new(interminai_bin())
+            .arg("status")
+            .arg("--socket")
+            .arg(env.socket())
+            .timeout(Duration::from_secs(2))
+            .output()
+            .expect("Failed to get status");
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+
+    let stdout = status();
+    assert!(stdout.contains("Bells: 3"), "Status should count 3 bells. Got: {}", stdout);
+
+    let stdout = status();
+    assert!(stdout.contains("Bells: 0"), "Bell count should clear after read. Got: {}", stdout);
+
+    daemon.stop();
+}
+
+/// Test input --focus sends focus in/out events to the application
+#[test]
+fn test_input_focus_events() {
+    let env = TestEnv::new();
+    let daemon = DaemonHandle::spawn_script(&env.socket(), r#"
+        stty raw -echo
+        printf '\033[?1004h'
+        events=$(head -c 6 | od -An -c | tr -s ' ')
+        stty sane
+        echo "EVENTS:$events"
+        sleep 5
+    "#);
+
+    for focus in ["in", "out"] {
+        let output = Command::new(interminai_bin())
+            .arg("input")
+            .arg("--socket")
+            .arg(env.socket())
+            .arg("--focus")
+            .arg(focus)
+            .timeout(Duration::from_secs(2))
+            .output()
+            .expect("Failed to send focus event");
+        assert!(output.status.success(), "input --focus {} should succeed", focus);
+    }
+
+    thread::sleep(Duration::from_millis(500));
+
+    let output = daemon.get_output();
+    assert!(output.contains("EVENTS: 033 [ I 033 [ O"),
+        "Should receive focus in then focus out. Got: {}", output);
+
+    daemon.stop();
+}
+
+/// Test input --focus rejects values other than in/out
+#[test]
+fn test_input_focus_invalid_value() {
+    let output = Command::new(interminai_bin())
+        .arg("input")
+        .arg("--socket")
+        .arg("/nonexistent/socket")
+        .arg("--focus")
+        .arg("sideways")
+        .output()
+        .expect("Failed to run input");
+
+    assert!(!output.status.success(), "Invalid --focus value should fail");
+}
+
+/// Test DECXCPR (CSI ? 6 n) reports the cursor position in DEC form
+#[test]
+fn test_decdsr_cursor_position() {
+    let env = TestEnv::new();
+    let daemon = DaemonHandle::spawn_script(&env.socket(), r#"
+        stty -echo
+        printf '\033[3;5H\033[?6n'
+        # Reply is ESC [ ? row ; col R
+        if read -r -t 1 -d 'R' response; then
+            echo "GOT:${response#*[}"
+        else
+            echo "NO_RESPONSE"
+        fi
+        sleep 5
+    "#);
+
+    let output = daemon.get_output();
+    assert!(output.contains("GOT:?3;5"),
+        "Should receive DECXCPR reply. Got: {}", output);
+
+    daemon.stop();
+}
+
+/// Test DECDSR printer status (CSI ? 15 n) reports no printer
+#[test]
+fn test_decdsr_printer_status() {
+    let env = TestEnv::new();
+    let daemon = DaemonHandle::spawn_script(&env.socket(), r#"
+        stty -echo
+        printf '\033[?15n'
+        if read -r -t 1 -d 'n' response; then
+            echo "GOT:${response#*[}"
+        else
+            echo "NO_RESPONSE"
+        fi
+        sleep 5
+    "#);
+
+    let output = daemon.get_output();
+    assert!(output.contains("GOT:?13"),
+        "Should receive no-printer reply. Got: {}", output);
+
+    daemon.stop();
+}
+
+/// Test XTWINOPS size reports: CSI 18 t (text area in characters), 14 t (in
+/// pixels, assuming 8x16 cells) and 19 t (screen in characters)
+#[test]
+fn test_xtwinops_size_reports() {
+    let env = TestEnv::new();
+    let daemon = DaemonHandle::spawn_script(&env.socket(), r#"
+        stty -echo
+        for op in 18 14 19; do
+            printf "\033[${op}t"
+            # Reply is ESC [ kind ; height ; width t
+            if read -r -t 1 -d 't' response; then
+                echo "GOT$op:${response#*[}"
+            else
+                echo "NO_RESPONSE$op"
+            fi
+        done
+        sleep 5
+    "#);
+
+    let output = daemon.get_output();
+    assert!(output.contains("GOT18:8;24;80"), "Should report 24x80 characters. Got: {}", output);
+    assert!(output.contains("GOT14:4;384;640"), "Should report 384x640 pixels. Got: {}", output);
+    assert!(output.contains("GOT19:9;24;80"), "Should report the screen size. Got: {}", output);
+
+    daemon.stop();
+}
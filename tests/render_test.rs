@@ -0,0 +1,448 @@
+// Escape-sequence tests on the pure render path: no PTY, no daemon, no sleeps.
+// Each test runs against both backends.
+
+use interminai::render::{feed, new_screen, render, Emulator};
+
+const EMULATORS: [Emulator; 2] = [Emulator::Xterm, Emulator::Custom];
+
+/// Feed `bytes` to a blank `rows` x `cols` screen of each backend and return
+/// (emulator, rendered screen) pairs
+fn render_each(rows: usize, cols: usize, bytes: &[u8]) -> Vec<(Emulator, String)> {
+    EMULATORS.iter().map(|&emulator| (emulator, render_one(emulator, rows, cols, bytes))).collect()
+}
+
+/// Feed `bytes` to a blank `rows` x `cols` screen of `emulator` and render it
+fn render_one(emulator: Emulator, rows: usize, cols: usize, bytes: &[u8]) -> String {
+    let mut screen = new_screen(emulator, rows, cols);
+    feed(screen.as_mut(), bytes);
+    render(screen.as_ref())
+}
+
+fn first_line(screen: &str) -> &str {
+    screen.lines().next().unwrap_or("")
+}
+
+/// Test CSI G - horizontal position absolute (hpa)
+#[test]
+fn test_csi_hpa_horizontal_position() {
+    for (emulator, screen) in render_each(10, 40, b"\x1b[10GX") {
+        assert_eq!(first_line(&screen), "         X", "{:?}: X should be at column 10", emulator);
+    }
+}
+
+/// Test CSI ` - horizontal position absolute, clamped to the width
+#[test]
+fn test_csi_backtick_horizontal_position() {
+    for (emulator, screen) in render_each(10, 40, b"\x1b[10`X") {
+        assert_eq!(first_line(&screen), "         X", "{:?}: X should be at column 10", emulator);
+    }
+    for (emulator, screen) in render_each(10, 20, b"\x1b[99`X") {
+        assert_eq!(first_line(&screen), format!("{}X", " ".repeat(19)), "{:?}: X should be in the last column", emulator);
+    }
+}
+
+/// Test CSI a - horizontal position relative, clamped to the width
+#[test]
+fn test_csi_hpr_horizontal_position_relative() {
+    for (emulator, screen) in render_each(10, 40, b"AB\x1b[3aX\x1b[aY") {
+        assert_eq!(first_line(&screen), "AB   X Y", "{:?}: CSI a should move right", emulator);
+    }
+    for (emulator, screen) in render_each(10, 20, b"A\x1b[99aX") {
+        assert_eq!(first_line(&screen), format!("A{}X", " ".repeat(18)), "{:?}: X should be in the last column", emulator);
+    }
+}
+
+/// Test CSI d - vertical position absolute (vpa)
+#[test]
+fn test_csi_vpa_vertical_position() {
+    for (emulator, screen) in render_each(10, 40, b"\x1b[3dY") {
+        let lines: Vec<&str> = screen.lines().collect();
+        assert_eq!(lines.get(2).copied(), Some("Y"), "{:?}: Y should be at row 3. Lines: {:?}", emulator, lines);
+    }
+}
+
+/// Test CSI e - vertical position relative, clamped to the bottom row
+#[test]
+fn test_csi_vpr_vertical_position_relative() {
+    for (emulator, screen) in render_each(10, 40, b"A\r\x1b[2eB\r\x1b[eC") {
+        let lines: Vec<&str> = screen.lines().collect();
+        assert_eq!(lines[..5], ["A", "", "B", "C", ""], "{:?}: CSI e should move down", emulator);
+    }
+    for (emulator, screen) in render_each(10, 40, b"\x1b[99eZ") {
+        let lines: Vec<&str> = screen.lines().collect();
+        assert_eq!(lines.len(), 10, "{:?}: the screen should not scroll", emulator);
+        assert_eq!(lines[9], "Z", "{:?}: Z should be on the last row", emulator);
+    }
+}
+
+/// Test CSI X - erase character (ech)
+#[test]
+fn test_csi_ech_erase_character() {
+    for (emulator, screen) in render_each(10, 40, b"ABCDEFGH\x1b[3G\x1b[3X") {
+        assert_eq!(first_line(&screen), "AB   FGH", "{:?}: columns 3-5 should be erased", emulator);
+    }
+}
+
+/// Test CSI P - delete character (dch)
+#[test]
+fn test_csi_dch_delete_character() {
+    for (emulator, screen) in render_each(10, 40, b"ABCDEFGH\x1b[3G\x1b[2P") {
+        assert_eq!(first_line(&screen), "ABEFGH", "{:?}: CD should be deleted", emulator);
+    }
+}
+
+/// Test CSI @ - insert character (ich)
+#[test]
+fn test_csi_ich_insert_character() {
+    for (emulator, screen) in render_each(10, 40, b"ABCDEF\x1b[3G\x1b[2@") {
+        assert_eq!(first_line(&screen), "AB  CDEF", "{:?}: 2 blanks should be inserted at column 3", emulator);
+    }
+}
+
+/// Test CSI E - cursor next line (cnl): down N rows, to column 1
+#[test]
+fn test_csi_cnl_cursor_next_line() {
+    for (emulator, screen) in render_each(10, 40, b"\x1b[2;10H\x1b[2EX") {
+        let lines: Vec<&str> = screen.lines().collect();
+        assert_eq!(lines[3], "X", "{:?}: X should be at row 4, column 1", emulator);
+    }
+}
+
+/// Test CSI F - cursor previous line (cpl): up N rows, to column 1
+#[test]
+fn test_csi_cpl_cursor_previous_line() {
+    // Default count is 1
+    for (emulator, screen) in render_each(10, 40, b"\x1b[5;10H\x1b[3FX\x1b[FY") {
+        let lines: Vec<&str> = screen.lines().collect();
+        assert_eq!(lines[..2], ["Y", "X"], "{:?}: X should be at row 2 and Y at row 1, column 1", emulator);
+    }
+}
+
+/// Test CSI E/F stop at the screen edges
+#[test]
+fn test_csi_cnl_cpl_clamped() {
+    for (emulator, screen) in render_each(5, 40, b"\x1b[3;10H\x1b[99EB\x1b[99FT") {
+        let lines: Vec<&str> = screen.lines().collect();
+        assert_eq!(lines[0], "T", "{:?}: T should be at the top-left", emulator);
+        assert_eq!(lines[4], "B", "{:?}: B should be at the bottom-left", emulator);
+    }
+}
+
+/// Test CSI s / CSI u - save and restore cursor position (scosc/scorc)
+#[test]
+fn test_csi_save_restore_cursor() {
+    for (emulator, screen) in render_each(10, 40, b"\x1b[2;5H\x1b[s\x1b[6;1Hother\x1b[uX") {
+        let lines: Vec<&str> = screen.lines().collect();
+        assert_eq!(lines[1], "    X", "{:?}: X should be at the saved position, row 2 col 5", emulator);
+        assert_eq!(lines[5], "other", "{:?}", emulator);
+    }
+}
+
+/// Test CSI H with only a row parameter - column defaults to 1
+#[test]
+fn test_csi_cup_row_only() {
+    // Move right first so a missing column must actually reset it
+    for (emulator, screen) in render_each(10, 40, b"\x1b[1;10H\x1b[5HX") {
+        let lines: Vec<&str> = screen.lines().collect();
+        assert_eq!(lines[4], "X", "{:?}: X should be at row 5, col 1", emulator);
+    }
+}
+
+/// Test CSI H with an empty row parameter - row defaults to 1
+#[test]
+fn test_csi_cup_column_only() {
+    for (emulator, screen) in render_each(10, 40, b"\x1b[3;3H\x1b[;5HX") {
+        assert_eq!(first_line(&screen), "    X", "{:?}: X should be at row 1, col 5", emulator);
+    }
+}
+
+/// Test bare CSI H - cursor home
+#[test]
+fn test_csi_cup_home() {
+    for (emulator, screen) in render_each(10, 40, b"ABC\x1b[4;4H\x1b[HZ") {
+        assert_eq!(first_line(&screen), "ZBC", "{:?}: Z should overwrite A at home position", emulator);
+    }
+}
+
+/// Test CSI J mode 1 - erase from start of display through the cursor (ed1)
+#[test]
+fn test_csi_ed1_erase_to_beginning_of_display() {
+    for (emulator, screen) in render_each(10, 40, b"AAAAA\r\nBBBBB\r\nCCCCC\r\nDDDDD\r\nEEEEE\x1b[3;3H\x1b[1J") {
+        let lines: Vec<&str> = screen.lines().collect();
+        assert_eq!(lines[..5], ["", "", "   CC", "DDDDD", "EEEEE"], "{:?}: rows 1-2 and row 3 through the cursor should be erased", emulator);
+    }
+}
+
+/// Test CSI K mode 1 - erase from beginning of line to cursor (el1)
+#[test]
+fn test_csi_el1_erase_to_beginning() {
+    for (emulator, screen) in render_each(10, 40, b"ABCDEFGH\x1b[5G\x1b[1K") {
+        assert_eq!(first_line(&screen), "     FGH", "{:?}: ABCDE should be erased", emulator);
+    }
+}
+
+/// Test CSI @ at the last column blanks only that cell
+#[test]
+fn test_csi_ich_at_last_column() {
+    for (emulator, screen) in render_each(5, 10, b"ABCDEFGHIJ\x1b[1;10H\x1b[3@\x1b[2;1HNEXT") {
+        let lines: Vec<&str> = screen.lines().collect();
+        assert_eq!(lines[..2], ["ABCDEFGHI", "NEXT"], "{:?}: J should be pushed off without wrapping", emulator);
+    }
+}
+
+/// Test CSI @ with n larger than the rest of the line blanks to the margin
+#[test]
+fn test_csi_ich_count_past_line_end() {
+    for (emulator, screen) in render_each(5, 10, b"ABCDEFGHIJ\x1b[4G\x1b[500@X") {
+        assert_eq!(first_line(&screen), "ABCX", "{:?}", emulator);
+    }
+}
+
+/// Test CSI P at the last column deletes only that character
+#[test]
+fn test_csi_dch_at_last_column() {
+    // alacritty_terminal blanks `n` cells at the end of the line even when
+    // fewer than `n` remain after the cursor
+    let screen = render_one(Emulator::Custom, 5, 10, b"ABCDEFGHIJ\x1b[1;10H\x1b[3PZ");
+    assert_eq!(first_line(&screen), "ABCDEFGHIZ", "J should be deleted and Z printed in its place");
+}
+
+/// Test CSI P with n larger than the rest of the line blanks to the margin
+#[test]
+fn test_csi_dch_count_past_line_end() {
+    // alacritty_terminal blanks `n` cells at the end of the line even when
+    // fewer than `n` remain after the cursor
+    let screen = render_one(Emulator::Custom, 5, 10, b"ABCDEFGHIJ\x1b[4G\x1b[500P\x1b[2;1HNEXT");
+    let lines: Vec<&str> = screen.lines().collect();
+    assert_eq!(lines[..2], ["ABC", "NEXT"], "The next row should be untouched");
+}
+
+/// Test CSI S - scroll up
+#[test]
+fn test_csi_scroll_up() {
+    for (emulator, screen) in render_each(10, 40, b"LINE1\r\nLINE2\r\nLINE3\x1b[2S") {
+        assert_eq!(first_line(&screen), "LINE3", "{:?}: LINE1 and LINE2 should be scrolled off", emulator);
+    }
+}
+
+/// Test CSI T - scroll down
+#[test]
+fn test_csi_scroll_down() {
+    for (emulator, screen) in render_each(10, 40, b"TOPLINE\x1b[2T") {
+        let lines: Vec<&str> = screen.lines().collect();
+        assert_eq!(lines[..3], ["", "", "TOPLINE"], "{:?}: TOPLINE should be at row 3", emulator);
+    }
+}
+
+/// Column of the X printed after `bytes` on the first row of a `cols` wide screen
+fn x_column(emulator: Emulator, cols: usize, bytes: &[u8]) -> Option<usize> {
+    first_line(&render_one(emulator, 5, cols, bytes)).find('X')
+}
+
+/// Test CSI I - cursor horizontal tab forward (cht) and CSI Z - back tab (cbt)
+#[test]
+fn test_csi_horizontal_tab() {
+    // Tab stops: 1, 9, 17, 25... (1-based) = 0, 8, 16, 24... (0-indexed)
+    for emulator in EMULATORS {
+        assert_eq!(x_column(emulator, 40, b"\x1b[3G\x1b[IX"), Some(8), "{:?}: one tab from col 3", emulator);
+        assert_eq!(x_column(emulator, 40, b"\x1b[1G\x1b[2IX"), Some(16), "{:?}: two tabs from col 1", emulator);
+        assert_eq!(x_column(emulator, 40, b"\x1b[20G\x1b[ZX"), Some(16), "{:?}: back tab from col 20", emulator);
+    }
+}
+
+/// Test that a tab past the last stop lands exactly on the right margin
+#[test]
+fn test_tab_past_last_stop_lands_on_margin() {
+    for emulator in EMULATORS {
+        // Col 19 of 20 (index 18, cols-2): the next stop (index 24) is past the margin
+        assert_eq!(x_column(emulator, 20, b"\x1b[19G\tX"), Some(19), "{:?}", emulator);
+        assert_eq!(x_column(emulator, 20, b"\x1b[19G\x1b[IX"), Some(19), "{:?}", emulator);
+        // Already on the margin: tab stays there
+        assert_eq!(x_column(emulator, 20, b"\x1b[20G\tX"), Some(19), "{:?}", emulator);
+    }
+}
+
+/// Test HTS (ESC H) and TBC (CSI g) - tab stops set and cleared by the program
+#[test]
+fn test_tab_stops_set_and_cleared() {
+    for emulator in EMULATORS {
+        // Clear all stops, set one at col 5 (index 4): tab from col 1 goes there
+        assert_eq!(x_column(emulator, 40, b"\x1b[3g\x1b[5G\x1bH\x1b[1G\tX"), Some(4), "{:?}", emulator);
+        // No stops at all: tab goes to the right margin
+        assert_eq!(x_column(emulator, 40, b"\x1b[3g\x1b[1G\tX"), Some(39), "{:?}", emulator);
+        // Clear only the stop at col 9 (index 8): tab from col 1 skips to index 16
+        assert_eq!(x_column(emulator, 40, b"\x1b[9G\x1b[0g\x1b[1G\tX"), Some(16), "{:?}", emulator);
+        // Back tab uses the same stops
+        assert_eq!(x_column(emulator, 40, b"\x1b[3g\x1b[5G\x1bH\x1b[20G\x1b[ZX"), Some(4), "{:?}", emulator);
+    }
+}
+
+/// Test Unicode support
+#[test]
+fn test_unicode_support() {
+    for (emulator, screen) in render_each(10, 40, "日本語 ├── test".as_bytes()) {
+        assert_eq!(first_line(&screen), "日本語 ├── test", "{:?}", emulator);
+    }
+}
+
+/// OSC 8 hyperlinks are reported with the text they cover and move with scrolling
+#[test]
+fn test_osc8_hyperlinks() {
+    let bytes = b"a \x1b]8;id=x;https://example.com/a;b\x1b\\link\x1b]8;;\x1b\\ b\r\n\x1b]8;;https://example.com/c\x07two\x1b]8;;\x07\r\nthree";
+    for emulator in EMULATORS {
+        let mut screen = new_screen(emulator, 3, 20);
+        feed(screen.as_mut(), bytes);
+        assert_eq!(first_line(&render(screen.as_ref())), "a link b", "{:?}: the URL shouldn't be printed", emulator);
+        let links: Vec<(String, String, usize, usize)> = screen.hyperlinks().into_iter()
+            .map(|link| (link.url, link.text, link.row, link.col))
+            .collect();
+        assert_eq!(links, [
+            ("https://example.com/a;b".to_string(), "link".to_string(), 0, 2),
+            ("https://example.com/c".to_string(), "two".to_string(), 1, 0),
+        ], "{:?}", emulator);
+
+        // Scroll the first link off the screen
+        feed(screen.as_mut(), b"\r\nfour");
+        let links: Vec<(String, usize)> = screen.hyperlinks().into_iter().map(|link| (link.text, link.row)).collect();
+        assert_eq!(links, [("two".to_string(), 0)], "{:?}", emulator);
+
+        // Overwriting the text drops the link
+        feed(screen.as_mut(), b"\x1b[1;1Hxxx");
+        assert!(screen.hyperlinks().is_empty(), "{:?}", emulator);
+    }
+}
+
+/// Test CSI b - repeat the last character (rep)
+#[test]
+fn test_csi_repeat_character() {
+    for (emulator, screen) in render_each(10, 40, b"A\x1b[5b") {
+        assert_eq!(first_line(&screen), "AAAAAA", "{:?}: 1 original + 5 repeats", emulator);
+    }
+}
+
+/// Test repeat after SGR (escape shouldn't reset the last character)
+#[test]
+fn test_csi_repeat_after_sgr() {
+    for (emulator, screen) in render_each(10, 40, b"X\x1b[1m\x1b[3b") {
+        assert_eq!(first_line(&screen), "XXXX", "{:?}", emulator);
+    }
+}
+
+/// Test repeat right after a cursor move repeats at the new position
+#[test]
+fn test_csi_repeat_after_cursor_move() {
+    for (emulator, screen) in render_each(10, 40, b"A\x1b[3;5H\x1b[3b") {
+        let lines: Vec<&str> = screen.lines().collect();
+        assert_eq!(lines[..3], ["A", "", "    AAA"], "{:?}: row 3 should have 3 A's from col 5", emulator);
+    }
+}
+
+/// Test repeat at the start of a session is a no-op (nothing to repeat)
+#[test]
+fn test_csi_repeat_at_session_start() {
+    for (emulator, screen) in render_each(10, 40, b"\x1b[5bZ") {
+        assert_eq!(first_line(&screen), "Z", "{:?}: repeat should print nothing", emulator);
+    }
+}
+
+/// Test repeat after a control character is a no-op
+#[test]
+fn test_csi_repeat_after_control_character() {
+    // alacritty_terminal has its own REP handling
+    let screen = render_one(Emulator::Custom, 10, 40, b"A\r\n\x1b[3bB");
+    let lines: Vec<&str> = screen.lines().collect();
+    assert_eq!(lines[1], "B", "Nothing should be repeated after CR LF");
+}
+
+/// Test repeat count is capped to the columns left on the line
+#[test]
+fn test_csi_repeat_capped_at_line_end() {
+    // alacritty_terminal has its own REP handling
+    let screen = render_one(Emulator::Custom, 10, 40, b"\x1b[38GA\x1b[100b");
+    let lines: Vec<&str> = screen.lines().collect();
+    assert_eq!(lines[..2], [format!("{}AAA", " ".repeat(37)).as_str(), ""], "Repeat should fill row 1 without wrapping");
+}
+
+/// A read that stops inside an escape sequence is reported as such
+#[test]
+fn test_parser_in_escape_across_chunks() {
+    let chunks: [(&[u8], bool); 9] = [
+        (b"A\x1b", true),
+        (b"[3", true),
+        (b"1mB", false),
+        (b"\x1b]0;title", true),
+        (b"\x07C", false),
+        (b"\x1bP$q", true),
+        (b"m\x1b", true),
+        (b"\\D", false),
+        (b"\x1b[12\x18E", false),
+    ];
+    for emulator in EMULATORS {
+        let mut screen = new_screen(emulator, 3, 20);
+        for (chunk, in_escape) in chunks {
+            feed(screen.as_mut(), chunk);
+            assert_eq!(screen.parser_in_escape(), in_escape, "{:?}: after {:?}", emulator, String::from_utf8_lossy(chunk));
+        }
+        assert_eq!(first_line(&render(screen.as_ref())), "ABCDE", "{:?}", emulator);
+    }
+}
+
+/// DECSCUSR (CSI Ps SP q) sets the reported cursor style
+#[test]
+fn test_decscusr_cursor_style() {
+    for emulator in EMULATORS {
+        let mut screen = new_screen(emulator, 3, 20);
+        assert_eq!(screen.cursor_style().to_string(), "steady block", "{:?}", emulator);
+        for (bytes, style) in [
+            (&b"\x1b[4 q"[..], "steady underline"),
+            (b"\x1b[5 q", "blinking bar"),
+            (b"\x1b[1 q", "blinking block"),
+            (b"\x1b[0 q", "steady block"),
+        ] {
+            feed(screen.as_mut(), bytes);
+            assert_eq!(screen.cursor_style().to_string(), style, "{:?}: after {:?}", emulator, String::from_utf8_lossy(bytes));
+        }
+    }
+}
+
+/// Test SO / SI switching between ASCII in G0 and line drawing in G1
+#[test]
+fn test_so_si_toggle_line_drawing() {
+    for (emulator, screen) in render_each(4, 40, b"\x1b)0\x1b(Ba\x0elqqk\x0fb\r\n\x0ex\x0fq\x0ex\r\n\x0emqqj") {
+        let lines: Vec<&str> = screen.lines().collect();
+        assert_eq!(lines[..3], ["a┌──┐b", "│q│", "└──┘"], "{:?}", emulator);
+    }
+}
+
+/// Test ESC ( 0 / ESC ( B designating into G0, which stays invoked
+#[test]
+fn test_g0_designation() {
+    for (emulator, screen) in render_each(4, 40, b"\x1b(0tqu\x1b(Btqu") {
+        assert_eq!(first_line(&screen), "├─┤tqu", "{:?}", emulator);
+    }
+}
+
+/// Test SS2 / SS3 taking one character from G2 / G3
+#[test]
+fn test_single_shifts() {
+    for (emulator, screen) in render_each(4, 40, b"\x1b*0\x1b+B\x1bNqq\x1bOq\x1b)0\x0e\x1bOqq") {
+        assert_eq!(first_line(&screen), "─qqq─", "{:?}", emulator);
+    }
+}
+
+/// Test RIS (ESC c) going back to ASCII in G0, also after SO and single shifts
+#[test]
+fn test_ris_resets_character_sets() {
+    for (emulator, screen) in render_each(4, 40, b"\x1b)0\x0e\x1bcq\x1b)0\x1b*0\x1bNqq") {
+        assert_eq!(first_line(&screen), "q─q", "{:?}", emulator);
+    }
+}
+
+/// Test DECSC / DECRC (ESC 7 / ESC 8) saving and restoring the character sets
+#[test]
+fn test_decsc_decrc_restore_character_sets() {
+    for (emulator, screen) in render_each(4, 40, b"ab\x1b(0\x1b7\x1b(B\r\nq\x1b8q") {
+        let lines: Vec<&str> = screen.lines().collect();
+        assert_eq!(lines[..2], ["ab─", "q"], "{:?}", emulator);
+    }
+}
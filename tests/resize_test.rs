@@ -5,7 +5,7 @@ use std::time::Duration;
 use tempfile::TempDir;
 
 mod common;
-use common::{interminai_bin, emulator_args};
+use common::{interminai_bin, emulator, emulator_args};
 
 struct TestEnv {
     _temp_dir: TempDir,
@@ -324,6 +324,49 @@ fn test_multiple_resizes() {
     daemon.stop();
 }
 
+#[test]
+fn test_resize_verbose_reports_old_and_new_size() {
+    let env = TestEnv::new();
+
+    let daemon = DaemonHandle::spawn_with_socket_and_size(
+        &env.socket(),
+        "80x24",
+        &["cat"]
+    );
+
+    thread::sleep(Duration::from_millis(300));
+
+    let output = Command::new(interminai_bin())
+        .arg("resize")
+        .arg("--socket")
+        .arg(env.socket())
+        .arg("--size")
+        .arg("100x30")
+        .arg("--verbose")
+        .timeout(Duration::from_secs(2))
+        .output()
+        .expect("Failed to resize");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Terminal resized from 80x24 to 100x30"),
+        "Should report old and new size, got: {}", stdout);
+
+    let output = Command::new(interminai_bin())
+        .arg("resize")
+        .arg("--socket")
+        .arg(env.socket())
+        .arg("--size")
+        .arg("60x20")
+        .arg("--verbose")
+        .timeout(Duration::from_secs(2))
+        .output()
+        .expect("Failed to resize");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Terminal resized from 100x30 to 60x20"),
+        "Old size should be the previous resize, got: {}", stdout);
+
+    daemon.stop();
+}
+
 #[test]
 fn test_resize_invalid_size() {
     let env = TestEnv::new();
@@ -340,7 +383,7 @@ fn test_resize_invalid_size() {
     let result = Command::new(interminai_bin())
         .arg("resize")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .arg("--size")
         .arg("invalid")
         .timeout(Duration::from_secs(2))
@@ -354,6 +397,60 @@ fn test_resize_invalid_size() {
     daemon.stop();
 }
 
+#[test]
+fn test_resize_rejects_huge_size_without_allow_large() {
+    let env = TestEnv::new();
+
+    let daemon = DaemonHandle::spawn_with_socket_and_size(
+        &env.socket(),
+        "80x24",
+        &["cat"]
+    );
+
+    thread::sleep(Duration::from_millis(300));
+
+    // Checked by the client for --size...
+    Command::new(interminai_bin())
+        .arg("resize")
+        .arg("--socket")
+        .arg(env.socket())
+        .arg("--size")
+        .arg("9999x9999")
+        .timeout(Duration::from_secs(2))
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("larger than 2000x1000"));
+
+    // ...and by the daemon for sizes it works out itself
+    Command::new(interminai_bin())
+        .arg("resize")
+        .arg("--socket")
+        .arg(env.socket())
+        .arg("--rows")
+        .arg("+5000")
+        .timeout(Duration::from_secs(2))
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("larger than 2000x1000"));
+
+    let output = Command::new(interminai_bin())
+        .arg("resize")
+        .arg("--socket")
+        .arg(env.socket())
+        .arg("--size")
+        .arg("2100x30")
+        .arg("--allow-large")
+        .arg("--verbose")
+        .timeout(Duration::from_secs(2))
+        .output()
+        .expect("Failed to resize");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Terminal resized from 80x24 to 2100x30"),
+        "--allow-large should accept the size, got: {}", stdout);
+
+    daemon.stop();
+}
+
 #[test]
 fn test_vim_reflow_after_resize() {
     let env = TestEnv::new();
@@ -395,3 +492,397 @@ fn test_vim_reflow_after_resize() {
 
     daemon.stop();
 }
+
+#[test]
+fn test_resize_auto_requires_terminal() {
+    let env = TestEnv::new();
+
+    let daemon = DaemonHandle::spawn_with_socket_and_size(
+        &env.socket(),
+        "80x24",
+        &["sleep", "10"]
+    );
+
+    // stdin and stdout are both redirected, so there is no terminal to follow
+    let output = Command::new(interminai_bin())
+        .arg("resize")
+        .arg("--socket")
+        .arg(env.socket())
+        .arg("--auto")
+        .write_stdin("")
+        .timeout(Duration::from_secs(2))
+        .output()
+        .expect("Failed to run resize");
+
+    assert!(!output.status.success(), "resize --auto without a terminal should fail");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("terminal"), "Error should mention the missing terminal. Got: {}", stderr);
+
+    daemon.stop();
+}
+
+#[test]
+fn test_resize_auto_conflicts_with_size() {
+    let output = Command::new(interminai_bin())
+        .arg("resize")
+        .arg("--socket")
+        .arg("/nonexistent/socket")
+        .arg("--auto")
+        .arg("--size")
+        .arg("100x30")
+        .timeout(Duration::from_secs(2))
+        .output()
+        .expect("Failed to run resize");
+
+    assert!(!output.status.success(), "--auto and --size should be mutually exclusive");
+}
+
+#[test]
+fn test_resize_auto_follows_caller_terminal() {
+    let env = TestEnv::new();
+    let caller_socket = env._temp_dir.path().join("caller.sock").to_string_lossy().to_string();
+
+    let target = DaemonHandle::spawn_with_socket_and_size(
+        &env.socket(),
+        "80x24",
+        &["sleep", "10"]
+    );
+
+    // Run `resize --auto` inside a 100x30 interminai session so it has a terminal to follow
+    let resize_cmd = format!("{} resize --socket {} --auto; sleep 10", interminai_bin(), env.socket());
+    let caller = DaemonHandle::spawn_with_socket_and_size(
+        &caller_socket,
+        "100x30",
+        &["bash", "-c", &resize_cmd]
+    );
+
+    thread::sleep(Duration::from_millis(1000));
+
+    let output = Command::new(interminai_bin())
+        .arg("status")
+        .arg("--socket")
+        .arg(env.socket())
+        .timeout(Duration::from_secs(2))
+        .output()
+        .expect("Failed to get status");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(stdout.contains("Size: 100x30"), "Target should follow caller's size. Got: {}", stdout);
+
+    caller.stop();
+    target.stop();
+}
+
+#[test]
+fn test_resize_delivers_sigwinch() {
+    let env = TestEnv::new();
+
+    // Print the size the child sees every time it gets SIGWINCH
+    let daemon = DaemonHandle::spawn_with_socket_and_size(
+        &env.socket(),
+        "80x24",
+        &["bash", "-c", "trap 'echo SIZE:$(stty size)' WINCH; while true; do sleep 0.1; done"]
+    );
+
+    thread::sleep(Duration::from_millis(500));
+
+    resize_terminal(&env.socket(), "100x30");
+    thread::sleep(Duration::from_millis(500));
+
+    let screen = get_screen(&env.socket());
+    assert!(screen.contains("SIZE:30 100"), "Child should see new size after SIGWINCH. Got:\n{}", screen);
+
+    daemon.stop();
+}
+
+fn resize_relative(socket: &str, flag: &str, value: &str) -> String {
+    let output = Command::new(interminai_bin())
+        .arg("resize")
+        .arg("--socket")
+        .arg(socket)
+        .arg(flag)
+        .arg(value)
+        .timeout(Duration::from_secs(2))
+        .output()
+        .expect("Failed to resize");
+    assert!(output.status.success(), "resize {} {} failed: {}",
+        flag, value, String::from_utf8_lossy(&output.stderr));
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+#[test]
+fn test_resize_rows_relative_grow() {
+    let env = TestEnv::new();
+
+    let daemon = DaemonHandle::spawn_with_socket_and_size(
+        &env.socket(),
+        "80x24",
+        &["sleep", "10"]
+    );
+
+    thread::sleep(Duration::from_millis(300));
+
+    // Columns are kept, rows grow by 10
+    let stdout = resize_relative(&env.socket(), "--rows", "+10");
+    assert!(stdout.contains("Terminal resized to 80x34"), "Got: {}", stdout);
+
+    daemon.stop();
+}
+
+#[test]
+fn test_resize_cols_relative_shrink() {
+    let env = TestEnv::new();
+
+    let daemon = DaemonHandle::spawn_with_socket_and_size(
+        &env.socket(),
+        "80x24",
+        &["sleep", "10"]
+    );
+
+    thread::sleep(Duration::from_millis(300));
+
+    let stdout = resize_relative(&env.socket(), "--cols", "-20");
+    assert!(stdout.contains("Terminal resized to 60x24"), "Got: {}", stdout);
+
+    // Shrinking far past zero is clamped instead of failing
+    let stdout = resize_relative(&env.socket(), "--cols", "-1000");
+    assert!(stdout.contains("Terminal resized to 10x24"), "Got: {}", stdout);
+
+    daemon.stop();
+}
+
+#[test]
+fn test_resize_rows_absolute_keeps_cols() {
+    let env = TestEnv::new();
+
+    let daemon = DaemonHandle::spawn_with_socket_and_size(
+        &env.socket(),
+        "100x30",
+        &["sleep", "10"]
+    );
+
+    thread::sleep(Duration::from_millis(300));
+
+    let stdout = resize_relative(&env.socket(), "--rows", "40");
+    assert!(stdout.contains("Terminal resized to 100x40"), "Got: {}", stdout);
+
+    daemon.stop();
+}
+
+#[test]
+fn test_random_resizes_while_flooding_output() {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::UnixStream;
+
+    let env = TestEnv::new();
+
+    let daemon = DaemonHandle::spawn_with_socket_and_size(
+        &env.socket(),
+        "80x24",
+        &["bash", "-c", "yes 'scrolling while the terminal is resized'"]
+    );
+
+    thread::sleep(Duration::from_millis(300));
+
+    // Fixed-seed LCG so failures are reproducible; sizes go down to 1x1
+    let mut seed: u32 = 2352;
+    let mut next = |max: u32| {
+        seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+        (seed >> 16) % max + 1
+    };
+    for _ in 0..60 {
+        let size = format!("{}x{}", next(200), next(60));
+        Command::new(interminai_bin())
+            .arg("resize")
+            .arg("--socket")
+            .arg(env.socket())
+            .arg("--size")
+            .arg(&size)
+            .timeout(Duration::from_secs(5))
+            .assert()
+            .success();
+    }
+
+    // A zero size sent directly over the protocol is refused, not applied
+    let mut stream = UnixStream::connect(env.socket()).expect("Failed to connect");
+    stream.write_all(b"{\"type\":\"RESIZE\",\"cols\":0,\"rows\":0}\n").unwrap();
+    let mut reply = String::new();
+    BufReader::new(&stream).read_line(&mut reply).unwrap();
+    assert!(reply.contains("\"error\""), "Zero size should be rejected. Got: {}", reply);
+
+    // Still responsive afterwards
+    resize_terminal(&env.socket(), "40x10");
+    let screen = get_screen(&env.socket());
+    assert!(screen.contains("scrolling"), "Screen should still render. Got: {}", screen);
+    assert!(screen.lines().count() <= 10, "Screen should be 10 rows. Got: {}", screen);
+
+    daemon.stop();
+}
+
+#[test]
+fn test_output_snapshot_consistent_while_flooding_and_resizing() {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::UnixStream;
+
+    let env = TestEnv::new();
+
+    let daemon = DaemonHandle::spawn_with_socket_and_size(
+        &env.socket(),
+        "80x24",
+        &["bash", "-c", "yes 'reading while the terminal is resized'"]
+    );
+
+    thread::sleep(Duration::from_millis(300));
+
+    let readers: Vec<_> = (0..4).map(|_| {
+        let socket = env.socket();
+        thread::spawn(move || {
+            let mut last_generation = 0;
+            for _ in 0..30 {
+                let mut stream = UnixStream::connect(&socket).expect("Failed to connect");
+                stream.write_all(b"{\"type\":\"OUTPUT\",\"format\":\"ascii\"}\n").unwrap();
+                let mut reply = String::new();
+                BufReader::new(&stream).read_line(&mut reply).unwrap();
+                let reply: serde_json::Value = serde_json::from_str(&reply).expect("Reply should be JSON");
+                let data = &reply["data"];
+
+                let rows = data["size"]["rows"].as_u64().unwrap();
+                let cols = data["size"]["cols"].as_u64().unwrap();
+                let screen = data["screen"].as_str().unwrap();
+                assert_eq!(screen.lines().count() as u64, rows, "Screen should match the size: {}", reply);
+                assert!(screen.lines().all(|l| l.chars().count() as u64 <= cols), "Rows should fit the width: {}", reply);
+                assert!(data["cursor"]["row"].as_u64().unwrap() < rows, "Cursor should be on screen: {}", reply);
+                assert!(data["cursor"]["col"].as_u64().unwrap() <= cols, "Cursor should be on screen: {}", reply);
+
+                let generation = data["generation"].as_u64().unwrap();
+                assert!(generation >= last_generation, "Generation went back from {} to {}", last_generation, generation);
+                last_generation = generation;
+            }
+        })
+    }).collect();
+
+    for size in ["40x10", "120x30", "20x5", "80x24"].iter().cycle().take(12) {
+        resize_terminal(&env.socket(), size);
+    }
+
+    for reader in readers {
+        reader.join().expect("Reader thread failed");
+    }
+
+    daemon.stop();
+}
+
+/// Resize with --reflow and return the "Reflow: ..." line
+fn resize_with_reflow(socket: &str, size: &str) -> String {
+    let output = Command::new(interminai_bin())
+        .arg("resize")
+        .arg("--socket")
+        .arg(socket)
+        .arg("--size")
+        .arg(size)
+        .arg("--reflow")
+        .timeout(Duration::from_secs(2))
+        .output()
+        .expect("Failed to resize");
+    assert!(output.status.success(), "stderr: {}", String::from_utf8_lossy(&output.stderr));
+    thread::sleep(Duration::from_millis(200));
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    stdout.lines().find(|l| l.starts_with("Reflow: ")).unwrap_or_else(|| panic!("No reflow line: {}", stdout)).to_string()
+}
+
+#[test]
+fn test_resize_reflow_keeps_wrapped_line_retrievable() {
+    if emulator() == "custom" {
+        return;
+    }
+    let env = TestEnv::new();
+    let long_line = "0".repeat(60);
+
+    let daemon = DaemonHandle::spawn_with_socket_and_size(
+        &env.socket(),
+        "80x5",
+        &["bash", "-c", "printf '%060d\\n' 0; seq 1 3; sleep 30"]
+    );
+    thread::sleep(Duration::from_millis(300));
+
+    // At 20 columns the line takes 3 rows, pushing the top rows into scrollback
+    let narrowed = resize_with_reflow(&env.socket(), "20x5");
+    assert_ne!(narrowed, "Reflow: 0 lines moved into scrollback", "Narrowing should push rows into scrollback");
+
+    let output = Command::new(interminai_bin())
+        .arg("output")
+        .arg("--socket")
+        .arg(env.socket())
+        .arg("--no-color")
+        .arg("--from")
+        .arg("-")
+        .timeout(Duration::from_secs(2))
+        .output()
+        .expect("Failed to get output");
+    let all = String::from_utf8_lossy(&output.stdout);
+    let zeros: usize = all.lines().filter(|l| l.trim_end().chars().all(|c| c == '0')).map(|l| l.trim_end().len()).sum();
+    assert_eq!(zeros, 60, "The wrapped line should be split over scrollback and screen: {:?}", all);
+
+    // Widening pulls the rows back and unwraps the line
+    let widened = resize_with_reflow(&env.socket(), "80x5");
+    assert!(widened.starts_with("Reflow: ") && widened.ends_with(" lines moved back from scrollback"),
+        "Widening should pull rows back: {}", widened);
+    let screen = get_screen(&env.socket());
+    assert!(screen.lines().any(|l| l.trim_end() == long_line), "The line should be unwrapped: {:?}", screen);
+
+    daemon.stop();
+}
+
+#[test]
+fn test_resize_reflow_ignores_height_change() {
+    if emulator() == "custom" {
+        return;
+    }
+    let env = TestEnv::new();
+    let daemon = DaemonHandle::spawn_with_socket_and_size(&env.socket(), "80x5", &["bash", "-c", "seq 1 10; sleep 30"]);
+    thread::sleep(Duration::from_millis(300));
+
+    // Shrinking the height scrolls rows off the top, but nothing was rewrapped
+    let reflowed = resize_with_reflow(&env.socket(), "80x3");
+    assert_eq!(reflowed, "Reflow: 0 lines moved into scrollback");
+
+    daemon.stop();
+}
+
+#[test]
+fn test_resize_reflow_with_full_scrollback() {
+    if emulator() == "custom" {
+        return;
+    }
+    let env = TestEnv::new();
+    let daemon = DaemonHandle::spawn_with_socket_and_size(&env.socket(), "80x5", &["bash", "-c", "seq 1 20000; sleep 30"]);
+    thread::sleep(Duration::from_millis(500));
+
+    // The default 10000-line scrollback is full, so dropped rows hide the count
+    let reflowed = resize_with_reflow(&env.socket(), "2x5");
+    assert_eq!(reflowed, "Reflow: scrollback is full, moved lines not counted");
+
+    daemon.stop();
+}
+
+#[test]
+fn test_resize_reflow_unsupported_by_custom() {
+    if emulator() != "custom" {
+        return;
+    }
+    let env = TestEnv::new();
+    let daemon = DaemonHandle::spawn_with_socket_and_size(&env.socket(), "80x5", &["sleep", "30"]);
+
+    Command::new(interminai_bin())
+        .arg("resize")
+        .arg("--socket")
+        .arg(env.socket())
+        .arg("--size")
+        .arg("40x5")
+        .arg("--reflow")
+        .timeout(Duration::from_secs(2))
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("Reflow is only supported with --emulator xterm"));
+
+    daemon.stop();
+}
@@ -28,6 +28,10 @@ struct DaemonHandle {
 
 impl DaemonHandle {
     fn spawn_with_socket_and_size(socket: &str, size: &str, args: &[&str]) -> Self {
+        Self::spawn_with_options(socket, size, &[], args)
+    }
+
+    fn spawn_with_options(socket: &str, size: &str, options: &[&str], args: &[&str]) -> Self {
         let mut cmd = std::process::Command::new(interminai_bin());
         cmd.arg("start")
             .args(emulator_args())
@@ -35,6 +39,7 @@ impl DaemonHandle {
             .arg(socket)
             .arg("--size")
             .arg(size)
+            .args(options)
             .arg("--");
 
         for arg in args {
@@ -229,7 +234,7 @@ fn test_vim_scrolls_to_show_last_lines() {
     Command::new(interminai_bin())
         .arg("input")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .write_stdin("G")
         .timeout(Duration::from_secs(2))
         .assert()
@@ -252,7 +257,7 @@ fn test_vim_scrolls_to_show_last_lines() {
     Command::new(interminai_bin())
         .arg("input")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .write_stdin(":q!\n")
         .timeout(Duration::from_secs(2))
         .assert()
@@ -290,7 +295,7 @@ fn test_vim_pagedown_causes_scroll() {
     Command::new(interminai_bin())
         .arg("input")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .write_stdin("\x06") // Ctrl+F
         .timeout(Duration::from_secs(2))
         .assert()
@@ -315,7 +320,7 @@ fn test_vim_pagedown_causes_scroll() {
     Command::new(interminai_bin())
         .arg("input")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .write_stdin(":q!\n")
         .timeout(Duration::from_secs(2))
         .assert()
@@ -417,7 +422,7 @@ fn test_scrollback_preserves_colors() {
     let output = Command::new(interminai_bin())
         .arg("output")
         .arg("--socket")
-        .arg(&env.socket())
+        .arg(env.socket())
         .arg("--from")
         .arg("-5")
         .arg("--to")
@@ -432,3 +437,159 @@ fn test_scrollback_preserves_colors() {
     assert!(stdout.contains("RED"), "Scrollback should contain RED text");
     assert!(stdout.contains("\x1b["), "Scrollback should preserve ANSI color codes");
 }
+
+#[test]
+fn test_max_output_bytes_bounds_history() {
+    let env = TestEnv::new();
+
+    // 20000 bytes: 10000 for unread raw output, the rest is 125 lines of 80 columns
+    let _daemon = DaemonHandle::spawn_with_options(
+        &env.socket(),
+        "80x5",
+        &["--max-output-bytes", "20000"],
+        &["bash", "-c", "seq 1 50000; sleep 10"]
+    );
+
+    thread::sleep(Duration::from_millis(2000));
+
+    let output = Command::new(interminai_bin())
+        .arg("status")
+        .arg("--socket")
+        .arg(env.socket())
+        .output()
+        .expect("Failed to get status");
+    let status = String::from_utf8_lossy(&output.stdout).to_string();
+    let scrollback_line = status.lines().find(|l| l.starts_with("Scrollback:"))
+        .unwrap_or_else(|| panic!("Status should report scrollback. Got: {}", status));
+    let available: usize = scrollback_line["Scrollback: ".len()..].split('/').next().unwrap()
+        .parse().expect("Failed to parse scrollback lines");
+    assert!(available <= 125, "Scrollback should be capped. Got: {}", scrollback_line);
+    assert!(scrollback_line.contains("lines dropped"), "Should count dropped lines. Got: {}", scrollback_line);
+
+    let output = Command::new(interminai_bin())
+        .arg("output")
+        .arg("--socket")
+        .arg(env.socket())
+        .arg("--format")
+        .arg("raw")
+        .output()
+        .expect("Failed to get raw output");
+    assert!(output.stdout.len() <= 10000, "Raw buffer should be capped, got {} bytes", output.stdout.len());
+
+    // The visible screen is always kept
+    let screen = get_screen(&env.socket());
+    assert!(screen.contains("50000"), "Screen should show the last line. Got: {}", screen);
+}
+
+/// Scrollback (available, capacity) from the status "Scrollback: a/b" line
+fn scrollback_status(socket: &str) -> (usize, usize) {
+    let output = Command::new(interminai_bin())
+        .arg("status")
+        .arg("--socket")
+        .arg(socket)
+        .output()
+        .expect("Failed to get status");
+    let status = String::from_utf8_lossy(&output.stdout).to_string();
+    let line = status.lines().find(|l| l.starts_with("Scrollback:"))
+        .unwrap_or_else(|| panic!("Status should report scrollback. Got: {}", status));
+    let counts = line["Scrollback: ".len()..].split_whitespace().next().unwrap();
+    let (available, capacity) = counts.split_once('/').unwrap();
+    (available.parse().unwrap(), capacity.parse().unwrap())
+}
+
+fn all_output(socket: &str) -> String {
+    let output = Command::new(interminai_bin())
+        .arg("output")
+        .arg("--socket")
+        .arg(socket)
+        .arg("--no-color")
+        .arg("--from")
+        .arg("-")
+        .timeout(Duration::from_secs(5))
+        .output()
+        .expect("Failed to get output");
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+#[test]
+fn test_scrollback_none_keeps_nothing() {
+    let env = TestEnv::new();
+
+    let _daemon = DaemonHandle::spawn_with_options(
+        &env.socket(),
+        "80x5",
+        &["--scrollback", "none"],
+        &["bash", "-c", "seq 1 50; sleep 10"]
+    );
+
+    thread::sleep(Duration::from_millis(800));
+
+    assert_eq!(scrollback_status(&env.socket()), (0, 0));
+    let all = all_output(&env.socket());
+    assert!(all.starts_with("47"), "Only the screen should be left. Got: {}", all);
+
+    let output = Command::new(interminai_bin())
+        .arg("info")
+        .arg("--socket")
+        .arg(env.socket())
+        .output()
+        .expect("Failed to get info");
+    let info = String::from_utf8_lossy(&output.stdout).to_string();
+    assert!(info.contains("Scrollback: none"), "Info should show the policy. Got: {}", info);
+}
+
+#[test]
+fn test_scrollback_lines_evicts_oldest() {
+    let env = TestEnv::new();
+
+    let _daemon = DaemonHandle::spawn_with_options(
+        &env.socket(),
+        "80x5",
+        &["--scrollback", "20"],
+        &["bash", "-c", "seq 1 50; sleep 10"]
+    );
+
+    thread::sleep(Duration::from_millis(800));
+
+    assert_eq!(scrollback_status(&env.socket()), (20, 20));
+    // 1-46 scrolled off the 5-line screen; the last 20 of them are kept
+    let all = all_output(&env.socket());
+    assert!(all.starts_with("27\n28\n"), "Oldest lines should be evicted first. Got: {}", all);
+}
+
+#[test]
+fn test_scrollback_unlimited_keeps_everything() {
+    let env = TestEnv::new();
+
+    let _daemon = DaemonHandle::spawn_with_options(
+        &env.socket(),
+        "80x5",
+        &["--scrollback", "unlimited"],
+        &["bash", "-c", "seq 1 20000; sleep 10"]
+    );
+
+    thread::sleep(Duration::from_millis(2000));
+
+    // More than the default 10000 lines
+    let (available, _) = scrollback_status(&env.socket());
+    assert_eq!(available, 19996);
+    assert!(all_output(&env.socket()).starts_with("1\n2\n"), "Nothing should be evicted");
+}
+
+#[test]
+fn test_scrollback_unlimited_respects_max_output_bytes() {
+    let env = TestEnv::new();
+
+    // As in test_max_output_bytes_bounds_history: room for 125 lines of 80 columns
+    let _daemon = DaemonHandle::spawn_with_options(
+        &env.socket(),
+        "80x5",
+        &["--scrollback", "unlimited", "--max-output-bytes", "20000"],
+        &["bash", "-c", "seq 1 20000; sleep 10"]
+    );
+
+    thread::sleep(Duration::from_millis(2000));
+
+    assert_eq!(scrollback_status(&env.socket()), (125, 125));
+    assert!(all_output(&env.socket()).starts_with("19872\n"), "Oldest lines should be evicted first");
+}
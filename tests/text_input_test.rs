@@ -29,6 +29,10 @@ struct DaemonHandle {
 
 impl DaemonHandle {
     fn spawn_with_socket(socket: &str, command_args: &[&str]) -> Self {
+        Self::spawn_with_options(socket, &[], command_args)
+    }
+
+    fn spawn_with_options(socket: &str, options: &[&str], command_args: &[&str]) -> Self {
         use std::process::Stdio;
         use std::io::BufRead;
 
@@ -37,6 +41,7 @@ impl DaemonHandle {
             .args(emulator_args())
             .arg("--socket")
             .arg(socket)
+            .args(options)
             .arg("--no-daemon")
             .arg("--");
 
@@ -166,6 +171,66 @@ fn test_text_flag_tab_escape() {
     assert!(screen.contains("b"), "Screen should contain 'b': {}", screen);
 }
 
+/// Send "hello" to cat with `input --text hello` plus `enter_args`; return the screen lines
+fn cat_lines_after_enter(enter_args: &[&str]) -> Vec<String> {
+    let env = TestEnv::new();
+    let daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["cat"]);
+    thread::sleep(Duration::from_millis(200));
+
+    Command::new(interminai_bin())
+        .arg("input")
+        .arg("--socket")
+        .arg(&daemon.socket_path)
+        .arg("--text")
+        .arg("hello")
+        .args(enter_args)
+        .assert()
+        .success();
+
+    thread::sleep(Duration::from_millis(200));
+
+    let output = Command::new(interminai_bin())
+        .arg("output")
+        .arg("--socket")
+        .arg(&daemon.socket_path)
+        .output()
+        .expect("Failed to get output");
+
+    String::from_utf8_lossy(&output.stdout).lines().map(|l| l.trim_end().to_string()).collect()
+}
+
+// Test --enter: the terminal echoes the line, then cat prints it on its own line
+#[test]
+fn test_enter_flag_submits_line() {
+    for enter_args in [&["--enter"][..], &["--enter", "cr"][..], &["--enter", "lf"][..]] {
+        let lines = cat_lines_after_enter(enter_args);
+        assert_eq!(lines.first().map(String::as_str), Some("hello"), "{:?}: echoed line: {:?}", enter_args, lines);
+        assert_eq!(lines.get(1).map(String::as_str), Some("hello"), "{:?}: cat's line: {:?}", enter_args, lines);
+    }
+}
+
+// Without --enter the line is still being edited, so cat prints nothing
+#[test]
+fn test_text_without_enter_is_not_submitted() {
+    let lines = cat_lines_after_enter(&[]);
+    assert_eq!(lines.first().map(String::as_str), Some("hello"));
+    assert!(lines.iter().skip(1).all(|l| l.is_empty()), "cat should not have printed yet: {:?}", lines);
+}
+
+#[test]
+fn test_enter_conflicts_with_key() {
+    Command::new(interminai_bin())
+        .arg("input")
+        .arg("--socket")
+        .arg("/nonexistent.sock")
+        .arg("--key")
+        .arg("up")
+        .arg("--enter")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("cannot be used with"));
+}
+
 // Test \e escape (ESC) - vim quit without save
 #[test]
 fn test_text_flag_escape_sequence() {
@@ -227,6 +292,97 @@ fn test_text_flag_hex_escape() {
     assert!(screen.contains("ABC"), "Screen should contain 'ABC': {}", screen);
 }
 
+// Test multibyte UTF-8 text round-trips intact
+#[test]
+fn test_text_flag_utf8() {
+    let env = TestEnv::new();
+    let daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["cat"]);
+    thread::sleep(Duration::from_millis(200));
+
+    Command::new(interminai_bin())
+        .arg("input")
+        .arg("--socket")
+        .arg(&daemon.socket_path)
+        .arg("--text")
+        .arg("héllo wörld ✓ 日本")
+        .assert()
+        .success();
+
+    thread::sleep(Duration::from_millis(200));
+
+    let output = Command::new(interminai_bin())
+        .arg("output")
+        .arg("--socket")
+        .arg(&daemon.socket_path)
+        .output()
+        .expect("Failed to get output");
+
+    let screen = String::from_utf8_lossy(&output.stdout);
+    assert!(screen.contains("héllo wörld ✓"), "Screen should contain UTF-8 text: {}", screen);
+    assert!(screen.contains("日"), "Screen should contain CJK text: {}", screen);
+}
+
+// Test \xHH escapes are raw bytes, so an escaped UTF-8 sequence decodes
+#[test]
+fn test_text_flag_hex_utf8_bytes() {
+    let env = TestEnv::new();
+    let daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["cat"]);
+    thread::sleep(Duration::from_millis(200));
+
+    // \xc3\xa9 is the UTF-8 encoding of 'é'
+    Command::new(interminai_bin())
+        .arg("input")
+        .arg("--socket")
+        .arg(&daemon.socket_path)
+        .arg("--text")
+        .arg("caf\\xc3\\xa9")
+        .assert()
+        .success();
+
+    thread::sleep(Duration::from_millis(200));
+
+    let output = Command::new(interminai_bin())
+        .arg("output")
+        .arg("--socket")
+        .arg(&daemon.socket_path)
+        .output()
+        .expect("Failed to get output");
+
+    let screen = String::from_utf8_lossy(&output.stdout);
+    assert!(screen.contains("café"), "Screen should contain 'café': {}", screen);
+}
+
+// Test non-UTF-8 bytes and NUL reach the PTY unchanged
+#[test]
+fn test_text_flag_binary_bytes() {
+    let env = TestEnv::new();
+    // Raw mode so the bytes are delivered without line editing; od shows them in hex
+    let daemon = DaemonHandle::spawn_with_socket(&env.socket(),
+        &["bash", "-c", "stty raw -echo; head -c 3 | od -An -tx1; sleep 5"]);
+    thread::sleep(Duration::from_millis(300));
+
+    Command::new(interminai_bin())
+        .arg("input")
+        .arg("--socket")
+        .arg(&daemon.socket_path)
+        .arg("--text")
+        .arg("\\x00\\xff\\x01")
+        .assert()
+        .success();
+
+    thread::sleep(Duration::from_millis(300));
+
+    let output = Command::new(interminai_bin())
+        .arg("output")
+        .arg("--socket")
+        .arg(&daemon.socket_path)
+        .output()
+        .expect("Failed to get output");
+
+    let screen = String::from_utf8_lossy(&output.stdout);
+    assert!(screen.contains("00 ff 01"), "PTY should receive exactly 00 ff 01: {}", screen);
+}
+
 // Test \\ escape (literal backslash)
 #[test]
 fn test_text_flag_backslash_escape() {
@@ -529,3 +685,609 @@ fn test_text_flag_takes_precedence_over_stdin() {
     let screen = String::from_utf8_lossy(&output.stdout);
     assert!(screen.contains("from_text_flag"), "Should use --text content: {}", screen);
 }
+
+// Test input larger than the PTY buffer arrives in full
+#[test]
+fn test_large_input_not_truncated() {
+    let env = TestEnv::new();
+    let out_file = env._temp_dir.path().join("received.txt");
+    let cmd = format!("cat > {}", out_file.display());
+    let daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["bash", "-c", &cmd]);
+    thread::sleep(Duration::from_millis(200));
+
+    // 64KB in 1024 lines of 64 bytes, well over the kernel's PTY buffer
+    let payload: String = (0..1024).map(|i| format!("{:063}\n", i)).collect();
+    assert_eq!(payload.len(), 65536);
+
+    Command::new(interminai_bin())
+        .arg("input")
+        .arg("--socket")
+        .arg(&daemon.socket_path)
+        .write_stdin(payload.clone())
+        .timeout(Duration::from_secs(20))
+        .assert()
+        .success();
+
+    thread::sleep(Duration::from_millis(500));
+
+    let received = std::fs::read_to_string(&out_file).expect("Failed to read received file");
+    assert_eq!(received.len(), payload.len(), "All input bytes should reach the child");
+    assert!(received == payload, "Input should arrive unchanged");
+}
+
+// Test input is held back, not dropped, while the program pauses reading
+#[test]
+fn test_input_waits_for_paused_reader() {
+    let env = TestEnv::new();
+    let out_file = env._temp_dir.path().join("received.txt");
+    let cmd = format!("stty raw -echo; sleep 2; cat > {}", out_file.display());
+    let daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["bash", "-c", &cmd]);
+    thread::sleep(Duration::from_millis(200));
+
+    // Far more than the PTY buffers while nothing reads
+    let payload: String = (0..4096).map(|i| format!("{:063}\n", i)).collect();
+
+    Command::new(interminai_bin())
+        .arg("input")
+        .arg("--socket")
+        .arg(&daemon.socket_path)
+        .write_stdin(payload.clone())
+        .timeout(Duration::from_secs(20))
+        .assert()
+        .success();
+
+    thread::sleep(Duration::from_millis(500));
+
+    let received = std::fs::read_to_string(&out_file).expect("Failed to read received file");
+    assert_eq!(received.len(), payload.len(), "All input bytes should reach the child");
+    assert!(received == payload, "Input should arrive unchanged");
+}
+
+// Test input to a program that never reads fails as busy, saying what was sent
+#[test]
+fn test_input_to_stalled_reader_reports_busy() {
+    let env = TestEnv::new();
+    let daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["bash", "-c", "stty raw -echo; sleep 30"]);
+    thread::sleep(Duration::from_millis(200));
+
+    let payload = "x".repeat(1 << 20);
+    let output = Command::new(interminai_bin())
+        .arg("input")
+        .arg("--socket")
+        .arg(&daemon.socket_path)
+        .arg("--timeout")
+        .arg("1")
+        .write_stdin(payload)
+        .timeout(Duration::from_secs(20))
+        .output()
+        .expect("Failed to run input");
+    assert!(!output.status.success(), "Input the program never reads must not succeed");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(stderr.contains("PTY busy"), "Should report the PTY as busy: {}", stderr);
+    assert!(stderr.contains(&format!("of {} bytes were written", 1 << 20)), "Should say how much was sent: {}", stderr);
+
+    // Still usable afterwards
+    Command::new(interminai_bin())
+        .arg("status")
+        .arg("--socket")
+        .arg(&daemon.socket_path)
+        .timeout(Duration::from_secs(5))
+        .assert()
+        .success();
+}
+
+fn wait_for_exit(socket: &str) -> String {
+    for _ in 0..20 {
+        let output = Command::new(interminai_bin())
+            .arg("status")
+            .arg("--socket")
+            .arg(socket)
+            .output()
+            .expect("Failed to get status");
+        let status = String::from_utf8_lossy(&output.stdout).to_string();
+        if status.contains("Running: false") {
+            return status;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    panic!("Process did not exit");
+}
+
+// Test --eof ends cat's input so it exits
+#[test]
+fn test_eof_flag_ends_cat() {
+    let env = TestEnv::new();
+    let daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["cat"]);
+
+    Command::new(interminai_bin())
+        .arg("input")
+        .arg("--socket")
+        .arg(&daemon.socket_path)
+        .arg("--eof")
+        .assert()
+        .success();
+
+    let status = wait_for_exit(&daemon.socket_path);
+    assert!(status.contains("Exit code: 0"), "cat should exit cleanly on EOF: {}", status);
+}
+
+// Test --eof sends the remapped VEOF rather than a hardcoded ^D
+#[test]
+fn test_eof_flag_uses_remapped_veof() {
+    let env = TestEnv::new();
+    let daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["bash", "-c", "stty eof ^B; cat"]);
+
+    // ^D is now an ordinary character for the line discipline
+    Command::new(interminai_bin())
+        .arg("input")
+        .arg("--socket")
+        .arg(&daemon.socket_path)
+        .arg("--ctrl")
+        .arg("C-d")
+        .assert()
+        .success();
+    thread::sleep(Duration::from_millis(300));
+
+    let output = Command::new(interminai_bin())
+        .arg("status")
+        .arg("--socket")
+        .arg(&daemon.socket_path)
+        .output()
+        .expect("Failed to get status");
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Running: true"),
+        "^D should not end input after stty eof ^B");
+
+    // The first EOF flushes the pending ^D to cat, the second ends its input
+    for _ in 0..2 {
+        Command::new(interminai_bin())
+            .arg("input")
+            .arg("--socket")
+            .arg(&daemon.socket_path)
+            .arg("--eof")
+            .assert()
+            .success();
+        thread::sleep(Duration::from_millis(100));
+    }
+
+    wait_for_exit(&daemon.socket_path);
+}
+
+// Test --ctrl C-c interrupts the foreground program
+#[test]
+fn test_ctrl_flag_interrupts() {
+    let env = TestEnv::new();
+    let daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["sleep", "30"]);
+
+    Command::new(interminai_bin())
+        .arg("input")
+        .arg("--socket")
+        .arg(&daemon.socket_path)
+        .arg("--ctrl")
+        .arg("C-c")
+        .assert()
+        .success();
+
+    let status = wait_for_exit(&daemon.socket_path);
+    assert!(status.contains("Exit code: 130"), "sleep should die from SIGINT: {}", status);
+}
+
+// Test --ctrl rejects names that are not control keys
+#[test]
+fn test_ctrl_flag_invalid_key() {
+    let env = TestEnv::new();
+    let daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["cat"]);
+
+    Command::new(interminai_bin())
+        .arg("input")
+        .arg("--socket")
+        .arg(&daemon.socket_path)
+        .arg("--ctrl")
+        .arg("ctrl-c")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("Invalid control key"));
+}
+
+fn received_key_bytes(setup: &str, key: &str) -> String {
+    received_input_bytes(setup, 3, &["--key", key])
+}
+
+/// Run `input ARGS` after the program printed `setup`, and return the screen
+/// showing the first `count` bytes the program received, in hex
+fn received_input_bytes(setup: &str, count: usize, args: &[&str]) -> String {
+    let env = TestEnv::new();
+    // Raw mode so the input bytes arrive unchanged; od shows them in hex
+    let cmd = format!("printf '{}'; stty raw -echo; head -c {} | od -An -tx1; sleep 5", setup, count);
+    let daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["bash", "-c", &cmd]);
+    thread::sleep(Duration::from_millis(300));
+
+    Command::new(interminai_bin())
+        .arg("input")
+        .arg("--socket")
+        .arg(&daemon.socket_path)
+        .args(args)
+        .assert()
+        .success();
+
+    thread::sleep(Duration::from_millis(300));
+
+    let output = Command::new(interminai_bin())
+        .arg("output")
+        .arg("--socket")
+        .arg(&daemon.socket_path)
+        .output()
+        .expect("Failed to get output");
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+// Test --key up sends CSI A in normal mode
+#[test]
+fn test_key_flag_up_normal_mode() {
+    let screen = received_key_bytes("", "up");
+    assert!(screen.contains("1b 5b 41"), "Up should be sent as \\e[A: {}", screen);
+}
+
+// Test --key up sends SS3 A once the app enables application keypad (ESC =)
+#[test]
+fn test_key_flag_up_application_keypad() {
+    let screen = received_key_bytes("\\033=", "up");
+    assert!(screen.contains("1b 4f 41"), "Up should be sent as \\eOA: {}", screen);
+}
+
+// Test ESC > returns the keypad to numeric mode
+#[test]
+fn test_key_flag_numeric_keypad_after_reset() {
+    let screen = received_key_bytes("\\033=\\033>", "up");
+    assert!(screen.contains("1b 5b 41"), "Up should be back to \\e[A: {}", screen);
+}
+
+// Test --key rejects unknown key names
+#[test]
+fn test_key_flag_unknown_key() {
+    let env = TestEnv::new();
+    let daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["cat"]);
+
+    Command::new(interminai_bin())
+        .arg("input")
+        .arg("--socket")
+        .arg(&daemon.socket_path)
+        .arg("--key")
+        .arg("sideways")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("Unknown key 'sideways'"));
+}
+
+// Test --mouse sends an SGR click once the app enables SGR mouse mode
+#[test]
+fn test_mouse_flag_sgr_click() {
+    let screen = received_input_bytes("\\033[?1000h\\033[?1006h", 10, &["--mouse", "left,5,10"]);
+    assert!(screen.contains("1b 5b 3c 30 3b 31 30 3b 35 4d"), "Click should be sent as \\e[<0;10;5M: {}", screen);
+}
+
+// Test --mouse uses the legacy encoding without SGR mode; a release has no button
+#[test]
+fn test_mouse_flag_legacy_release() {
+    let screen = received_input_bytes("\\033[?1000h", 6, &["--mouse", "left,5,10,release"]);
+    assert!(screen.contains("1b 5b 4d 23 2a 25"), "Release should be sent as \\e[M#*%: {}", screen);
+}
+
+// Test --mouse fails unless the app enabled mouse reporting
+#[test]
+fn test_mouse_flag_requires_mouse_mode() {
+    let env = TestEnv::new();
+    let daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["cat"]);
+    thread::sleep(Duration::from_millis(300));
+
+    Command::new(interminai_bin())
+        .arg("input")
+        .arg("--socket")
+        .arg(&daemon.socket_path)
+        .arg("--mouse")
+        .arg("left,1,1")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("has not enabled mouse reporting"));
+}
+
+// Test --echo shows input on screen for a program with echo off
+#[test]
+fn test_echo_flag_shows_input_with_echo_off() {
+    let env = TestEnv::new();
+    let out_file = env._temp_dir.path().join("received.txt");
+    let cmd = format!("stty -echo; cat > {}", out_file.display());
+    let daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["bash", "-c", &cmd]);
+    thread::sleep(Duration::from_millis(200));
+
+    let get_screen = || {
+        let output = Command::new(interminai_bin())
+            .arg("output")
+            .arg("--socket")
+            .arg(&daemon.socket_path)
+            .output()
+            .expect("Failed to get output");
+        String::from_utf8_lossy(&output.stdout).to_string()
+    };
+
+    Command::new(interminai_bin())
+        .arg("input")
+        .arg("--socket")
+        .arg(&daemon.socket_path)
+        .arg("--text")
+        .arg("hidden\\n")
+        .assert()
+        .success();
+    thread::sleep(Duration::from_millis(200));
+    assert!(!get_screen().contains("hidden"), "Echo is off, so plain input stays off screen");
+
+    Command::new(interminai_bin())
+        .arg("input")
+        .arg("--socket")
+        .arg(&daemon.socket_path)
+        .arg("--text")
+        .arg("shown\\n")
+        .arg("--echo")
+        .assert()
+        .success();
+    thread::sleep(Duration::from_millis(200));
+
+    let screen = get_screen();
+    assert!(screen.contains("shown"), "--echo should show the text: {}", screen);
+
+    // The program got exactly what was sent, nothing extra
+    let received = std::fs::read_to_string(&out_file).expect("Failed to read received file");
+    assert_eq!(received, "hidden\nshown\n");
+}
+
+// Test --echo is refused for passwords
+#[test]
+fn test_echo_flag_conflicts_with_password() {
+    let env = TestEnv::new();
+
+    Command::new(interminai_bin())
+        .arg("input")
+        .arg("--socket")
+        .arg(env.socket())
+        .arg("--password")
+        .arg("--echo")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("cannot be used with"));
+}
+
+#[test]
+fn test_input_not_starved_by_flooding_output() {
+    let env = TestEnv::new();
+    let socket = env.socket();
+    let got_file = env._temp_dir.path().join("got.txt");
+    let script = format!("yes & read line; echo \"$line\" > {}; kill $!", got_file.display());
+    let _daemon = DaemonHandle::spawn_with_options(
+        &socket,
+        &["--read-chunk-size", "512", "--read-limit", "8192"],
+        &["bash", "-c", &script],
+    );
+
+    // Each request has to get the lock between reads of the flood
+    let start = std::time::Instant::now();
+    for _ in 0..5 {
+        Command::new(interminai_bin())
+            .arg("output")
+            .arg("--socket")
+            .arg(&socket)
+            .arg("--no-color")
+            .timeout(Duration::from_secs(5))
+            .assert()
+            .success();
+    }
+    Command::new(interminai_bin())
+        .arg("input")
+        .arg("--socket")
+        .arg(&socket)
+        .arg("--text")
+        .arg("hello\n")
+        .timeout(Duration::from_secs(5))
+        .assert()
+        .success();
+    assert!(start.elapsed() < Duration::from_secs(5), "Requests were starved: took {:?}", start.elapsed());
+
+    let mut got = String::new();
+    for _ in 0..50 {
+        got = std::fs::read_to_string(&got_file).unwrap_or_default();
+        if got.ends_with('\n') {
+            break;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    assert_eq!(got, "hello\n", "Input should reach the program despite the flood");
+}
+
+// A pty can't carry a real break, but the request must succeed and leave
+// the session usable
+#[test]
+fn test_break_flag_keeps_session_responsive() {
+    let env = TestEnv::new();
+    let daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["cat"]);
+    thread::sleep(Duration::from_millis(200));
+
+    Command::new(interminai_bin())
+        .arg("input")
+        .arg("--socket")
+        .arg(&daemon.socket_path)
+        .arg("--break")
+        .timeout(Duration::from_secs(5))
+        .assert()
+        .success();
+
+    Command::new(interminai_bin())
+        .arg("input")
+        .arg("--socket")
+        .arg(&daemon.socket_path)
+        .arg("--text")
+        .arg("after-break")
+        .arg("--enter")
+        .assert()
+        .success();
+    thread::sleep(Duration::from_millis(200));
+
+    let output = Command::new(interminai_bin())
+        .arg("output")
+        .arg("--socket")
+        .arg(&daemon.socket_path)
+        .output()
+        .expect("Failed to get output");
+    let screen = String::from_utf8_lossy(&output.stdout);
+    assert_eq!(screen.matches("after-break").count(), 2, "cat should still echo input: {}", screen);
+}
+
+#[test]
+fn test_break_conflicts_with_text() {
+    Command::new(interminai_bin())
+        .arg("input")
+        .arg("--socket")
+        .arg("/nonexistent.sock")
+        .arg("--break")
+        .arg("--text")
+        .arg("x")
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("cannot be used with"));
+}
+
+fn raw_output_after_input(input_args: &[&str]) -> String {
+    let env = TestEnv::new();
+    let daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["bash", "-c", "echo stale-line; exec cat"]);
+    thread::sleep(Duration::from_millis(200));
+
+    Command::new(interminai_bin())
+        .arg("input")
+        .arg("--socket")
+        .arg(&daemon.socket_path)
+        .arg("--text")
+        .arg("fresh-line")
+        .arg("--enter")
+        .args(input_args)
+        .assert()
+        .success();
+    thread::sleep(Duration::from_millis(200));
+
+    let output = Command::new(interminai_bin())
+        .arg("output")
+        .arg("--socket")
+        .arg(&daemon.socket_path)
+        .arg("--format")
+        .arg("raw")
+        .output()
+        .expect("Failed to get output");
+    String::from_utf8_lossy(&output.stdout).to_string()
+}
+
+// Unread output from before the input would otherwise be reported with its reply
+#[test]
+fn test_drain_first_discards_earlier_output() {
+    let raw = raw_output_after_input(&[]);
+    assert!(raw.contains("stale-line") && raw.contains("fresh-line"), "Got: {:?}", raw);
+
+    let raw = raw_output_after_input(&["--drain-first"]);
+    assert!(!raw.contains("stale-line"), "Earlier output should be discarded: {:?}", raw);
+    assert!(raw.contains("fresh-line"), "The reply should be kept: {:?}", raw);
+}
+
+#[test]
+fn test_input_from_json_edits_file_in_vim() {
+    let env = TestEnv::new();
+    let test_file = env._temp_dir.path().join("edit.txt");
+    std::fs::write(&test_file, "first\n").expect("Failed to write file");
+    let daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["vim", "-u", "NONE", test_file.to_str().unwrap()]);
+    thread::sleep(Duration::from_millis(500));
+
+    let program = env._temp_dir.path().join("program.json");
+    std::fs::write(&program, r#"[
+        {"key": "Esc"},
+        {"text": "o"},
+        {"text": "second\tline"},
+        {"key": "Esc"},
+        {"sleep_ms": 100},
+        {"text": ":wq"},
+        {"key": "Enter"},
+        {"sleep_ms": 200}
+    ]"#).expect("Failed to write program");
+
+    Command::new(interminai_bin())
+        .arg("input")
+        .arg("--socket")
+        .arg(&daemon.socket_path)
+        .arg("--from-json")
+        .arg(program.to_str().unwrap())
+        .timeout(Duration::from_secs(5))
+        .assert()
+        .success();
+    thread::sleep(Duration::from_millis(300));
+
+    let content = std::fs::read_to_string(&test_file).expect("Failed to read file");
+    assert_eq!(content, "first\nsecond\tline\n");
+}
+
+#[test]
+fn test_input_from_json_reports_bad_step() {
+    let env = TestEnv::new();
+    let daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["cat"]);
+
+    Command::new(interminai_bin())
+        .arg("input")
+        .arg("--socket")
+        .arg(&daemon.socket_path)
+        .arg("--from-json")
+        .arg("-")
+        .write_stdin(r#"[{"text": "sent-too-early"}, {"key": "no-such-key"}]"#)
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("Step 2: Unknown key 'no-such-key'"));
+
+    // The program was rejected as a whole, before step 1 was sent
+    thread::sleep(Duration::from_millis(200));
+    let output = Command::new(interminai_bin())
+        .arg("output")
+        .arg("--socket")
+        .arg(&daemon.socket_path)
+        .arg("--no-color")
+        .output()
+        .expect("Failed to get output");
+    let screen = String::from_utf8_lossy(&output.stdout);
+    assert!(!screen.contains("sent-too-early"), "Nothing should be sent: {}", screen);
+
+    Command::new(interminai_bin())
+        .arg("input")
+        .arg("--socket")
+        .arg(&daemon.socket_path)
+        .arg("--from-json")
+        .arg("-")
+        .write_stdin(r#"[{"text": "a", "sleep_ms": 5}]"#)
+        .assert()
+        .failure()
+        .stderr(predicates::str::contains("Step 1: expected exactly one of"));
+}
+
+// Test an INPUT timeout too large for a Duration is rejected, not fatal to the daemon
+#[test]
+fn test_input_rejects_out_of_range_timeout() {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::UnixStream;
+
+    let env = TestEnv::new();
+    let daemon = DaemonHandle::spawn_with_socket(&env.socket(), &["sleep", "30"]);
+
+    let request = |request: serde_json::Value| -> serde_json::Value {
+        let mut stream = UnixStream::connect(&daemon.socket_path).expect("Failed to connect");
+        writeln!(stream, "{}", request).unwrap();
+        let mut line = String::new();
+        BufReader::new(stream).read_line(&mut line).unwrap();
+        serde_json::from_str(&line).expect("Invalid response")
+    };
+
+    let input = request(serde_json::json!({ "type": "INPUT", "data": "x", "timeout": 1e30 }));
+    assert_eq!(input["status"], "error", "Got: {}", input);
+    assert!(input["error"].as_str().unwrap().contains("'timeout' is too large"), "Got: {}", input);
+
+    let status = request(serde_json::json!({ "type": "STATUS" }));
+    assert_eq!(status["status"], "ok", "The daemon should still answer: {}", status);
+}
@@ -88,3 +88,207 @@ fn test_wait_interrupted_by_client_disconnect() {
         "Daemon should have stopped after kill"
     );
 }
+
+#[test]
+fn test_wait_exit_code_keepalive_waits_for_matching_code() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let socket_path = temp_dir.path().join("wait.sock");
+    let runs = temp_dir.path().join("runs");
+
+    // Run N exits with code N
+    let script = format!("echo run >> {0}; sleep 0.3; exit $(wc -l < {0})", runs.display());
+    Command::new(interminai_bin())
+        .arg("start")
+        .args(emulator_args())
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .arg("--keepalive")
+        .arg("--")
+        .arg("bash")
+        .arg("-c")
+        .arg(&script)
+        .assert()
+        .success();
+    thread::sleep(Duration::from_millis(200));
+
+    let output = Command::new(interminai_bin())
+        .arg("wait")
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .arg("--exit-code")
+        .arg("3")
+        .timeout(Duration::from_secs(10))
+        .output()
+        .expect("Failed to run wait command");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "Should succeed once a run exits with 3: {}", stdout);
+    assert!(stdout.contains("Exited with code 3"), "Got: {}", stdout);
+
+    let runs = std::fs::read_to_string(&runs).expect("Failed to read run log");
+    assert!(runs.lines().count() >= 3, "Runs 1 and 2 should not have matched. Got: {}", runs);
+
+    Command::new(interminai_bin())
+        .arg("stop")
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_wait_exit_code_reports_mismatch() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let socket_path = temp_dir.path().join("wait.sock");
+
+    Command::new(interminai_bin())
+        .arg("start")
+        .args(emulator_args())
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .arg("--")
+        .arg("bash")
+        .arg("-c")
+        .arg("sleep 0.3; exit 4")
+        .assert()
+        .success();
+    thread::sleep(Duration::from_millis(200));
+
+    let output = Command::new(interminai_bin())
+        .arg("wait")
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .arg("--exit-code")
+        .arg("0")
+        .timeout(Duration::from_secs(5))
+        .output()
+        .expect("Failed to run wait command");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(!output.status.success(), "A different exit code should fail: {}", stdout);
+    assert!(stdout.contains("Exited with code 4, not 0"), "Got: {}", stdout);
+
+    Command::new(interminai_bin())
+        .arg("stop")
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_wait_ready_returns_when_prompt_appears() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let socket_path = temp_dir.path().join("wait.sock");
+
+    // The prompt only shows up after a second of startup noise
+    Command::new(interminai_bin())
+        .arg("start")
+        .args(emulator_args())
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .arg("--prompt-regex")
+        .arg(r"^my-prompt\$ $")
+        .arg("--")
+        .arg("bash")
+        .arg("-c")
+        .arg("echo loading; sleep 1; PS1='my-prompt$ ' exec bash --norc --noprofile -i")
+        .assert()
+        .success();
+
+    let start = std::time::Instant::now();
+    let output = Command::new(interminai_bin())
+        .arg("wait")
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .arg("--ready")
+        .timeout(Duration::from_secs(5))
+        .output()
+        .expect("Failed to run wait command");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(output.status.success(), "wait --ready failed: {}", String::from_utf8_lossy(&output.stderr));
+    assert!(start.elapsed() >= Duration::from_millis(800), "Returned before the prompt: {}", stdout);
+    assert!(stdout.contains("Ready, cursor: row 2, col 12"), "Got: {}", stdout);
+
+    Command::new(interminai_bin())
+        .arg("stop")
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_wait_ready_fails_if_program_exits_first() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let socket_path = temp_dir.path().join("wait.sock");
+
+    Command::new(interminai_bin())
+        .arg("start")
+        .args(emulator_args())
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .arg("--prompt-regex")
+        .arg(r"\$ $")
+        .arg("--")
+        .arg("bash")
+        .arg("-c")
+        .arg("sleep 0.3; exit 3")
+        .assert()
+        .success();
+    thread::sleep(Duration::from_millis(200));
+
+    let output = Command::new(interminai_bin())
+        .arg("wait")
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .arg("--ready")
+        .timeout(Duration::from_secs(5))
+        .output()
+        .expect("Failed to run wait command");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success(), "An exit should fail wait --ready");
+    assert!(stderr.contains("exited with code 3 before the prompt"), "Got: {}", stderr);
+
+    Command::new(interminai_bin())
+        .arg("stop")
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .assert()
+        .success();
+}
+
+#[test]
+fn test_wait_ready_requires_prompt_regex() {
+    let temp_dir = TempDir::new().expect("Failed to create temp dir");
+    let socket_path = temp_dir.path().join("wait.sock");
+
+    Command::new(interminai_bin())
+        .arg("start")
+        .args(emulator_args())
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .arg("--")
+        .arg("sleep")
+        .arg("30")
+        .assert()
+        .success();
+    thread::sleep(Duration::from_millis(200));
+
+    let output = Command::new(interminai_bin())
+        .arg("wait")
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .arg("--ready")
+        .timeout(Duration::from_secs(5))
+        .output()
+        .expect("Failed to run wait command");
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!output.status.success());
+    assert!(stderr.contains("--prompt-regex"), "Got: {}", stderr);
+
+    Command::new(interminai_bin())
+        .arg("stop")
+        .arg("--socket")
+        .arg(socket_path.to_str().unwrap())
+        .assert()
+        .success();
+}